@@ -1,22 +1,49 @@
 use iced::widget::{
-    button, column, container, horizontal_space, row, scrollable, text, text_input, Row, Space,
+    button, checkbox, column, container, horizontal_space, image, pick_list, radio, row, scrollable, text, text_editor, text_input, Row, Space,
 };
 use iced::{Alignment, Color, Element, Length, Padding, Settings, Theme, Application, Command, Font, Subscription};
 use iced::window;
 use iced::mouse;
 use iced::event::{self, Event};
 use iced::keyboard;
+use futures::future::{AbortHandle, Aborted};
 
 mod network;
 mod parser;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use network::HttpClient;
+use network::auth::{AuthChallenge, AuthScheme, Credentials};
+use network::blocker::{self, ContentBlocker};
+use network::download::{self, Download, PendingDownload};
+use network::har::HarReplay;
+use network::http_client::{HttpClientConfig, HttpClientError};
+use network::loader::{LoadedResource, ResourceKind, ResourceLoader};
+use network::cookies::{site_of, CookiePolicy};
+use network::dns::{self, DnsConfig, DohConfig};
+use network::privacy::{self, PrivacyMode};
+use network::protocol::{self, HttpProtocols};
+use network::proxy::{self, ProxyConfig};
+use network::request::Request;
+use network::response::RedirectHop;
+use network::retry::RetryAttempt;
+use network::stream::{Progress, ResponseStream};
+use network::tls::{CertificateError, SecurityInfo};
 use parser::{HtmlParser, HtmlRenderer, flatten_render_tree_with_body, StyledText};
+use parser::css_parser::CssParser;
+use parser::charset;
+use parser::dom::{Document, StylesheetRef};
+use parser::forms::{ControlKind, FormSet};
+use parser::media::media_matches;
 
 /// Résout une URL relative par rapport à une URL de base
 fn resolve_url(base_url: &str, href: &str) -> String {
     // Si l'URL est déjà absolue, la retourner telle quelle
-    if href.starts_with("http://") || href.starts_with("https://") {
+    if ["http://", "https://", "file://", "data:"].iter().any(|scheme| href.starts_with(scheme)) {
         return href.to_string();
     }
 
@@ -59,6 +86,39 @@ fn resolve_url(base_url: &str, href: &str) -> String {
     format!("{}{}{}{}", protocol, origin, parent_path, href)
 }
 
+/// Indique si une sous-ressource a été bloquée par une liste de filtres
+fn is_blocked(resource: &LoadedResource) -> bool {
+    matches!(resource.result, Err(HttpClientError::Blocked(_)))
+}
+
+/// Explication d'un certificat refusé, pour la page d'avertissement et le cadenas
+fn certificate_error_text(error: &CertificateError) -> &'static str {
+    match error {
+        CertificateError::Untrusted => "le certificat n'est pas délivré par une autorité de confiance",
+        CertificateError::Expired => "le certificat a expiré",
+        CertificateError::NotYetValid => "le certificat n'est pas encore valide (vérifiez l'horloge de l'ordinateur)",
+        CertificateError::NameMismatch => "le certificat a été émis pour un autre nom de domaine",
+        CertificateError::Revoked => "le certificat a été révoqué",
+        CertificateError::Invalid(_) => "le certificat est invalide",
+    }
+}
+
+/// Taille lisible d'un nombre d'octets (ex. "1.5 MB")
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 // Police avec support Unicode étendu (cross-platform)
 #[cfg(target_os = "windows")]
 const ICONS: Font = Font::with_name("Segoe UI Symbol");
@@ -108,11 +168,11 @@ enum LoadingState {
     Idle,
     Loading,
     Loaded,
-    Error(String),
+    Error(HttpClientError),
 }
 
 /// Représente un onglet du navigateur
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct Tab {
     id: usize,
     title: String,
@@ -121,6 +181,33 @@ struct Tab {
     content: Option<PageContent>,
     history: Vec<String>,
     history_index: usize,
+    // Onglet privé : client HTTP isolé (cookies et connexions séparés) et son chargeur
+    private_loader: Option<Arc<ResourceLoader>>,
+    // Navigation en cours : identifiant de la dernière navigation et moyen de l'annuler
+    navigation_id: u64,
+    load_handle: Option<AbortHandle>,
+    // Adresse HTTP à essayer si la version HTTPS d'un domaine saisi est injoignable
+    http_fallback: Option<String>,
+    // Requêtes bloquées par les listes de filtres depuis le début de la navigation
+    blocked_count: usize,
+    // Identifiants demandés par le site (réponse 401)
+    auth_prompt: Option<AuthPrompt>,
+    // Éditeurs multilignes des <textarea> de la page, par indice de contrôle
+    textareas: HashMap<usize, text_editor::Content>,
+}
+
+/// Boîte de dialogue d'identification HTTP (Basic ou Digest) affichée dans l'onglet
+#[derive(Debug, Clone)]
+struct AuthPrompt {
+    // URL protégée et défi envoyé par le serveur
+    url: String,
+    challenge: AuthChallenge,
+    username: String,
+    password: String,
+    // Les identifiants ont été envoyés, on attend la réponse
+    submitted: bool,
+    // Le serveur a refusé les identifiants précédents
+    rejected: bool,
 }
 
 /// Contenu d'une page web chargée avec styles CSS appliqués
@@ -129,6 +216,28 @@ struct PageContent {
     document_title: String,
     styled_content: Vec<StyledText>,
     body_styles: Option<parser::renderer::ComputedStyles>,
+    // URL finale (après redirections), sert de base pour les liens relatifs
+    url: String,
+    response_info: Option<ResponseInfo>,
+    // Sous-ressources chargées (onglet Network) et celles restant à charger
+    resources: Vec<LoadedResource>,
+    pending_resources: Vec<(String, ResourceKind)>,
+    // Éléments masqués par les règles cosmétiques (##sélecteur)
+    hidden_elements: usize,
+    // Formulaires de la page, avec les valeurs saisies
+    forms: FormSet,
+    // Images reçues, par URL, prêtes à être dessinées
+    images: HashMap<String, image::Handle>,
+}
+
+/// Métadonnées réseau de la réponse principale (affichées dans DevTools)
+#[derive(Debug, Clone)]
+struct ResponseInfo {
+    status: u16,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    redirects: Vec<RedirectHop>,
+    retries: Vec<RetryAttempt>,
 }
 
 impl Tab {
@@ -141,9 +250,41 @@ impl Tab {
             content: None,
             history: vec!["faga://newtab".to_string()],
             history_index: 0,
+            private_loader: None,
+            navigation_id: 0,
+            load_handle: None,
+            http_fallback: None,
+            blocked_count: 0,
+            auth_prompt: None,
+            textareas: HashMap::new(),
+        }
+    }
+
+    /// Recrée les éditeurs des <textarea> à partir des valeurs du formulaire
+    fn reset_textareas(&mut self) {
+        self.textareas = match &self.content {
+            Some(content) => content.forms.controls
+                .iter()
+                .enumerate()
+                .filter(|(_, control)| control.kind == ControlKind::TextArea)
+                .map(|(index, control)| (index, text_editor::Content::with_text(&control.value)))
+                .collect(),
+            None => HashMap::new(),
+        };
+    }
+
+    fn new_private(id: usize, loader: Arc<ResourceLoader>) -> Self {
+        Self {
+            title: "Private Tab".to_string(),
+            private_loader: Some(loader),
+            ..Self::new(id)
         }
     }
 
+    fn is_private(&self) -> bool {
+        self.private_loader.is_some()
+    }
+
     fn can_go_back(&self) -> bool {
         self.history_index > 0
     }
@@ -177,6 +318,159 @@ impl Tab {
         self.history_index = self.history.len() - 1;
         self.url = url.to_string();
     }
+
+    /// Annuler le chargement en cours, s'il y en a un
+    fn abort_load(&mut self) {
+        if let Some(handle) = self.load_handle.take() {
+            handle.abort();
+            log::debug!("🛑 Aborted navigation {} of tab {}", self.navigation_id, self.id);
+        }
+    }
+
+    /// Revenir à la page affichée quand la navigation aboutit à un téléchargement
+    fn cancel_navigation(&mut self) {
+        if self.history_index > 0 && self.history.get(self.history_index) == Some(&self.url) {
+            self.history.remove(self.history_index);
+            self.history_index -= 1;
+            self.url = self.history[self.history_index].clone();
+        }
+        self.loading_state = if self.content.is_some() {
+            LoadingState::Loaded
+        } else {
+            LoadingState::Idle
+        };
+    }
+}
+
+/// Résultat du chargement d'une URL : une page à afficher ou un fichier à télécharger
+#[derive(Debug, Clone)]
+enum LoadOutcome {
+    Page(Box<PageContent>),
+    Download(PendingDownload),
+}
+
+/// Intervalle minimal entre deux mises à jour de progression d'un téléchargement
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Un téléchargement lancé depuis un onglet
+#[derive(Debug, Clone)]
+struct DownloadItem {
+    id: usize,
+    tab_id: usize,
+    url: String,
+    // Réponse déjà ouverte dont le corps est écrit dans le fichier
+    pending: PendingDownload,
+    path: Option<PathBuf>,
+    progress: Progress,
+    state: DownloadState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DownloadState {
+    Running,
+    Finished,
+    Failed(String),
+}
+
+impl DownloadItem {
+    fn file_name(&self) -> String {
+        match &self.path {
+            Some(path) => path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            None => self.url.rsplit('/').find(|s| !s.is_empty()).unwrap_or(&self.url).to_string(),
+        }
+    }
+}
+
+/// Événements émis par la subscription d'un téléchargement
+#[derive(Debug, Clone)]
+enum DownloadEvent {
+    Started(PathBuf),
+    Progress(Progress),
+    Finished(Progress),
+    Failed(String),
+}
+
+/// État de la tâche de téléchargement pilotée par la subscription
+enum DownloadTask {
+    Pending(PendingDownload, PathBuf),
+    Running(Box<Download>),
+    Done,
+}
+
+/// Réglages réseau choisis dans faga://settings (enregistrés sur disque)
+#[derive(Debug, Clone, Default)]
+struct NetworkSettings {
+    proxy: Option<ProxyConfig>,
+    doh: Option<DohConfig>,
+    privacy: PrivacyMode,
+    protocols: HttpProtocols,
+    // Listes de filtres chargées depuis le dossier filters (non enregistrées)
+    blocker: Option<Arc<ContentBlocker>>,
+    // Archive HAR rejouée à la place du réseau (variable FAGA_HAR_REPLAY)
+    replay: Option<Arc<HarReplay>>,
+}
+
+impl NetworkSettings {
+    fn load() -> Self {
+        Self {
+            proxy: proxy::default_proxy_path().and_then(|path| proxy::load_proxy(&path)),
+            doh: dns::default_dns_path().and_then(|path| dns::load_doh(&path)),
+            privacy: privacy::default_privacy_path().map(|path| privacy::load_privacy_mode(&path)).unwrap_or_default(),
+            protocols: protocol::default_protocols_path().map(|path| protocol::load_protocols(&path)).unwrap_or_default(),
+            blocker: Self::load_filters(),
+            replay: Self::load_replay(),
+        }
+    }
+
+    /// Charger l'archive HAR désignée par FAGA_HAR_REPLAY, pour rejouer un chargement hors ligne
+    fn load_replay() -> Option<Arc<HarReplay>> {
+        let path = PathBuf::from(std::env::var_os("FAGA_HAR_REPLAY")?);
+        match HarReplay::load(&path) {
+            Ok(replay) if replay.is_empty() => {
+                log::warn!("⚠️ {} has no entries, loading from the network", path.display());
+                None
+            }
+            Ok(replay) => {
+                log::info!("📼 Replaying {} entries from {}", replay.len(), path.display());
+                Some(Arc::new(replay))
+            }
+            Err(e) => {
+                log::error!("❌ {}", e);
+                None
+            }
+        }
+    }
+
+    /// Charger les listes de filtres (*.txt) du dossier filters, s'il en contient
+    fn load_filters() -> Option<Arc<ContentBlocker>> {
+        let blocker = ContentBlocker::load_dir(&blocker::default_filters_dir()?);
+        (!blocker.lists().is_empty()).then(|| Arc::new(blocker))
+    }
+
+    fn save(&self) {
+        let results = [
+            proxy::default_proxy_path().map(|path| proxy::save_proxy(&path, self.proxy.as_ref())),
+            dns::default_dns_path().map(|path| dns::save_doh(&path, self.doh.as_ref())),
+            privacy::default_privacy_path().map(|path| privacy::save_privacy_mode(&path, self.privacy)),
+            protocol::default_protocols_path().map(|path| protocol::save_protocols(&path, self.protocols)),
+        ];
+        for error in results.into_iter().flatten().filter_map(Result::err) {
+            log::warn!("⚠️ Failed to save network settings: {}", error);
+        }
+    }
+
+    /// Configuration du client HTTP correspondante
+    fn client_config(&self) -> HttpClientConfig {
+        HttpClientConfig {
+            proxy: self.proxy.clone(),
+            dns: self.doh.clone().map_or(DnsConfig::System, DnsConfig::Doh),
+            privacy: self.privacy.profile(),
+            protocols: self.protocols,
+            blocker: self.blocker.clone(),
+            replay: self.replay.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 struct FagaBrowser {
@@ -184,15 +478,32 @@ struct FagaBrowser {
     active_tab: usize,
     url_input: String,
     next_tab_id: usize,
-    http_client: Option<HttpClient>,
+    // Client HTTP partagé par tous les onglets (pool de connexions + cookies),
+    // derrière la file de chargement des sous-ressources
+    loader: Option<Arc<ResourceLoader>>,
     // Drag state for tab reordering
     dragging_tab: Option<DragState>,
     // DevTools state
     dev_tools_open: bool,
+    // Panneau du certificat du site actif (cadenas)
+    certificate_viewer_open: bool,
     dev_tools_tab: DevToolsTab,
+    // Résultat du dernier export HAR (chemin ou erreur)
+    har_status: Option<String>,
     // Window size for viewport units (vw, vh)
     window_width: f32,
     window_height: f32,
+    // Téléchargements (fichiers non affichables)
+    downloads: Vec<DownloadItem>,
+    next_download_id: usize,
+    download_dir: PathBuf,
+    // Réglages réseau appliqués, champs de la page faga://settings et dernière erreur de réglage
+    network: NetworkSettings,
+    proxy_url_input: String,
+    proxy_bypass_input: String,
+    network_error: Option<String>,
+    doh_endpoint_input: String,
+    doh_fallback_input: bool,
 }
 
 /// État du drag d'un onglet
@@ -212,6 +523,7 @@ enum DevToolsTab {
     Styles,
     Console,
     Network,
+    Cookies,
 }
 
 impl Default for DevToolsTab {
@@ -226,9 +538,16 @@ enum Message {
     GoForward,
     Refresh,
     NewTab,
+    NewPrivateTab,
     CloseTab(usize),
     SelectTab(usize),
     OpenShortcut(String),
+    // Formulaires (index du contrôle dans FormSet::controls)
+    FormInput(usize, String),
+    FormEdit(usize, text_editor::Action),
+    FormToggle(usize, bool),
+    FormSelect(usize, String),
+    FormActivate(usize),
     // Window controls
     MinimizeWindow,
     MaximizeWindow,
@@ -247,8 +566,51 @@ enum Message {
     // DevTools
     ToggleDevTools,
     SelectDevToolsTab(DevToolsTab),
+    // Enregistrement des échanges réseau de l'onglet actif, puis export en fichier HAR
+    ToggleHarRecording,
+    ExportHar,
+    // Certificat du site : afficher, ou accepter malgré l'erreur (hôte, empreinte)
+    ToggleCertificateViewer,
+    TlsInspected(Result<(), HttpClientError>),
+    TrustCertificate(String, String),
+    RemoveCertificateException(String),
+    // Cookies (domaine, nom, chemin)
+    DeleteCookie(String, String, String),
+    ClearCookies,
+    SetCookiePolicy(String, Option<CookiePolicy>),
+    ToggleThirdPartyCookies,
     // Network events
-    PageLoaded(usize, Result<PageContent, String>),
+    // (onglet, navigation, résultat) : les résultats d'une navigation remplacée sont ignorés
+    PageLoaded(usize, u64, Result<Box<PageContent>, HttpClientError>),
+    LoadAborted(usize, u64),
+    ResourceLoaded(usize, u64, Box<LoadedResource>),
+    // Downloads (onglet, navigation, URL) puis (id du téléchargement, événement)
+    StartDownload(usize, u64, PendingDownload),
+    DownloadProgressed(usize, DownloadEvent),
+    DismissDownload(usize),
+    // Paramètres (faga://settings)
+    OpenSettings,
+    ProxyUrlChanged(String),
+    ProxyBypassChanged(String),
+    ApplyProxy,
+    UseTorProxy,
+    DisableProxy,
+    DohEndpointChanged(String),
+    ToggleDohFallback(bool),
+    ApplyDoh,
+    DisableDoh,
+    SetPrivacyMode(PrivacyMode),
+    SetProtocols(HttpProtocols),
+    ToggleHttpsOnly(bool),
+    // Continuer en HTTP sur ce site malgré le mode HTTPS uniquement (URL de la page)
+    AllowHttp(String),
+    RemoveHttpException(String),
+    ReloadFilters,
+    // Identification HTTP de l'onglet actif
+    AuthUsernameChanged(String),
+    AuthPasswordChanged(String),
+    SubmitAuth,
+    CancelAuth,
     LoadingStarted(usize),
 }
 
@@ -259,20 +621,16 @@ impl Application for FagaBrowser {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
-        let http_client = HttpClient::new().ok();
+        let network = NetworkSettings::load();
+        let loader = match HttpClient::with_config(network.client_config()) {
+            Ok(client) => Some(Arc::new(ResourceLoader::new(Arc::new(client)))),
+            Err(e) => {
+                log::error!("❌ Failed to create HTTP client: {}", e);
+                None
+            }
+        };
 
-        (FagaBrowser {
-            tabs: vec![Tab::new(0)],
-            active_tab: 0,
-            url_input: String::new(),
-            next_tab_id: 1,
-            http_client,
-            dragging_tab: None,
-            dev_tools_open: false,
-            dev_tools_tab: DevToolsTab::default(),
-            window_width: 1200.0,
-            window_height: 800.0,
-        }, Command::none())
+        (Self::with_network(network, loader), Command::none())
     }
 
     fn title(&self) -> String {
@@ -289,24 +647,47 @@ impl Application for FagaBrowser {
                 self.url_input = url;
             }
             Message::Navigate => {
-                let url = if self.url_input.starts_with("http://")
-                    || self.url_input.starts_with("https://")
-                {
-                    self.url_input.clone()
-                } else if self.url_input.starts_with("faga://") {
+                // Un chemin absolu saisi tel quel ouvre le fichier local
+                let local_path = std::path::Path::new(self.url_input.trim());
+                let file_url = local_path
+                    .is_absolute()
+                    .then(|| url::Url::from_file_path(local_path).ok())
+                    .flatten();
+
+                let typed_url = ["http://", "https://", "faga://", "file://", "data:"]
+                    .iter()
+                    .any(|scheme| self.url_input.starts_with(scheme));
+                let url = if typed_url {
                     self.url_input.clone()
+                } else if let Some(file_url) = &file_url {
+                    file_url.to_string()
                 } else if self.url_input.contains('.') {
                     format!("https://{}", self.url_input)
                 } else {
                     format!("https://www.google.com/search?q={}", self.url_input)
                 };
 
+                // Un domaine saisi sans schéma passe en HTTP si HTTPS ne répond pas ;
+                // en mode HTTPS uniquement, c'est la page d'avertissement qui le propose
+                let https_only = self.active_client().is_some_and(|c| c.https_only().is_enabled());
+                let typed_domain = !typed_url && file_url.is_none() && !self.url_input.contains("://") && self.url_input.contains('.');
+                let fallback = typed_domain.then(|| format!("http://{}", self.url_input));
+                let (url, fallback) = match fallback {
+                    Some(http) if https_only => (http, None),
+                    fallback => (url, fallback),
+                };
+
                 if let Some(tab) = self.tabs.get_mut(self.active_tab) {
                     tab.navigate_to(&url);
                     tab.loading_state = LoadingState::Loading;
                     let tab_id = tab.id;
                     log::info!("🌐 Navigating to: {}", url);
-                    return Self::load_page(tab_id, url, self.window_width, self.window_height);
+                    let command = self.start_load(tab_id, url);
+                    // Après start_load, qui oublie le repli de la navigation précédente
+                    if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                        tab.http_fallback = fallback;
+                    }
+                    return command;
                 }
             }
             Message::GoBack => {
@@ -324,7 +705,7 @@ impl Application for FagaBrowser {
                 if let Some((tab_id, url)) = result {
                     log::info!("⬅️ Going back to: {}", url);
                     self.url_input = url.clone();
-                    return Self::load_page(tab_id, url, self.window_width, self.window_height);
+                    return self.start_load(tab_id, url);
                 }
             }
             Message::GoForward => {
@@ -342,7 +723,7 @@ impl Application for FagaBrowser {
                 if let Some((tab_id, url)) = result {
                     log::info!("➡️ Going forward to: {}", url);
                     self.url_input = url.clone();
-                    return Self::load_page(tab_id, url, self.window_width, self.window_height);
+                    return self.start_load(tab_id, url);
                 }
             }
             Message::Refresh => {
@@ -356,7 +737,7 @@ impl Application for FagaBrowser {
 
                 if let Some((tab_id, url)) = result {
                     log::info!("🔄 Refreshing: {}", url);
-                    return Self::load_page(tab_id, url, self.window_width, self.window_height);
+                    return self.start_load(tab_id, url);
                 }
             }
             Message::NewTab => {
@@ -367,10 +748,28 @@ impl Application for FagaBrowser {
                 self.url_input.clear();
                 log::info!("➕ New tab created");
             }
+            Message::NewPrivateTab => {
+                let loader = match self.loader.as_ref().map(|l| l.client().isolated()) {
+                    Some(Ok(client)) => Arc::new(ResourceLoader::new(Arc::new(client))),
+                    Some(Err(e)) => {
+                        log::error!("❌ Failed to create private HTTP client: {}", e);
+                        return Command::none();
+                    }
+                    None => return Command::none(),
+                };
+                self.tabs.push(Tab::new_private(self.next_tab_id, loader));
+                self.active_tab = self.tabs.len() - 1;
+                self.next_tab_id += 1;
+                self.url_input.clear();
+                log::info!("🕶️ New private tab created");
+            }
             Message::CloseTab(id) => {
                 if self.tabs.len() > 1 {
                     if let Some(pos) = self.tabs.iter().position(|t| t.id == id) {
-                        self.tabs.remove(pos);
+                        let mut tab = self.tabs.remove(pos);
+                        tab.abort_load();
+                        // Dropping their subscriptions stops the tab's downloads
+                        self.downloads.retain(|d| d.tab_id != id || d.state != DownloadState::Running);
                         if self.active_tab >= self.tabs.len() {
                             self.active_tab = self.tabs.len() - 1;
                         }
@@ -410,9 +809,192 @@ impl Application for FagaBrowser {
                 if let Some(id) = tab_id {
                     self.url_input = resolved_url.clone();
                     log::info!("🔗 Opening link: {} (resolved from {})", resolved_url, url);
-                    return Self::load_page(id, resolved_url, self.window_width, self.window_height);
+                    return self.start_load(id, resolved_url);
+                }
+            }
+            Message::FormInput(control, value) => {
+                if let Some(content) = self.tabs.get_mut(self.active_tab).and_then(|t| t.content.as_mut()) {
+                    content.forms.set_value(control, value);
+                }
+            }
+            Message::FormEdit(control, action) => {
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    if let (Some(editor), Some(content)) = (tab.textareas.get_mut(&control), tab.content.as_mut()) {
+                        editor.perform(action);
+                        // Content::text() ajoute un saut de ligne final : on recolle les lignes telles quelles
+                        let value = editor.lines().map(|line| line.to_string()).collect::<Vec<_>>().join("\n");
+                        content.forms.set_value(control, value);
+                    }
+                }
+            }
+            Message::FormToggle(control, checked) => {
+                if let Some(content) = self.tabs.get_mut(self.active_tab).and_then(|t| t.content.as_mut()) {
+                    content.forms.set_checked(control, checked);
+                }
+            }
+            Message::FormSelect(control, value) => {
+                if let Some(content) = self.tabs.get_mut(self.active_tab).and_then(|t| t.content.as_mut()) {
+                    content.forms.select(control, &value);
+                }
+            }
+            Message::FormActivate(control) => {
+                let tab = match self.tabs.get_mut(self.active_tab) {
+                    Some(tab) => tab,
+                    None => return Command::none(),
+                };
+                let content = match tab.content.as_mut() {
+                    Some(content) => content,
+                    None => return Command::none(),
+                };
+
+                // Un bouton de réinitialisation remet les valeurs d'origine, un simple bouton ne fait rien sans JavaScript
+                match content.forms.controls.get(control).map(|c| c.kind.clone()) {
+                    Some(ControlKind::Reset) => {
+                        if let Some(form) = content.forms.controls[control].form {
+                            content.forms.reset(form);
+                        }
+                        tab.reset_textareas();
+                        return Command::none();
+                    }
+                    Some(ControlKind::Button) | None => return Command::none(),
+                    Some(_) => {}
+                }
+
+                if let Some(request) = content.forms.submission(control) {
+                    tab.navigate_to(&request.url);
+                    tab.loading_state = LoadingState::Loading;
+                    let tab_id = tab.id;
+                    self.url_input = request.url.clone();
+                    log::info!("📝 Form submitted: {} {}", request.method, request.url);
+                    return self.start_request(tab_id, request);
+                }
+            }
+            Message::OpenSettings => {
+                let url = "faga://settings".to_string();
+                let tab_id = self.tabs.get_mut(self.active_tab).map(|tab| {
+                    tab.navigate_to(&url);
+                    tab.id
+                });
+                self.proxy_url_input = self.network.proxy.as_ref().map(|p| p.url.clone()).unwrap_or_default();
+                self.proxy_bypass_input = self.network.proxy.as_ref().map(|p| p.bypass.join(", ")).unwrap_or_default();
+                self.network_error = None;
+                if let Some(id) = tab_id {
+                    self.url_input = url.clone();
+                    return self.start_load(id, url);
+                }
+            }
+            Message::ProxyUrlChanged(url) => {
+                self.proxy_url_input = url;
+            }
+            Message::ProxyBypassChanged(rules) => {
+                self.proxy_bypass_input = rules;
+            }
+            Message::ApplyProxy => {
+                if self.proxy_url_input.trim().is_empty() {
+                    self.apply_network_settings(NetworkSettings { proxy: None, ..self.network.clone() });
+                } else {
+                    match ProxyConfig::new(&self.proxy_url_input) {
+                        Ok(config) => {
+                            let config = config.with_bypass(proxy::parse_bypass_list(&self.proxy_bypass_input));
+                            self.apply_network_settings(NetworkSettings { proxy: Some(config), ..self.network.clone() });
+                        }
+                        Err(e) => self.network_error = Some(e.to_string()),
+                    }
+                }
+            }
+            Message::UseTorProxy => {
+                let tor = ProxyConfig::tor();
+                self.proxy_url_input = tor.url.clone();
+                self.proxy_bypass_input = tor.bypass.join(", ");
+                self.apply_network_settings(NetworkSettings { proxy: Some(tor), ..self.network.clone() });
+            }
+            Message::DisableProxy => {
+                self.proxy_url_input.clear();
+                self.proxy_bypass_input.clear();
+                self.apply_network_settings(NetworkSettings { proxy: None, ..self.network.clone() });
+            }
+            Message::DohEndpointChanged(endpoint) => {
+                self.doh_endpoint_input = endpoint;
+            }
+            Message::ToggleDohFallback(fallback) => {
+                self.doh_fallback_input = fallback;
+            }
+            Message::ApplyDoh => {
+                let doh = DohConfig { endpoint: self.doh_endpoint_input.trim().to_string(), fallback: self.doh_fallback_input };
+                self.apply_network_settings(NetworkSettings { doh: Some(doh), ..self.network.clone() });
+            }
+            Message::DisableDoh => {
+                self.apply_network_settings(NetworkSettings { doh: None, ..self.network.clone() });
+            }
+            Message::ToggleHttpsOnly(enabled) => {
+                if let Some(client) = self.loader.as_ref().map(|l| l.client()) {
+                    client.https_only().set_enabled(enabled);
+                    log::info!("🔒 HTTPS-only mode {}", if enabled { "enabled" } else { "disabled" });
+                }
+                // Les onglets privés ont leur propre copie, jamais enregistrée
+                for loader in self.tabs.iter().filter_map(|t| t.private_loader.as_ref()) {
+                    loader.client().https_only().set_enabled(enabled);
+                }
+            }
+            Message::AllowHttp(url) => {
+                let host = url::Url::parse(&url).ok().and_then(|u| u.host_str().map(|h| h.to_string()));
+                if let (Some(host), Some(client)) = (host, self.active_client()) {
+                    client.https_only().allow_http(&host);
+                    return self.update(Message::Refresh);
+                }
+            }
+            Message::RemoveHttpException(host) => {
+                if let Some(client) = self.loader.as_ref().map(|l| l.client()) {
+                    client.https_only().remove_exception(&host);
+                }
+                for loader in self.tabs.iter().filter_map(|t| t.private_loader.as_ref()) {
+                    loader.client().https_only().remove_exception(&host);
+                }
+            }
+            Message::SetPrivacyMode(mode) => {
+                self.apply_network_settings(NetworkSettings { privacy: mode, ..self.network.clone() });
+            }
+            Message::SetProtocols(protocols) => {
+                self.apply_network_settings(NetworkSettings { protocols, ..self.network.clone() });
+            }
+            Message::AuthUsernameChanged(username) => {
+                if let Some(prompt) = self.tabs.get_mut(self.active_tab).and_then(|t| t.auth_prompt.as_mut()) {
+                    prompt.username = username;
+                }
+            }
+            Message::AuthPasswordChanged(password) => {
+                if let Some(prompt) = self.tabs.get_mut(self.active_tab).and_then(|t| t.auth_prompt.as_mut()) {
+                    prompt.password = password;
+                }
+            }
+            Message::SubmitAuth => {
+                let client = self.active_client();
+                if let (Some(tab), Some(client)) = (self.tabs.get_mut(self.active_tab), client) {
+                    if let Some(prompt) = tab.auth_prompt.as_mut() {
+                        if let Ok(url) = url::Url::parse(&prompt.url) {
+                            let credentials = Credentials {
+                                username: prompt.username.clone(),
+                                password: std::mem::take(&mut prompt.password),
+                            };
+                            client.auth().set_credentials(&url, &prompt.challenge, credentials);
+                            prompt.submitted = true;
+                            tab.loading_state = LoadingState::Loading;
+                            let (tab_id, url) = (tab.id, tab.url.clone());
+                            return self.start_load(tab_id, url);
+                        }
+                    }
                 }
             }
+            Message::CancelAuth => {
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    if tab.auth_prompt.take().is_some() {
+                        tab.loading_state = LoadingState::Error(HttpClientError::HttpStatus(401));
+                    }
+                }
+            }
+            Message::ReloadFilters => {
+                self.apply_network_settings(NetworkSettings { blocker: NetworkSettings::load_filters(), ..self.network.clone() });
+            }
             Message::MinimizeWindow => {
                 return window::minimize(window::Id::MAIN, true);
             }
@@ -422,26 +1004,152 @@ impl Application for FagaBrowser {
             Message::CloseWindow => {
                 return window::close(window::Id::MAIN);
             }
-            Message::PageLoaded(tab_id, result) => {
+            Message::PageLoaded(tab_id, navigation_id, result) => {
+                let active_tab_id = self.tabs.get(self.active_tab).map(|t| t.id);
+                let loader = self.loader_for_tab(tab_id);
                 if let Some(tab) = self.tabs.iter_mut().find(|t| t.id == tab_id) {
+                    if tab.navigation_id != navigation_id {
+                        log::debug!("⏭️ Ignoring stale load {} of tab {}", navigation_id, tab_id);
+                        return Command::none();
+                    }
+                    tab.load_handle = None;
+                    // Le repli ne sert qu'une fois, quel que soit le résultat
+                    let http_fallback = tab.http_fallback.take();
+                    if result.is_ok() {
+                        tab.auth_prompt = None;
+                    }
                     match result {
                         Ok(content) => {
+                            // Après une redirection, l'onglet affiche l'URL finale
+                            if content.url != tab.url {
+                                tab.url = content.url.clone();
+                                if let Some(entry) = tab.history.get_mut(tab.history_index) {
+                                    *entry = content.url.clone();
+                                }
+                                if active_tab_id == Some(tab_id) {
+                                    self.url_input = content.url.clone();
+                                }
+                            }
                             tab.title = if content.document_title.is_empty() {
                                 tab.url.replace("https://", "").replace("http://", "")
                             } else {
                                 content.document_title.clone()
                             };
+                            let mut content = *content;
+                            tab.blocked_count += content.resources.iter().filter(|r| is_blocked(r)).count();
+                            let pending = std::mem::take(&mut content.pending_resources);
                             tab.content = Some(content);
+                            tab.reset_textareas();
                             tab.loading_state = LoadingState::Loaded;
                             log::info!("✅ Page loaded successfully: {}", tab.url);
+
+                            // Charger les sous-ressources restantes (polices, scripts, images)
+                            if let (Some(loader), false) = (loader, pending.is_empty()) {
+                                let (command, handle) = Self::load_subresources(loader, tab_id, navigation_id, tab.url.clone(), pending);
+                                tab.load_handle = Some(handle);
+                                return command;
+                            }
+                        }
+                        // Seul un serveur injoignable en HTTPS justifie le repli : une erreur
+                        // TLS ou un certificat refusé mènent à la page d'erreur ou d'avertissement
+                        Err(error) if http_fallback.is_some()
+                            && matches!(error.last(), HttpClientError::ConnectionRefused(_) | HttpClientError::Timeout) => {
+                            let http = http_fallback.unwrap_or_default();
+                            log::warn!("⚠️ HTTPS unavailable for {}, falling back to {}", tab.url, http);
+                            if let Some(entry) = tab.history.get_mut(tab.history_index) {
+                                *entry = http.clone();
+                            }
+                            tab.url = http.clone();
+                            if active_tab_id == Some(tab_id) {
+                                self.url_input = http.clone();
+                            }
+                            return self.start_load(tab_id, http);
+                        }
+                        Err(HttpClientError::AuthRequired(url, challenge)) => {
+                            // Identifiants refusés si on venait d'en envoyer pour cette adresse
+                            let previous = tab.auth_prompt.take().filter(|p| p.url == url);
+                            let rejected = previous.as_ref().is_some_and(|p| p.submitted);
+                            log::info!("🔑 {} asks for credentials (realm \"{}\")", url, challenge.realm);
+                            tab.auth_prompt = Some(AuthPrompt {
+                                url: url.clone(),
+                                challenge: (*challenge).clone(),
+                                username: previous.map(|p| p.username).unwrap_or_default(),
+                                password: String::new(),
+                                submitted: false,
+                                rejected,
+                            });
+                            tab.loading_state = LoadingState::Error(HttpClientError::AuthRequired(url, challenge));
                         }
                         Err(error) => {
+                            if matches!(error, HttpClientError::Blocked(_)) {
+                                tab.blocked_count += 1;
+                            }
                             tab.loading_state = LoadingState::Error(error.clone());
                             log::error!("❌ Failed to load page: {}", error);
                         }
                     }
                 }
             }
+            Message::LoadAborted(tab_id, navigation_id) => {
+                log::debug!("🛑 Navigation {} of tab {} cancelled", navigation_id, tab_id);
+            }
+            Message::ResourceLoaded(tab_id, navigation_id, resource) => {
+                if let Some(tab) = self.tabs.iter_mut().find(|t| t.id == tab_id && t.navigation_id == navigation_id) {
+                    if is_blocked(&resource) {
+                        tab.blocked_count += 1;
+                    }
+                    if let Some(content) = tab.content.as_mut() {
+                        if let (ResourceKind::Image, Ok(response)) = (resource.kind, &resource.result) {
+                            if response.is_success() {
+                                content.images.insert(resource.url.clone(), image::Handle::from_memory(response.body.clone()));
+                            }
+                        }
+                        content.resources.push(*resource);
+                    }
+                }
+            }
+            Message::StartDownload(tab_id, navigation_id, pending) => {
+                let active_tab_id = self.tabs.get(self.active_tab).map(|t| t.id);
+                if let Some(tab) = self.tabs.iter_mut().find(|t| t.id == tab_id) {
+                    if tab.navigation_id != navigation_id {
+                        return Command::none();
+                    }
+                    tab.load_handle = None;
+                    tab.cancel_navigation();
+                    if active_tab_id == Some(tab_id) {
+                        self.url_input = if tab.url == "faga://newtab" { String::new() } else { tab.url.clone() };
+                    }
+                }
+                self.downloads.push(DownloadItem {
+                    id: self.next_download_id,
+                    tab_id,
+                    url: pending.url.clone(),
+                    pending,
+                    path: None,
+                    progress: Progress::default(),
+                    state: DownloadState::Running,
+                });
+                self.next_download_id += 1;
+            }
+            Message::DownloadProgressed(id, event) => {
+                if let Some(item) = self.downloads.iter_mut().find(|d| d.id == id) {
+                    match event {
+                        DownloadEvent::Started(path) => item.path = Some(path),
+                        DownloadEvent::Progress(progress) => item.progress = progress,
+                        DownloadEvent::Finished(progress) => {
+                            item.progress = progress;
+                            item.state = DownloadState::Finished;
+                        }
+                        DownloadEvent::Failed(error) => {
+                            log::error!("❌ Download failed: {}: {}", item.url, error);
+                            item.state = DownloadState::Failed(error);
+                        }
+                    }
+                }
+            }
+            Message::DismissDownload(id) => {
+                self.downloads.retain(|d| d.id != id);
+            }
             Message::LoadingStarted(tab_id) => {
                 if let Some(tab) = self.tabs.iter_mut().find(|t| t.id == tab_id) {
                     tab.loading_state = LoadingState::Loading;
@@ -557,10 +1265,89 @@ impl Application for FagaBrowser {
                 self.dev_tools_open = !self.dev_tools_open;
                 log::info!("🔧 DevTools {}", if self.dev_tools_open { "opened" } else { "closed" });
             }
+            Message::ToggleCertificateViewer => {
+                self.certificate_viewer_open = !self.certificate_viewer_open;
+                // Version TLS et suite de chiffrement lues sur une connexion à l'origine,
+                // par le proxy s'il y en a un
+                let url = self.tabs.get(self.active_tab).and_then(|tab| url::Url::parse(&tab.url).ok());
+                if let (true, Some(client), Some(url)) = (self.certificate_viewer_open, self.active_client(), url) {
+                    return Command::perform(async move { client.inspect_tls(&url).await }, Message::TlsInspected);
+                }
+            }
+            Message::TlsInspected(result) => {
+                if let Err(e) = result {
+                    log::warn!("⚠️ Could not read the TLS parameters: {}", e);
+                }
+            }
+            Message::TrustCertificate(host, fingerprint) => {
+                if let Some(client) = self.active_client() {
+                    client.certificates().trust(&host, &fingerprint);
+                    self.certificate_viewer_open = false;
+                    return self.update(Message::Refresh);
+                }
+            }
+            Message::RemoveCertificateException(host) => {
+                if let Some(client) = self.active_client() {
+                    client.certificates().remove_exception(&host);
+                    return self.update(Message::Refresh);
+                }
+            }
             Message::SelectDevToolsTab(tab) => {
                 self.dev_tools_tab = tab;
                 log::debug!("🔧 DevTools tab: {:?}", tab);
             }
+            Message::ToggleHarRecording => {
+                if let Some(client) = self.active_client() {
+                    let har = client.har();
+                    if har.is_recording() {
+                        har.stop();
+                    } else {
+                        har.clear();
+                        har.start();
+                        self.har_status = None;
+                    }
+                    log::info!("📼 HAR recording {}", if har.is_recording() { "started" } else { "stopped" });
+                }
+            }
+            Message::ExportHar => {
+                if let Some(client) = self.active_client() {
+                    let stamp = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs());
+                    let path = self.download_dir.join(format!("faga-{}.har", stamp));
+                    self.har_status = Some(match client.har().save(&path) {
+                        Ok(()) => format!("{} requête(s) exportée(s) dans {}", client.har().len(), path.display()),
+                        Err(e) => format!("Échec de l'export HAR : {}", e),
+                    });
+                }
+            }
+            Message::DeleteCookie(domain, name, path) => {
+                if let Some(client) = self.active_client() {
+                    client.cookie_jar().delete(&domain, &name, &path);
+                    log::info!("🍪 Deleted cookie {} for {}", name, domain);
+                }
+            }
+            Message::ClearCookies => {
+                if let Some(client) = self.active_client() {
+                    client.cookie_jar().clear();
+                    log::info!("🍪 Deleted every cookie");
+                }
+            }
+            Message::SetCookiePolicy(site, policy) => {
+                if let Some(client) = self.active_client() {
+                    match policy {
+                        Some(policy) => client.cookie_jar().set_site_policy(&site, policy),
+                        None => client.cookie_jar().remove_site_policy(&site),
+                    }
+                    log::info!("🍪 Cookie policy for {}: {:?}", site, policy);
+                }
+            }
+            Message::ToggleThirdPartyCookies => {
+                if let Some(client) = self.active_client() {
+                    let jar = client.cookie_jar();
+                    jar.set_block_third_party(!jar.blocks_third_party());
+                }
+            }
         }
         Command::none()
     }
@@ -591,7 +1378,13 @@ impl Application for FagaBrowser {
             content
         };
 
-        let main_content = column![tab_bar, nav_bar, page_area]
+        let certificate_viewer: Element<Message> = if self.certificate_viewer_open {
+            self.view_certificate_viewer()
+        } else {
+            Space::with_height(0).into()
+        };
+
+        let main_content = column![tab_bar, nav_bar, certificate_viewer, page_area, self.view_downloads_bar()]
             .spacing(0)
             .width(Length::Fill)
             .height(Length::Fill);
@@ -606,9 +1399,14 @@ impl Application for FagaBrowser {
         Theme::Light
     }
 
-    /// Subscription pour les événements clavier et souris
+    /// Subscription pour les événements clavier et souris, et les téléchargements en cours
     fn subscription(&self) -> Subscription<Message> {
-        event::listen_with(|event, _status| {
+        let downloads = self.downloads
+            .iter()
+            .filter(|d| d.state == DownloadState::Running)
+            .map(|d| Self::download_subscription(d.id, d.pending.clone(), self.download_dir.clone()));
+
+        let events = event::listen_with(|event, _status| {
             match event {
                 // Window resize
                 Event::Window(_, window::Event::Resized { width, height }) => {
@@ -623,6 +1421,10 @@ impl Application for FagaBrowser {
                     if modifiers.control() && modifiers.shift() && c.as_str() == "i" {
                         return Some(Message::ToggleDevTools);
                     }
+                    // CTRL+SHIFT+N pour un onglet privé
+                    if modifiers.control() && modifiers.shift() && c.as_str().eq_ignore_ascii_case("n") {
+                        return Some(Message::NewPrivateTab);
+                    }
                     // F12 aussi pour ouvrir DevTools
                     None
                 }
@@ -647,84 +1449,355 @@ impl Application for FagaBrowser {
                 }
                 _ => None,
             }
-        })
+        });
+
+        Subscription::batch(std::iter::once(events).chain(downloads))
     }
 }
 
 impl FagaBrowser {
+    /// Navigateur avec un onglet vide, ces réglages réseau et ce chargeur
+    fn with_network(network: NetworkSettings, loader: Option<Arc<ResourceLoader>>) -> Self {
+        Self {
+            tabs: vec![Tab::new(0)],
+            active_tab: 0,
+            url_input: String::new(),
+            next_tab_id: 1,
+            loader,
+            dragging_tab: None,
+            dev_tools_open: false,
+            certificate_viewer_open: false,
+            dev_tools_tab: DevToolsTab::default(),
+            har_status: None,
+            window_width: 1200.0,
+            window_height: 800.0,
+            downloads: Vec::new(),
+            next_download_id: 0,
+            download_dir: download::default_download_dir().unwrap_or_else(|| PathBuf::from("Downloads")),
+            proxy_url_input: network.proxy.as_ref().map(|p| p.url.clone()).unwrap_or_default(),
+            proxy_bypass_input: network.proxy.as_ref().map(|p| p.bypass.join(", ")).unwrap_or_default(),
+            network_error: None,
+            doh_endpoint_input: network.doh.as_ref().map(|d| d.endpoint.clone()).unwrap_or_else(|| dns::DEFAULT_DOH_ENDPOINT.to_string()),
+            doh_fallback_input: network.doh.as_ref().is_some_and(|d| d.fallback),
+            network,
+        }
+    }
+
+    /// Télécharge un fichier en streaming, en émettant sa progression
+    fn download_subscription(id: usize, pending: PendingDownload, dir: PathBuf) -> Subscription<Message> {
+        iced::subscription::unfold(("download", id), DownloadTask::Pending(pending, dir), move |task| async move {
+            match task {
+                DownloadTask::Pending(pending, dir) => {
+                    let Some(stream) = pending.take() else {
+                        return (Message::DownloadProgressed(id, DownloadEvent::Failed("Download already started".to_string())), DownloadTask::Done);
+                    };
+                    match Download::start(stream, &dir).await {
+                        Ok(download) => (
+                            Message::DownloadProgressed(id, DownloadEvent::Started(download.path().to_path_buf())),
+                            DownloadTask::Running(Box::new(download)),
+                        ),
+                        Err(e) => (Message::DownloadProgressed(id, DownloadEvent::Failed(e.to_string())), DownloadTask::Done),
+                    }
+                }
+                DownloadTask::Running(mut download) => {
+                    // Regrouper les morceaux pour ne pas inonder l'interface de messages
+                    let started = Instant::now();
+                    loop {
+                        match download.advance().await {
+                            Ok(true) if started.elapsed() < DOWNLOAD_PROGRESS_INTERVAL => continue,
+                            Ok(true) => {
+                                let progress = download.progress();
+                                return (Message::DownloadProgressed(id, DownloadEvent::Progress(progress)), DownloadTask::Running(download));
+                            }
+                            Ok(false) => {
+                                return (Message::DownloadProgressed(id, DownloadEvent::Finished(download.progress())), DownloadTask::Done);
+                            }
+                            Err(e) => {
+                                return (Message::DownloadProgressed(id, DownloadEvent::Failed(e.to_string())), DownloadTask::Done);
+                            }
+                        }
+                    }
+                }
+                DownloadTask::Done => futures::future::pending().await,
+            }
+        })
+    }
+
+    /// Chargeur (et donc client HTTP) d'un onglet, isolé pour les onglets privés
+    fn loader_for_tab(&self, tab_id: usize) -> Option<Arc<ResourceLoader>> {
+        self.tabs
+            .iter()
+            .find(|t| t.id == tab_id)
+            .and_then(|t| t.private_loader.clone())
+            .or_else(|| self.loader.clone())
+    }
+
+    /// Remplacer le client HTTP par un client utilisant ces réglages réseau
+    ///
+    /// Les onglets privés reçoivent aussi un nouveau client isolé, pour que
+    /// plus aucune requête ne passe par l'ancienne route.
+    fn apply_network_settings(&mut self, settings: NetworkSettings) {
+        // Seul le transport change : cookies, cache, identifiants, exceptions
+        // de certificats et enregistrement HAR de la session sont conservés
+        let client = match &self.loader {
+            Some(loader) => loader.client().reconfigured(settings.client_config()),
+            None => HttpClient::with_config(settings.client_config()),
+        };
+        let client = match client {
+            Ok(client) => Arc::new(client),
+            Err(e) => {
+                log::error!("❌ Failed to apply network settings: {}", e);
+                self.network_error = Some(e.to_string());
+                return;
+            }
+        };
+
+        for tab in self.tabs.iter_mut().filter(|t| t.is_private()) {
+            let private = match &tab.private_loader {
+                Some(loader) => loader.client().reconfigured(settings.client_config()),
+                None => client.isolated(),
+            };
+            match private {
+                Ok(private) => tab.private_loader = Some(Arc::new(ResourceLoader::new(Arc::new(private)))),
+                Err(e) => log::error!("❌ Failed to create private HTTP client: {}", e),
+            }
+        }
+        self.loader = Some(Arc::new(ResourceLoader::new(client)));
+
+        settings.save();
+        match &settings.proxy {
+            Some(p) => log::info!("🧅 Proxy enabled: {}", p),
+            None => log::info!("🌐 Proxy disabled, connecting directly"),
+        }
+        match &settings.doh {
+            Some(d) => log::info!("🔒 DNS-over-HTTPS enabled: {}", d.endpoint),
+            None => log::info!("🌐 Using the system DNS resolver"),
+        }
+        log::info!("🕵️ Privacy profile: {:?}", settings.privacy);
+        self.network = settings;
+        self.network_error = None;
+    }
+
+    /// Hôte et certificat de l'onglet actif, pour les pages HTTPS
+    fn active_security(&self) -> Option<(String, Option<SecurityInfo>)> {
+        let tab = self.tabs.get(self.active_tab)?;
+        let url = url::Url::parse(&tab.url).ok().filter(|u| u.scheme() == "https")?;
+        let host = url.host_str()?.to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let info = self.active_client().and_then(|client| client.certificates().security_info(&host, port));
+        Some((host, info))
+    }
+
+    /// Client HTTP à utiliser pour un onglet (isolé pour les onglets privés)
+    fn client_for_tab(&self, tab_id: usize) -> Option<Arc<HttpClient>> {
+        self.loader_for_tab(tab_id).map(|loader| loader.client().clone())
+    }
+
+    /// Client HTTP de l'onglet actif
+    fn active_client(&self) -> Option<Arc<HttpClient>> {
+        self.tabs.get(self.active_tab).and_then(|tab| self.client_for_tab(tab.id))
+    }
+
+    /// Start loading a page in a tab, aborting the load it replaces
+    fn start_load(&mut self, tab_id: usize, url: String) -> Command<Message> {
+        self.start_request(tab_id, Request::get(&url))
+    }
+
+    /// Démarre le chargement d'une requête quelconque (ex. envoi de formulaire)
+    fn start_request(&mut self, tab_id: usize, request: Request) -> Command<Message> {
+        let loader = self.loader_for_tab(tab_id);
+        let (viewport_width, viewport_height) = (self.window_width, self.window_height);
+        let tab = match self.tabs.iter_mut().find(|t| t.id == tab_id) {
+            Some(tab) => tab,
+            None => return Command::none(),
+        };
+
+        tab.abort_load();
+        tab.navigation_id += 1;
+        tab.blocked_count = 0;
+        tab.http_fallback = None;
+        let navigation_id = tab.navigation_id;
+
+        let (load, handle) = futures::future::abortable(Self::load_page(loader, request, viewport_width, viewport_height));
+        tab.load_handle = Some(handle);
+
+        Command::perform(load, move |result| match result {
+            Ok(Ok(LoadOutcome::Page(content))) => Message::PageLoaded(tab_id, navigation_id, Ok(content)),
+            Ok(Ok(LoadOutcome::Download(pending))) => Message::StartDownload(tab_id, navigation_id, pending),
+            Ok(Err(e)) => Message::PageLoaded(tab_id, navigation_id, Err(e)),
+            Err(Aborted) => Message::LoadAborted(tab_id, navigation_id),
+        })
+    }
+
     /// Load a page asynchronously (static method to avoid borrow issues)
-    fn load_page(tab_id: usize, url: String, viewport_width: f32, viewport_height: f32) -> Command<Message> {
+    async fn load_page(loader: Option<Arc<ResourceLoader>>, request: Request, viewport_width: f32, viewport_height: f32) -> Result<LoadOutcome, HttpClientError> {
+        let url = request.url.clone();
+
         // Handle internal URLs
         if url.starts_with("faga://") {
-            return Command::perform(
-                async move { Ok(PageContent {
-                    document_title: "New Tab".to_string(),
-                    styled_content: Vec::new(),
-                    body_styles: None,
-                }) },
-                move |result| Message::PageLoaded(tab_id, result),
-            );
+            let title = if url == "faga://settings" { "Settings" } else { "New Tab" };
+            return Ok(LoadOutcome::Page(Box::new(PageContent {
+                document_title: title.to_string(),
+                styled_content: Vec::new(),
+                body_styles: None,
+                url,
+                response_info: None,
+                resources: Vec::new(),
+                pending_resources: Vec::new(),
+                hidden_elements: 0,
+                forms: FormSet::default(),
+                images: HashMap::new(),
+            })));
         }
 
         // Perform HTTP request and render with CSS
-        Command::perform(
-            async move {
-                let client = HttpClient::new()
-                    .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let loader = loader
+            .ok_or_else(|| HttpClientError::ClientBuildError("HTTP client unavailable".to_string()))?;
 
-                let response = client.get(&url).await
-                    .map_err(|e| format!("Request failed: {}", e))?;
+        // Les envois de formulaire en POST sont lus d'un bloc
+        let stream = if request.method == "GET" {
+            loader.client().open(request).await?
+        } else {
+            ResponseStream::buffered(loader.client().execute(request).await?)
+        };
 
-                if !response.is_success() {
-                    return Err(format!("HTTP Error: {}", response.status));
-                }
+        if !stream.head.is_success() {
+            return Err(HttpClientError::HttpStatus(stream.head.status));
+        }
 
-                // Parse HTML
-                let document = HtmlParser::parse(&response.body, &url)
-                    .map_err(|e| format!("HTML parsing failed: {}", e))?;
+        // Les fichiers non affichables partent dans le dossier de téléchargement
+        if download::is_download(&stream.head) {
+            log::info!("⬇️ Not a page, downloading instead: {}", stream.head.url);
+            return Ok(LoadOutcome::Download(PendingDownload::new(stream)));
+        }
 
-                // Create renderer with default CSS and viewport dimensions
-                let mut renderer = HtmlRenderer::new()
-                    .with_viewport(viewport_width, viewport_height);
+        let response = stream.collect().await?;
 
-                // Add page stylesheets (inline CSS from <style> tags)
-                for stylesheet in &document.stylesheets {
-                    if stylesheet.starts_with("inline:") {
-                        let css = &stylesheet[7..]; // Remove "inline:" prefix
-                        log::info!("🎨 Adding inline CSS: {}...", &css[..css.len().min(50)]);
-                        renderer.add_stylesheet(css);
-                    }
-                }
+        if !response.redirects.is_empty() {
+            log::info!("↪️ {} redirect(s): {} → {}", response.redirects.len(), url, response.url);
+        }
 
-                // Render the document to get styled content with body styles
-                let rendered = if let Some(render_tree) = renderer.render(&document) {
-                    flatten_render_tree_with_body(&render_tree)
-                } else {
-                    parser::renderer::RenderedContent {
-                        styled_content: Vec::new(),
-                        body_styles: None,
-                    }
-                };
+        // Parse HTML (relative URLs resolve against the final URL)
+        let mut document = HtmlParser::parse_bytes(response.body_bytes(), Some(&response.content_type), &response.url)
+            .map_err(|e| HttpClientError::ResponseReadError(format!("HTML parsing failed: {}", e)))?;
 
-                // Log body styles for debugging
-                if let Some(ref body) = rendered.body_styles {
-                    log::info!("📐 Body styles: margin-top={}px, width={:?}, width_percent={:?}, margin_auto={}/{}",
-                        body.margin_top,
-                        body.width,
-                        body.width_percent,
-                        body.margin_left_auto,
-                        body.margin_right_auto
-                    );
+        // Element hiding rules remove their matches before anything is rendered
+        let hidden_elements = match (loader.client().blocker(), url::Url::parse(&response.url)) {
+            (Some(blocker), Ok(page_url)) => blocker.hide_elements(&mut document, &page_url),
+            _ => 0,
+        };
+
+        // The renderer numbers form controls in the same document order
+        let forms = FormSet::from_document(&document);
+
+        // Create renderer with default CSS and viewport dimensions
+        let mut renderer = HtmlRenderer::new()
+            .with_viewport(viewport_width, viewport_height);
+
+        // Add page stylesheets (<style> and <link rel="stylesheet">) in document order
+        let (stylesheets, resources) = Self::load_stylesheets(&loader, &document, &response.url, viewport_width, viewport_height).await;
+        let mut pending_resources = Vec::new();
+        for (base, css) in &stylesheets {
+            renderer.add_stylesheet(css);
+            for font in CssParser::font_face_urls(css) {
+                pending_resources.push((resolve_url(base, &font), ResourceKind::Font));
+            }
+        }
+
+        // Scripts and images are fetched once the page is displayed
+        pending_resources.extend(document.scripts.iter().map(|url| (url.clone(), ResourceKind::Script)));
+        pending_resources.extend(document.images.iter().map(|url| (url.clone(), ResourceKind::Image)));
+
+        // Render the document to get styled content with body styles
+        let rendered = if let Some(render_tree) = renderer.render(&document) {
+            flatten_render_tree_with_body(&render_tree)
+        } else {
+            parser::renderer::RenderedContent {
+                styled_content: Vec::new(),
+                body_styles: None,
+            }
+        };
+
+        // Log body styles for debugging
+        if let Some(ref body) = rendered.body_styles {
+            log::info!("📐 Body styles: margin-top={}px, width={:?}, width_percent={:?}, margin_auto={}/{}",
+                body.margin_top,
+                body.width,
+                body.width_percent,
+                body.margin_left_auto,
+                body.margin_right_auto
+            );
+        }
+
+        Ok(LoadOutcome::Page(Box::new(PageContent {
+            document_title: document.title,
+            styled_content: rendered.styled_content,
+            body_styles: rendered.body_styles,
+            url: response.url.clone(),
+            response_info: Some(ResponseInfo {
+                status: response.status,
+                http_version: response.http_version.clone(),
+                headers: response.headers.clone(),
+                redirects: response.redirects.clone(),
+                retries: response.retries.clone(),
+            }),
+            resources,
+            pending_resources,
+            hidden_elements,
+            forms,
+            images: HashMap::new(),
+        })))
+    }
+
+    /// Collect the CSS of the page's stylesheets whose media matches the viewport
+    ///
+    /// External sheets go through the loader concurrently; the result keeps
+    /// document order and pairs each sheet with the URL its own links resolve
+    /// against. Fetch records are returned for the DevTools Network tab.
+    async fn load_stylesheets(loader: &Arc<ResourceLoader>, document: &Document, page_url: &str, viewport_width: f32, viewport_height: f32) -> (Vec<(String, String)>, Vec<LoadedResource>) {
+        let applicable = document.stylesheets
+            .iter()
+            .filter(|sheet| media_matches(sheet.media().unwrap_or(""), viewport_width, viewport_height));
+
+        let sheets = applicable.map(|sheet| async move {
+            match sheet {
+                StylesheetRef::Inline { css, .. } => {
+                    log::info!("🎨 Adding inline CSS: {}...", css.chars().take(50).collect::<String>());
+                    (Some((page_url.to_string(), css.clone())), None)
+                }
+                StylesheetRef::External { url, .. } => {
+                    let loaded = loader.fetch(url, ResourceKind::Stylesheet, page_url).await;
+                    let css = match &loaded.result {
+                        Ok(response) if response.is_success() => {
+                            let encoding = charset::detect_css_encoding(&response.body, Some(&response.content_type));
+                            log::info!("🎨 Loaded stylesheet {} ({} bytes, {})", url, response.body.len(), encoding.name());
+                            Some((response.url.clone(), charset::decode(&response.body, encoding)))
+                        }
+                        Ok(response) => {
+                            log::warn!("⚠️ Stylesheet {} returned HTTP {}", url, response.status);
+                            None
+                        }
+                        Err(_) => None,
+                    };
+                    (css, Some(loaded))
                 }
+            }
+        });
 
-                Ok(PageContent {
-                    document_title: document.title,
-                    styled_content: rendered.styled_content,
-                    body_styles: rendered.body_styles,
-                })
-            },
-            move |result| Message::PageLoaded(tab_id, result),
-        )
+        let (sheets, records): (Vec<_>, Vec<_>) = futures::future::join_all(sheets).await.into_iter().unzip();
+        (sheets.into_iter().flatten().collect(), records.into_iter().flatten().collect())
+    }
+
+    /// Fetch the page's remaining subresources, delivering each one as it arrives
+    fn load_subresources(loader: Arc<ResourceLoader>, tab_id: usize, navigation_id: u64, page_url: String, resources: Vec<(String, ResourceKind)>) -> (Command<Message>, AbortHandle) {
+        let fetches: futures::stream::FuturesUnordered<_> = resources
+            .iter()
+            .map(|(url, kind)| loader.fetch(url, *kind, &page_url))
+            .collect();
+        let (stream, handle) = futures::stream::abortable(fetches);
+
+        let command = Command::run(stream, move |loaded| Message::ResourceLoaded(tab_id, navigation_id, Box::new(loaded)));
+        (command, handle)
     }
 
     fn view_tab_bar(&self) -> Element<Message> {
@@ -737,12 +1810,13 @@ impl FagaBrowser {
         for (index, tab) in self.tabs.iter().enumerate() {
             let is_active = index == self.active_tab;
 
-            // Titre de l'onglet
-            let tab_title = text(if tab.title.len() > 18 {
+            // Titre de l'onglet (préfixé pour les onglets privés)
+            let title = if tab.title.len() > 18 {
                 format!("{}...", &tab.title[..15])
             } else {
                 tab.title.clone()
-            })
+            };
+            let tab_title = text(if tab.is_private() { format!("🕶 {}", title) } else { title })
                 .size(TEXT_SIZE_SMALL);
 
             // Bouton fermer
@@ -751,9 +1825,27 @@ impl FagaBrowser {
                 .padding(Padding::from([2, 6]))
                 .style(iced::theme::Button::Custom(Box::new(TabCloseButtonStyle)));
 
+            // Indicateur de téléchargement en cours
+            let running: Vec<&DownloadItem> = self.downloads
+                .iter()
+                .filter(|d| d.tab_id == tab.id && d.state == DownloadState::Running)
+                .collect();
+            let download_indicator = if running.is_empty() {
+                text("")
+            } else {
+                let received: u64 = running.iter().map(|d| d.progress.received).sum();
+                let total: Option<u64> = running.iter().map(|d| d.progress.total).sum();
+                let label = match (Progress { received, total }).fraction() {
+                    Some(fraction) => format!("⬇{}%", (fraction * 100.0) as u32),
+                    None => "⬇".to_string(),
+                };
+                text(label).size(TEXT_SIZE_SMALL).style(Color::from_rgb(0.2, 0.4, 0.8))
+            };
+
             let tab_inner = row![
                 tab_title,
                 horizontal_space(),
+                download_indicator,
                 close_btn
             ]
                 .spacing(4)
@@ -902,6 +1994,25 @@ impl FagaBrowser {
             .padding(0)
             .style(iced::theme::Button::Custom(Box::new(NavButtonStyle)));
 
+        // Cadenas : état de la connexion, ouvre le détail du certificat
+        let (padlock, padlock_color) = match self.active_security() {
+            Some((_, Some(info))) if info.overridden => ("⚠", Color::from_rgb(0.8, 0.3, 0.1)),
+            Some((_, Some(info))) if info.error.is_some() => ("🔓", Color::from_rgb(0.8, 0.2, 0.2)),
+            Some(_) => ("🔒", Color::from_rgb(0.2, 0.5, 0.2)),
+            None if self.tabs.get(self.active_tab).is_some_and(|t| t.url.starts_with("http://")) => ("🔓", Color::from_rgb(0.5, 0.5, 0.5)),
+            None => ("ⓘ", Color::from_rgb(0.5, 0.5, 0.5)),
+        };
+        let padlock_btn = button(
+            container(text(padlock).size(16).style(padlock_color))
+                .width(Length::Fixed(MIN_TOUCH_TARGET))
+                .height(Length::Fixed(36.0))
+                .center_x()
+                .center_y()
+        )
+            .on_press(Message::ToggleCertificateViewer)
+            .padding(0)
+            .style(iced::theme::Button::Custom(Box::new(NavButtonStyle)));
+
         // URL bar - hauteur suffisante pour accessibilité
         let url_bar = text_input("Search FAGA or type a URL", &self.url_input)
             .on_input(Message::UrlInputChanged)
@@ -914,7 +2025,7 @@ impl FagaBrowser {
             .width(Length::Fill)
             .style(iced::theme::Container::Custom(Box::new(UrlBarStyle)));
 
-        // Menu button
+        // Menu button (paramètres)
         let menu_btn = button(
                 container(text("⋮").font(ICONS).size(20))
                 .width(Length::Fixed(MIN_TOUCH_TARGET))
@@ -922,16 +2033,27 @@ impl FagaBrowser {
                 .center_x()
                 .center_y()
         )
-            .on_press(Message::Refresh)
+            .on_press(Message::OpenSettings)
             .padding(0)
             .style(iced::theme::Button::Custom(Box::new(NavButtonStyle)));
 
+        // Compteur de requêtes bloquées de l'onglet actif
+        let blocked = self.tabs.get(self.active_tab).map_or(0, |t| t.blocked_count);
+        let blocked_badge = if blocked > 0 {
+            container(text(format!("🛡 {}", blocked)).size(12).style(Color::from_rgb(0.2, 0.5, 0.2)))
+                .padding(Padding::from([0, 6]))
+        } else {
+            container(Space::with_width(0))
+        };
+
         let nav_row = row![
             back_btn,
             forward_btn,
             refresh_btn,
             Space::with_width(8),
+            padlock_btn,
             url_container,
+            blocked_badge,
             Space::with_width(8),
             menu_btn,
         ]
@@ -952,6 +2074,9 @@ impl FagaBrowser {
             Some(tab) if tab.url == "faga://newtab" => {
                 self.view_new_tab_page()
             }
+            Some(tab) if tab.url == "faga://settings" => {
+                self.view_settings_page()
+            }
             Some(tab) => {
                 match &tab.loading_state {
                     LoadingState::Loading => {
@@ -972,33 +2097,14 @@ impl FagaBrowser {
                         .center_y()
                         .into()
                     }
-                    LoadingState::Error(error) => {
-                        // Afficher l'erreur
-                        container(
-                            column![
-                                text("⚠").font(ICONS).size(48).style(Color::from_rgb(0.9, 0.3, 0.3)),
-                                Space::with_height(16),
-                                text("Failed to load page").size(20),
-                                text(error).size(14).style(Color::from_rgb(0.5, 0.5, 0.5)),
-                                Space::with_height(16),
-                                button(text("Retry").size(14))
-                                    .on_press(Message::Refresh)
-                                    .padding(Padding::from([10, 20]))
-                                    .style(iced::theme::Button::Primary),
-                            ]
-                            .spacing(8)
-                            .align_items(Alignment::Center)
-                        )
-                        .width(Length::Fill)
-                        .height(Length::Fill)
-                        .center_x()
-                        .center_y()
-                        .into()
-                    }
+                    LoadingState::Error(error) => match &tab.auth_prompt {
+                        Some(prompt) if matches!(error, HttpClientError::AuthRequired(..)) => self.view_auth_dialog(prompt),
+                        _ => self.view_error_page(error, &tab.url),
+                    },
                     LoadingState::Loaded => {
                         // Afficher le contenu stylisé avec le CSS par défaut appliqué
                         if let Some(content) = &tab.content {
-                            self.render_styled_content(content, &tab.url)
+                            self.render_styled_content(content, &tab.textareas, &tab.url)
                         } else {
                             container(text("No content"))
                                 .width(Length::Fill)
@@ -1033,7 +2139,7 @@ impl FagaBrowser {
     }
 
     /// Render styled content using the parsed CSS styles
-    fn render_styled_content(&self, content: &PageContent, _url: &str) -> Element<Message> {
+    fn render_styled_content<'a>(&'a self, content: &'a PageContent, textareas: &'a HashMap<usize, text_editor::Content>, _url: &str) -> Element<'a, Message> {
         // Build the content column with styled text
         let mut content_column = column![].spacing(2).width(Length::Fill);
 
@@ -1061,6 +2167,26 @@ impl FagaBrowser {
                     );
                 }
                 line_margin_top = 0.0;
+            } else if let Some(control) = styled.control {
+                let size = (styled.styles.font_size as u16).clamp(10, 72);
+                if let Some(element) = Self::view_form_control(&content.forms, textareas, control, size) {
+                    current_line.push(element);
+                }
+            } else if let Some(ref src) = styled.image {
+                // L'image une fois reçue, son texte alternatif avant (ou si elle manque)
+                let element: Element<Message> = match content.images.get(&HtmlParser::resolve_url(src, &content.url)) {
+                    Some(handle) => image(handle.clone()).into(),
+                    None if !styled.text.is_empty() => text(&styled.text).size(12).style(Color::from_rgb(0.5, 0.5, 0.5)).into(),
+                    None => continue,
+                };
+                current_line.push(match styled.href {
+                    Some(ref href) => button(element)
+                        .on_press(Message::OpenShortcut(href.clone()))
+                        .padding(0)
+                        .style(iced::theme::Button::Custom(Box::new(LinkButtonStyle)))
+                        .into(),
+                    None => element,
+                });
             } else {
                 if styled.text.trim().is_empty() {
                     continue;
@@ -1174,9 +2300,83 @@ impl FagaBrowser {
                 color: body_bg
             })));
 
-        scrollable(outer_container)
-            .height(Length::Fill)
-            .into()
+        scrollable(outer_container)
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Contrôle de formulaire interactif (champ, case, liste, bouton)
+    fn view_form_control<'a>(forms: &'a FormSet, textareas: &'a HashMap<usize, text_editor::Content>, index: usize, size: u16) -> Option<Element<'a, Message>> {
+        let control = forms.controls.get(index)?;
+        let enabled = !control.disabled;
+
+        let element: Element<Message> = match &control.kind {
+            ControlKind::Hidden => return None,
+            // Plusieurs lignes : Entrée y insère un saut de ligne au lieu d'envoyer le formulaire
+            ControlKind::TextArea => {
+                let mut editor = text_editor(textareas.get(&index)?)
+                    .height(Length::Fixed(f32::from(size) * 5.0))
+                    .padding(Padding::from([4, 6]));
+                if enabled {
+                    editor = editor.on_action(move |action| Message::FormEdit(index, action));
+                }
+                container(editor).width(Length::Fixed(360.0)).into()
+            }
+            ControlKind::Text { .. } | ControlKind::Password => {
+                let mut input = text_input(&control.placeholder, &control.value)
+                    .size(size)
+                    .padding(Padding::from([4, 6]))
+                    .secure(control.kind == ControlKind::Password)
+                    .width(Length::Fixed(220.0));
+                if enabled {
+                    input = input
+                        .on_input(move |value| Message::FormInput(index, value))
+                        .on_submit(Message::FormActivate(index));
+                }
+                input.into()
+            }
+            ControlKind::Checkbox => {
+                let mut check = checkbox("", control.checked).size(size);
+                if enabled {
+                    check = check.on_toggle(move |checked| Message::FormToggle(index, checked));
+                }
+                check.into()
+            }
+            ControlKind::Radio => {
+                // radio() appelle toujours son callback : un bouton désactivé renvoie son état actuel
+                let checked = control.checked;
+                radio("", index, checked.then_some(index), move |_| Message::FormToggle(index, enabled || checked))
+                    .size(size as f32)
+                    .into()
+            }
+            ControlKind::Select { multiple } => {
+                let selected = control.options.iter().find(|o| o.selected).cloned();
+                let placeholder = control.options
+                    .iter()
+                    .filter(|o| o.selected)
+                    .map(|o| o.label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                pick_list(control.options.clone(), selected.filter(|_| !multiple), move |option| Message::FormSelect(index, option.value))
+                    .placeholder(placeholder)
+                    .text_size(size)
+                    .into()
+            }
+            ControlKind::File => text("📎 Envoi de fichiers non pris en charge")
+                .size(size)
+                .style(Color::from_rgb8(120, 120, 120))
+                .into(),
+            ControlKind::Submit | ControlKind::Image | ControlKind::Reset | ControlKind::Button => {
+                let label = if control.label.is_empty() { "Envoyer" } else { control.label.as_str() };
+                button(text(label).size(size))
+                    .on_press_maybe(enabled.then_some(Message::FormActivate(index)))
+                    .padding(Padding::from([4, 12]))
+                    .style(iced::theme::Button::Secondary)
+                    .into()
+            }
+        };
+
+        Some(container(element).padding(Padding::from([2, 4])).into())
     }
 
     /// Affiche le panneau DevTools (comme Chrome DevTools)
@@ -1202,6 +2402,7 @@ impl FagaBrowser {
             tab_button("Styles", DevToolsTab::Styles, self.dev_tools_tab),
             tab_button("Console", DevToolsTab::Console, self.dev_tools_tab),
             tab_button("Network", DevToolsTab::Network, self.dev_tools_tab),
+            tab_button("Cookies", DevToolsTab::Cookies, self.dev_tools_tab),
             horizontal_space(),
             button(text("×").size(14))
                 .on_press(Message::ToggleDevTools)
@@ -1218,6 +2419,7 @@ impl FagaBrowser {
             DevToolsTab::Styles => self.view_dev_tools_styles(),
             DevToolsTab::Console => self.view_dev_tools_console(),
             DevToolsTab::Network => self.view_dev_tools_network(),
+            DevToolsTab::Cookies => self.view_dev_tools_cookies(),
         };
 
         let dev_tools_panel = column![
@@ -1460,6 +2662,38 @@ impl FagaBrowser {
                 .size(13)
                 .style(Color::from_rgb(0.4, 0.2, 0.4))
         );
+
+        // Enregistrement HAR du client de l'onglet actif
+        if let Some(client) = self.active_client() {
+            let har = client.har();
+            let (label, status) = if har.is_recording() {
+                ("⏹ Arrêter", format!("📼 Enregistrement en cours : {} requête(s)", har.len()))
+            } else {
+                ("⏺ Enregistrer", format!("📼 {} requête(s) enregistrée(s)", har.len()))
+            };
+            let mut export = button(text("💾 Exporter HAR").size(10))
+                .padding(Padding::from([2, 8]))
+                .style(iced::theme::Button::Secondary);
+            if !har.is_empty() {
+                export = export.on_press(Message::ExportHar);
+            }
+            content = content.push(
+                row![
+                    text(status).size(10).style(Color::from_rgb(0.4, 0.4, 0.4)),
+                    horizontal_space(),
+                    button(text(label).size(10))
+                        .on_press(Message::ToggleHarRecording)
+                        .padding(Padding::from([2, 8]))
+                        .style(iced::theme::Button::Secondary),
+                    export,
+                ]
+                .spacing(6)
+                .align_items(Alignment::Center)
+            );
+            if let Some(ref har_status) = self.har_status {
+                content = content.push(text(har_status).size(10).style(Color::from_rgb(0.3, 0.3, 0.6)));
+            }
+        }
         content = content.push(Space::with_height(8));
 
         if let Some(tab) = self.tabs.get(self.active_tab) {
@@ -1484,6 +2718,105 @@ impl FagaBrowser {
                     .style(status_color)
             );
 
+            // Tentatives relancées avant l'échec final
+            if let LoadingState::Error(error) = &tab.loading_state {
+                for retry in error.retries() {
+                    content = content.push(
+                        text(format!("🔁 Tentative {} : {} (nouvel essai après {} ms)", retry.attempt, retry.outcome, retry.delay.as_millis()))
+                            .size(10)
+                            .style(Color::from_rgb(0.7, 0.4, 0.1))
+                    );
+                }
+            }
+
+            if let Some(info) = tab.content.as_ref().and_then(|c| c.response_info.as_ref()) {
+                content = content.push(
+                    text(format!("{} {}", info.http_version, info.status))
+                        .size(10)
+                        .style(Color::from_rgb(0.3, 0.3, 0.3))
+                );
+
+                // Tentatives échouées puis relancées
+                for retry in &info.retries {
+                    content = content.push(
+                        text(format!("🔁 Tentative {} : {} (nouvel essai après {} ms)", retry.attempt, retry.outcome, retry.delay.as_millis()))
+                            .size(10)
+                            .style(Color::from_rgb(0.7, 0.4, 0.1))
+                    );
+                }
+
+                // Chaîne de redirections
+                for hop in &info.redirects {
+                    content = content.push(
+                        text(format!("↪ {} {}", hop.status, hop.url))
+                            .size(10)
+                            .style(Color::from_rgb(0.6, 0.4, 0.1))
+                    );
+                }
+
+                // En-têtes de réponse
+                content = content.push(Space::with_height(8));
+                content = content.push(
+                    text("📨 En-têtes de réponse:")
+                        .size(11)
+                        .style(Color::from_rgb(0.4, 0.4, 0.4))
+                );
+                for (name, value) in &info.headers {
+                    content = content.push(
+                        row![
+                            text(format!("{}:", name)).size(10).style(Color::from_rgb(0.5, 0.2, 0.5)),
+                            text(value).size(10).style(Color::from_rgb(0.3, 0.3, 0.3)),
+                        ]
+                        .spacing(6)
+                    );
+                }
+            }
+
+            // Bloqueur de contenu
+            let hidden = tab.content.as_ref().map_or(0, |c| c.hidden_elements);
+            if tab.blocked_count > 0 || hidden > 0 {
+                content = content.push(
+                    text(format!("🛡 {} requête(s) bloquée(s), {} élément(s) masqué(s)", tab.blocked_count, hidden))
+                        .size(10)
+                        .style(Color::from_rgb(0.2, 0.5, 0.2))
+                );
+            }
+
+            // Sous-ressources de la page
+            if let Some(page) = tab.content.as_ref().filter(|c| !c.resources.is_empty()) {
+                content = content.push(Space::with_height(12));
+                content = content.push(
+                    text(format!("📦 Sous-ressources ({}):", page.resources.len()))
+                        .size(11)
+                        .style(Color::from_rgb(0.4, 0.4, 0.4))
+                );
+
+                for resource in &page.resources {
+                    let (status, size, color) = match &resource.result {
+                        Ok(response) => (
+                            response.status.to_string(),
+                            format_bytes(response.body.len() as u64),
+                            if response.is_success() { Color::from_rgb(0.2, 0.5, 0.2) } else { Color::from_rgb(0.7, 0.4, 0.1) },
+                        ),
+                        Err(error) => (error.to_string(), String::new(), Color::from_rgb(0.7, 0.2, 0.2)),
+                    };
+                    content = content.push(
+                        row![
+                            text(resource.kind.label()).size(10).style(Color::from_rgb(0.5, 0.2, 0.5)),
+                            text(status).size(10).style(color),
+                            text(size).size(10).style(Color::from_rgb(0.4, 0.4, 0.4)),
+                            text(format!(
+                                "attente {} ms · {} ms",
+                                resource.queued.as_millis(),
+                                resource.elapsed.as_millis()
+                            )).size(10).style(Color::from_rgb(0.5, 0.5, 0.5)),
+                            text(&resource.url).size(10).style(Color::from_rgb(0.3, 0.3, 0.6)),
+                        ]
+                        .spacing(8)
+                    );
+                }
+            }
+
             // Historique de navigation
             if !tab.history.is_empty() {
                 content = content.push(Space::with_height(12));
@@ -1515,6 +2848,458 @@ impl FagaBrowser {
             .into()
     }
 
+    /// Barre des téléchargements de l'onglet actif
+    fn view_downloads_bar(&self) -> Element<'_, Message> {
+        let tab_id = self.tabs.get(self.active_tab).map(|t| t.id);
+        let items: Vec<&DownloadItem> = self.downloads.iter().filter(|d| Some(d.tab_id) == tab_id).collect();
+        if items.is_empty() {
+            return Space::with_height(0).into();
+        }
+
+        let mut bar = Row::new().spacing(12).padding(Padding::from([4, 8])).align_items(Alignment::Center);
+        for item in items {
+            let (status, color) = match &item.state {
+                DownloadState::Running => {
+                    let size = match item.progress.total {
+                        Some(total) => format!("{} / {}", format_bytes(item.progress.received), format_bytes(total)),
+                        None => format_bytes(item.progress.received),
+                    };
+                    match item.progress.fraction() {
+                        Some(fraction) => (format!("{}% · {}", (fraction * 100.0) as u32, size), Color::from_rgb(0.2, 0.4, 0.8)),
+                        None => (size, Color::from_rgb(0.2, 0.4, 0.8)),
+                    }
+                }
+                DownloadState::Finished => (
+                    item.path.as_ref().and_then(|p| p.parent()).map(|dir| format!("✓ {}", dir.display())).unwrap_or_default(),
+                    Color::from_rgb(0.2, 0.6, 0.2),
+                ),
+                DownloadState::Failed(error) => (format!("✗ {}", error), Color::from_rgb(0.7, 0.2, 0.2)),
+            };
+
+            bar = bar.push(
+                row![
+                    text(format!("⬇ {}", item.file_name())).size(TEXT_SIZE_SMALL),
+                    text(status).size(10).style(color),
+                    button(text("×").size(12))
+                        .on_press(Message::DismissDownload(item.id))
+                        .padding(Padding::from([0, 6]))
+                        .style(iced::theme::Button::Custom(Box::new(TabCloseButtonStyle))),
+                ]
+                .spacing(6)
+                .align_items(Alignment::Center)
+            );
+        }
+
+        container(scrollable(bar).direction(scrollable::Direction::Horizontal(scrollable::Properties::default())))
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Page d'erreur adaptée au type d'échec, avec une action suggérée
+    fn view_error_page<'a>(&self, error: &'a HttpClientError, url: &'a str) -> Element<'a, Message> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_else(|| url.to_string());
+
+        let (title, explanation, suggestion) = match error.last() {
+            HttpClientError::DnsFailure(_) => (
+                "Site inaccessible".to_string(),
+                format!("L'adresse de {} est introuvable.", host),
+                "Vérifiez l'orthographe de l'adresse ou votre connexion Internet.",
+            ),
+            HttpClientError::TlsError(_) => (
+                "Connexion non sécurisée".to_string(),
+                format!("{} présente un certificat ou une configuration TLS invalide.", host),
+                "Ne saisissez aucune information sensible sur ce site. Réessayez plus tard ou contactez son administrateur.",
+            ),
+            HttpClientError::CertificateRejected(rejected_host, certificate_error) => (
+                "Attention : risque probable de sécurité".to_string(),
+                format!("{} : {}", rejected_host, certificate_error_text(certificate_error)),
+                "Quelqu'un essaie peut-être de se faire passer pour ce site afin d'intercepter vos informations. \
+                 Ne continuez que si vous savez pourquoi ce certificat est refusé.",
+            ),
+            HttpClientError::Timeout => (
+                "Délai de connexion dépassé".to_string(),
+                format!("{} a mis trop de temps à répondre.", host),
+                "Le site est peut-être surchargé. Réessayez dans quelques instants.",
+            ),
+            HttpClientError::ConnectionRefused(_) => (
+                "Connexion refusée".to_string(),
+                format!("{} a refusé la connexion.", host),
+                "Le serveur est peut-être arrêté. Vérifiez l'adresse et le port, puis réessayez.",
+            ),
+            HttpClientError::NetworkError(_) => (
+                "Erreur réseau".to_string(),
+                format!("Impossible de joindre {}.", host),
+                "Vérifiez votre connexion Internet, votre proxy ou votre pare-feu.",
+            ),
+            HttpClientError::HttpsUpgradeFailed(_) => (
+                "Connexion sécurisée indisponible".to_string(),
+                format!("{} ne prend pas en charge HTTPS.", host),
+                "Le mode HTTPS uniquement est activé. En continuant, la page sera chargée sans chiffrement : \
+                 un intermédiaire pourra lire et modifier son contenu.",
+            ),
+            HttpClientError::ProxyFailed(_) => (
+                "Proxy injoignable".to_string(),
+                format!("Le proxy n'a pas pu joindre {}.", host),
+                "Vérifiez que le proxy (ou Tor) est lancé et que son adresse est correcte dans faga://settings.",
+            ),
+            HttpClientError::Blocked(_) => (
+                "Page bloquée".to_string(),
+                format!("{} figure dans une liste de filtres.", host),
+                "Retirez la règle de la liste concernée dans le dossier des filtres, puis rechargez les listes dans faga://settings.",
+            ),
+            HttpClientError::NotInArchive(_) => (
+                "Absent de l'archive".to_string(),
+                format!("L'archive HAR rejouée ne contient pas de réponse pour {}.", url),
+                "Enregistrez de nouveau le chargement de cette page, ou lancez le navigateur sans FAGA_HAR_REPLAY.",
+            ),
+            HttpClientError::TooManyRedirects(_) => (
+                "Trop de redirections".to_string(),
+                format!("{} redirige en boucle.", host),
+                "Essayez de supprimer les cookies de ce site dans les DevTools.",
+            ),
+            HttpClientError::HttpStatus(status @ 404) | HttpClientError::HttpStatus(status @ 410) => (
+                format!("Page introuvable ({})", status),
+                format!("{} n'a pas trouvé cette page.", host),
+                "Vérifiez l'adresse ou revenez à la page précédente.",
+            ),
+            HttpClientError::HttpStatus(status @ (401 | 403)) => (
+                format!("Accès refusé ({})", status),
+                format!("{} n'autorise pas l'accès à cette page.", host),
+                "Connectez-vous au site ou revenez à la page précédente.",
+            ),
+            HttpClientError::HttpStatus(status) if *status >= 500 => (
+                format!("Erreur du serveur ({})", status),
+                format!("{} a rencontré un problème.", host),
+                "Le problème vient du site. Réessayez plus tard.",
+            ),
+            HttpClientError::HttpStatus(status) => (
+                format!("Erreur HTTP {}", status),
+                format!("{} a rejeté la requête.", host),
+                "Vérifiez l'adresse ou revenez à la page précédente.",
+            ),
+            _ => (
+                "Impossible de charger la page".to_string(),
+                format!("Le chargement de {} a échoué.", host),
+                "Réessayez ou vérifiez l'adresse.",
+            ),
+        };
+
+        let can_go_back = self.tabs
+            .get(self.active_tab)
+            .is_some_and(|tab| tab.can_go_back());
+
+        let mut actions = row![
+            button(text("Retry").size(14))
+                .on_press(Message::Refresh)
+                .padding(Padding::from([10, 20]))
+                .style(iced::theme::Button::Primary),
+        ]
+        .spacing(8);
+        if can_go_back {
+            actions = actions.push(
+                button(text("Back").size(14))
+                    .on_press(Message::GoBack)
+                    .padding(Padding::from([10, 20]))
+                    .style(iced::theme::Button::Secondary),
+            );
+        }
+        if let HttpClientError::CertificateRejected(rejected_host, _) = error {
+            actions = actions.push(
+                button(text("Voir le certificat").size(14))
+                    .on_press(Message::ToggleCertificateViewer)
+                    .padding(Padding::from([10, 20]))
+                    .style(iced::theme::Button::Secondary),
+            );
+            let port = url::Url::parse(url).ok().and_then(|u| u.port_or_known_default()).unwrap_or(443);
+            let fingerprint = self.active_client()
+                .and_then(|client| client.certificates().security_info(rejected_host, port))
+                .and_then(|info| info.chain.first().map(|cert| cert.fingerprint.clone()));
+            if let Some(fingerprint) = fingerprint {
+                actions = actions.push(
+                    button(text("Accepter le risque et continuer").size(14))
+                        .on_press(Message::TrustCertificate(rejected_host.clone(), fingerprint))
+                        .padding(Padding::from([10, 20]))
+                        .style(iced::theme::Button::Destructive),
+                );
+            }
+        }
+        if let HttpClientError::HttpsUpgradeFailed(http_url) = error {
+            actions = actions.push(
+                button(text("Continuer en HTTP").size(14))
+                    .on_press(Message::AllowHttp(http_url.clone()))
+                    .padding(Padding::from([10, 20]))
+                    .style(iced::theme::Button::Destructive),
+            );
+        }
+
+        container(
+            column![
+                text("⚠").font(ICONS).size(48).style(Color::from_rgb(0.9, 0.3, 0.3)),
+                Space::with_height(16),
+                text(title).size(20),
+                text(explanation).size(14),
+                text(suggestion).size(13).style(Color::from_rgb(0.4, 0.4, 0.4)),
+                text(error.to_string()).size(11).style(Color::from_rgb(0.6, 0.6, 0.6)),
+                Space::with_height(16),
+                actions,
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center)
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+    }
+
+    /// Panneau du cadenas : protocole, chiffrement et chaîne de certificats du site actif
+    fn view_certificate_viewer(&self) -> Element<'_, Message> {
+        let grey = Color::from_rgb(0.4, 0.4, 0.4);
+        let mut panel = column![].spacing(6);
+
+        match self.active_security() {
+            None => {
+                panel = panel.push(text("Cette page n'utilise pas de connexion chiffrée.").size(TEXT_SIZE_SMALL));
+            }
+            Some((host, None)) => {
+                panel = panel.push(text(format!("Aucune connexion sécurisée n'a encore été établie avec {}.", host)).size(TEXT_SIZE_SMALL));
+            }
+            Some((host, Some(info))) => {
+                let status = match &info.error {
+                    Some(error) if info.overridden => format!("⚠ Certificat accepté manuellement : {}", certificate_error_text(error)),
+                    Some(error) => format!("🔓 Certificat refusé : {}", certificate_error_text(error)),
+                    None => "🔒 Connexion sécurisée".to_string(),
+                };
+                // Sans tunnel possible (proxy HTTPS, proxy injoignable), rien n'a pu être lu
+                let proxied = self.tabs.get(self.active_tab)
+                    .and_then(|tab| url::Url::parse(&tab.url).ok())
+                    .zip(self.active_client())
+                    .is_some_and(|(url, client)| client.is_proxied(&url));
+                let unknown = if proxied { "inconnu derrière le proxy" } else { "inconnu" };
+                panel = panel
+                    .push(text(format!("Connexion à {}", host)).size(14))
+                    .push(text(status).size(TEXT_SIZE_SMALL))
+                    .push(text(format!("Protocole : {}", info.protocol.as_deref().unwrap_or(unknown))).size(TEXT_SIZE_SMALL).style(grey))
+                    .push(text(format!("Chiffrement : {}", info.cipher.as_deref().unwrap_or(if proxied { unknown } else { "non communiqué" }))).size(TEXT_SIZE_SMALL).style(grey));
+                if info.overridden {
+                    panel = panel.push(
+                        button(text("Ne plus faire confiance à ce certificat").size(TEXT_SIZE_SMALL))
+                            .on_press(Message::RemoveCertificateException(host.clone()))
+                            .style(iced::theme::Button::Secondary),
+                    );
+                }
+
+                for (index, cert) in info.chain.iter().enumerate() {
+                    let mut details = column![
+                        text(if index == 0 { format!("Certificat du site : {}", cert.name()) } else { format!("Certificat intermédiaire : {}", cert.name()) }).size(13),
+                        text(format!("Sujet : {}", cert.subject)).size(TEXT_SIZE_SMALL).style(grey),
+                        text(format!("Émetteur : {}", cert.issuer)).size(TEXT_SIZE_SMALL).style(grey),
+                        text(format!(
+                            "Valide du {} au {}",
+                            httpdate::fmt_http_date(cert.not_before),
+                            httpdate::fmt_http_date(cert.not_after)
+                        )).size(TEXT_SIZE_SMALL).style(grey),
+                    ]
+                    .spacing(2);
+                    if !cert.alt_names.is_empty() {
+                        details = details.push(text(format!("Noms : {}", cert.alt_names.join(", "))).size(TEXT_SIZE_SMALL).style(grey));
+                    }
+                    details = details
+                        .push(text(format!("Numéro de série : {}", cert.serial)).size(TEXT_SIZE_SMALL).style(grey))
+                        .push(text(format!("Empreinte SHA-256 : {}", cert.fingerprint)).size(TEXT_SIZE_SMALL).style(grey));
+                    panel = panel.push(Space::with_height(4)).push(details);
+                }
+            }
+        }
+
+        container(scrollable(panel.padding(Padding::from([8, 16]))))
+            .width(Length::Fill)
+            .max_height(260.0)
+            .into()
+    }
+
+    /// Boîte de dialogue demandant les identifiants d'un site (HTTP 401)
+    fn view_auth_dialog<'a>(&self, prompt: &'a AuthPrompt) -> Element<'a, Message> {
+        let host = url::Url::parse(&prompt.url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_else(|| prompt.url.clone());
+        let insecure = prompt.url.starts_with("http://") && prompt.challenge.scheme == AuthScheme::Basic;
+
+        let mut dialog = column![
+            text("🔑").size(40),
+            text("Identification requise").size(20),
+            text(format!("{} demande un nom d'utilisateur et un mot de passe.", host)).size(14),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .max_width(420);
+
+        if !prompt.challenge.realm.is_empty() {
+            dialog = dialog.push(
+                text(format!("Le site indique : « {} »", prompt.challenge.realm)).size(12).style(Color::from_rgb(0.4, 0.4, 0.4))
+            );
+        }
+        if insecure {
+            dialog = dialog.push(
+                text("⚠ La connexion n'est pas chiffrée : le mot de passe sera envoyé en clair.")
+                    .size(12)
+                    .style(Color::from_rgb(0.7, 0.4, 0.1))
+            );
+        }
+        if prompt.rejected {
+            dialog = dialog.push(
+                text("Nom d'utilisateur ou mot de passe incorrect.").size(12).style(Color::from_rgb(0.7, 0.2, 0.2))
+            );
+        }
+
+        dialog = dialog
+            .push(Space::with_height(8))
+            .push(
+                text_input("Nom d'utilisateur", &prompt.username)
+                    .on_input(Message::AuthUsernameChanged)
+                    .on_submit(Message::SubmitAuth)
+                    .padding(Padding::from([8, 12]))
+                    .size(TEXT_SIZE_NORMAL),
+            )
+            .push(
+                text_input("Mot de passe", &prompt.password)
+                    .on_input(Message::AuthPasswordChanged)
+                    .on_submit(Message::SubmitAuth)
+                    .secure(true)
+                    .padding(Padding::from([8, 12]))
+                    .size(TEXT_SIZE_NORMAL),
+            )
+            .push(
+                row![
+                    button(text("Se connecter").size(14))
+                        .on_press(Message::SubmitAuth)
+                        .padding(Padding::from([10, 20]))
+                        .style(iced::theme::Button::Primary),
+                    button(text("Annuler").size(14))
+                        .on_press(Message::CancelAuth)
+                        .padding(Padding::from([10, 20]))
+                        .style(iced::theme::Button::Secondary),
+                ]
+                .spacing(8),
+            );
+
+        container(dialog)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into()
+    }
+
+    /// Onglet Cookies - cookies du site courant et règles par site
+    fn view_dev_tools_cookies(&self) -> Element<'_, Message> {
+        let mut content = column![].spacing(4).padding(8);
+
+        content = content.push(
+            text("🍪 Cookies")
+                .size(13)
+                .style(Color::from_rgb(0.5, 0.3, 0.1))
+        );
+        content = content.push(Space::with_height(8));
+
+        let client = self.active_client();
+        let host = self.tabs
+            .get(self.active_tab)
+            .and_then(|tab| url::Url::parse(&tab.url).ok())
+            .and_then(|url| url.host_str().map(|h| h.to_string()));
+
+        match (client, host) {
+            (Some(client), Some(host)) => {
+                let jar = client.cookie_jar();
+                let site = site_of(&host);
+                let policy = jar.site_policy(&host);
+
+                let third_party_label = if jar.blocks_third_party() {
+                    "Cookies tiers : bloqués"
+                } else {
+                    "Cookies tiers : autorisés"
+                };
+                let policy_label = match policy {
+                    Some(CookiePolicy::Allow) => format!("{} : toujours autorisé", site),
+                    Some(CookiePolicy::Block) => format!("{} : toujours bloqué", site),
+                    None => format!("{} : règle par défaut", site),
+                };
+
+                content = content.push(
+                    row![
+                        text(policy_label).size(11).style(Color::from_rgb(0.3, 0.3, 0.6)),
+                        horizontal_space(),
+                        button(text("Autoriser").size(10))
+                            .on_press(Message::SetCookiePolicy(site.clone(), Some(CookiePolicy::Allow)))
+                            .padding(Padding::from([2, 8]))
+                            .style(iced::theme::Button::Secondary),
+                        button(text("Bloquer").size(10))
+                            .on_press(Message::SetCookiePolicy(site.clone(), Some(CookiePolicy::Block)))
+                            .padding(Padding::from([2, 8]))
+                            .style(iced::theme::Button::Destructive),
+                        button(text("Par défaut").size(10))
+                            .on_press(Message::SetCookiePolicy(site.clone(), None))
+                            .padding(Padding::from([2, 8]))
+                            .style(iced::theme::Button::Text),
+                    ]
+                    .spacing(4)
+                    .align_items(Alignment::Center)
+                );
+                content = content.push(
+                    row![
+                        button(text(third_party_label).size(10))
+                            .on_press(Message::ToggleThirdPartyCookies)
+                            .padding(Padding::from([2, 8]))
+                            .style(iced::theme::Button::Secondary),
+                        horizontal_space(),
+                        button(text("Supprimer tous les cookies").size(10))
+                            .on_press(Message::ClearCookies)
+                            .padding(Padding::from([2, 8]))
+                            .style(iced::theme::Button::Destructive),
+                    ]
+                    .align_items(Alignment::Center)
+                );
+                content = content.push(Space::with_height(8));
+
+                let cookies = jar.list_for_domain(&site);
+                if cookies.is_empty() {
+                    content = content.push(text("Aucun cookie pour ce site").size(11));
+                }
+                for cookie in cookies {
+                    let expires = match cookie.expires {
+                        Some(_) => "persistant",
+                        None => "session",
+                    };
+                    content = content.push(
+                        row![
+                            text(format!("{}={}", cookie.name, cookie.value)).size(10).style(Color::from_rgb(0.2, 0.2, 0.2)),
+                            text(format!("{}{} ({})", cookie.domain, cookie.path, expires)).size(10).style(Color::from_rgb(0.5, 0.5, 0.5)),
+                            horizontal_space(),
+                            button(text("×").size(12))
+                                .on_press(Message::DeleteCookie(cookie.domain.clone(), cookie.name.clone(), cookie.path.clone()))
+                                .padding(Padding::from([0, 6]))
+                                .style(iced::theme::Button::Custom(Box::new(TabCloseButtonStyle))),
+                        ]
+                        .spacing(8)
+                        .align_items(Alignment::Center)
+                    );
+                }
+            }
+            _ => {
+                content = content.push(text("Chargez une page pour voir ses cookies").size(12));
+            }
+        }
+
+        scrollable(content)
+            .height(Length::Fill)
+            .into()
+    }
+
     fn view_new_tab_page(&self) -> Element<Message> {
         // Shortcuts section
         let shortcuts = self.view_shortcuts();
@@ -1540,6 +3325,177 @@ impl FagaBrowser {
         .into()
     }
 
+    fn view_settings_page(&self) -> Element<'_, Message> {
+        let status = match &self.network.proxy {
+            Some(proxy) => {
+                let dns = if proxy.kind().is_some_and(|k| k.remote_dns()) {
+                    "DNS résolu par le proxy"
+                } else {
+                    "DNS résolu localement"
+                };
+                text(format!("🧅 Proxy actif : {} ({})", proxy, dns)).style(Color::from_rgb(0.2, 0.5, 0.2))
+            }
+            None => text("Connexion directe (aucun proxy)").style(Color::from_rgb(0.5, 0.5, 0.5)),
+        };
+
+        let proxy_section = column![
+            text("Proxy").size(18),
+            status.size(12),
+            text("URL du proxy (http://, https://, socks5:// ou socks5h://)").size(12),
+            text_input("socks5h://127.0.0.1:9050", &self.proxy_url_input)
+                .on_input(Message::ProxyUrlChanged)
+                .on_submit(Message::ApplyProxy)
+                .padding(Padding::from([8, 12]))
+                .size(TEXT_SIZE_NORMAL),
+            text("Exceptions, séparées par des virgules (ex. <local>, example.com, *.lan, 10.0.0.0/8)").size(12),
+            text_input("<local>", &self.proxy_bypass_input)
+                .on_input(Message::ProxyBypassChanged)
+                .on_submit(Message::ApplyProxy)
+                .padding(Padding::from([8, 12]))
+                .size(TEXT_SIZE_NORMAL),
+            row![
+                button(text("Appliquer").size(12)).on_press(Message::ApplyProxy),
+                button(text("Utiliser Tor").size(12)).on_press(Message::UseTorProxy),
+                button(text("Désactiver").size(12)).on_press(Message::DisableProxy),
+            ]
+            .spacing(8),
+        ]
+        .spacing(8)
+        .max_width(640);
+
+        let dns_status = match &self.network.doh {
+            Some(doh) => text(format!("🔒 DNS-over-HTTPS actif : {}", doh.endpoint)).style(Color::from_rgb(0.2, 0.5, 0.2)),
+            None => text("Résolveur DNS du système (les noms visités sont visibles par le FAI)").style(Color::from_rgb(0.5, 0.5, 0.5)),
+        };
+        let dns_section = column![
+            text("DNS").size(18),
+            dns_status.size(12),
+            text("Serveur DNS-over-HTTPS (RFC 8484)").size(12),
+            text_input(dns::DEFAULT_DOH_ENDPOINT, &self.doh_endpoint_input)
+                .on_input(Message::DohEndpointChanged)
+                .on_submit(Message::ApplyDoh)
+                .padding(Padding::from([8, 12]))
+                .size(TEXT_SIZE_NORMAL),
+            checkbox("Utiliser le DNS du système si le serveur est injoignable", self.doh_fallback_input)
+                .on_toggle(Message::ToggleDohFallback)
+                .size(16)
+                .text_size(12),
+            row![
+                button(text("Activer DoH").size(12)).on_press(Message::ApplyDoh),
+                button(text("Désactiver").size(12)).on_press(Message::DisableDoh),
+            ]
+            .spacing(8),
+        ]
+        .spacing(8)
+        .max_width(640);
+
+        let profile = self.network.privacy.profile();
+        let mode_button = |label: &'static str, mode: PrivacyMode| {
+            let label = if self.network.privacy == mode { format!("● {}", label) } else { label.to_string() };
+            button(text(label).size(12)).on_press(Message::SetPrivacyMode(mode))
+        };
+        let privacy_section = column![
+            text("Empreinte").size(18),
+            row![
+                mode_button("Uniforme", PrivacyMode::Uniform),
+                mode_button("Honnête", PrivacyMode::Honest),
+            ]
+            .spacing(8),
+            text(match self.network.privacy {
+                PrivacyMode::Uniform => "Tous les utilisateurs envoient les mêmes en-têtes, dans le même ordre.",
+                PrivacyMode::Honest => "Le vrai système et les langues du système sont annoncés aux sites.",
+            }).size(12),
+            text(format!("User-Agent : {}", profile.user_agent)).size(12).style(Color::from_rgb(0.4, 0.4, 0.4)),
+            text(format!("Accept-Language : {}", profile.accept_language)).size(12).style(Color::from_rgb(0.4, 0.4, 0.4)),
+            text("Referer : origine seule entre sites différents").size(12).style(Color::from_rgb(0.4, 0.4, 0.4)),
+        ]
+        .spacing(8)
+        .max_width(640);
+
+        let protocols_section = column![
+            text("Protocoles HTTP").size(18),
+            Row::with_children(HttpProtocols::choices().into_iter().map(|choice| {
+                let label = if self.network.protocols == choice { format!("● {}", choice.name()) } else { choice.name().to_string() };
+                button(text(label).size(12)).on_press(Message::SetProtocols(choice)).into()
+            }))
+            .spacing(8),
+            text("HTTP/2 est proposé aux serveurs HTTPS par ALPN ; HTTP/1.1 reste utilisé avec les serveurs qui ne le gèrent pas.").size(12),
+        ]
+        .spacing(8)
+        .max_width(640);
+
+        let https_only = self.loader.as_ref().map(|l| l.client().https_only());
+        let mut https_section = column![
+            text("HTTPS").size(18),
+            checkbox("Mode HTTPS uniquement", https_only.is_some_and(|p| p.is_enabled()))
+                .on_toggle(Message::ToggleHttpsOnly)
+                .size(16)
+                .text_size(12),
+            text("Les adresses http:// sont chargées en HTTPS ; un avertissement s'affiche si le site ne le permet pas.").size(12),
+        ]
+        .spacing(8)
+        .max_width(640);
+        let exceptions = https_only.map(|p| p.exceptions()).unwrap_or_default();
+        if !exceptions.is_empty() {
+            https_section = https_section.push(text("Sites autorisés en HTTP :").size(12));
+        }
+        for host in exceptions {
+            https_section = https_section.push(
+                row![
+                    text(&host).size(12).width(Length::Fill),
+                    button(text("Retirer").size(11)).on_press(Message::RemoveHttpException(host.clone())),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center)
+            );
+        }
+
+        let filters_dir = blocker::default_filters_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let mut blocker_section = column![
+            text("Bloqueur de contenu").size(18),
+            text(format!("Listes au format Adblock Plus / EasyList (*.txt) chargées depuis {}", filters_dir)).size(12),
+        ]
+        .spacing(8)
+        .max_width(640);
+        match &self.network.blocker {
+            Some(blocker) => {
+                let (network_rules, cosmetic_rules) = blocker.rule_count();
+                blocker_section = blocker_section.push(
+                    text(format!("🛡 {} règles réseau, {} règles cosmétiques", network_rules, cosmetic_rules))
+                        .size(12)
+                        .style(Color::from_rgb(0.2, 0.5, 0.2))
+                );
+                for (name, count) in blocker.lists() {
+                    blocker_section = blocker_section.push(
+                        text(format!("{} ({} règles)", name, count)).size(12).style(Color::from_rgb(0.4, 0.4, 0.4))
+                    );
+                }
+            }
+            None => {
+                blocker_section = blocker_section.push(
+                    text("Aucune liste de filtres : rien n'est bloqué").size(12).style(Color::from_rgb(0.5, 0.5, 0.5))
+                );
+            }
+        }
+        blocker_section = blocker_section.push(button(text("Recharger les listes").size(12)).on_press(Message::ReloadFilters));
+
+        let mut page = column![text("Paramètres").size(24), proxy_section, dns_section, privacy_section, protocols_section, https_section, blocker_section].spacing(16);
+        if let Some(error) = &self.network_error {
+            page = page.push(text(error).size(12).style(Color::from_rgb(0.7, 0.2, 0.2)));
+        }
+
+        scrollable(
+            container(page)
+                .width(Length::Fill)
+                .padding(Padding::from([40, 20]))
+                .center_x()
+        )
+        .height(Length::Fill)
+        .into()
+    }
+
     fn view_shortcuts(&self) -> Element<Message> {
         let shortcuts_data = vec![
             ("Project Zomboi...", "https://projectzomboid.com", "P"),
@@ -2238,3 +4194,219 @@ impl iced::widget::button::StyleSheet for NavButtonStyle {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str) -> Box<PageContent> {
+        Box::new(PageContent {
+            document_title: url.to_string(),
+            styled_content: Vec::new(),
+            body_styles: None,
+            url: url.to_string(),
+            response_info: None,
+            resources: Vec::new(),
+            pending_resources: Vec::new(),
+            hidden_elements: 0,
+            forms: FormSet::default(),
+            images: HashMap::new(),
+        })
+    }
+
+    #[test]
+    fn test_superseded_loads_are_aborted_and_ignored() {
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+
+        let _ = browser.start_load(0, "https://first.test/".to_string());
+        let first = browser.tabs[0].navigation_id;
+        let first_handle = browser.tabs[0].load_handle.clone().unwrap();
+        let _ = browser.start_load(0, "https://second.test/".to_string());
+        assert!(first_handle.is_aborted());
+        let second = browser.tabs[0].navigation_id;
+        assert_ne!(first, second);
+
+        // The first page arriving late does not replace anything
+        let _ = browser.update(Message::PageLoaded(0, first, Ok(page("https://first.test/"))));
+        assert!(browser.tabs[0].content.is_none());
+        assert!(browser.tabs[0].load_handle.is_some());
+
+        let _ = browser.update(Message::PageLoaded(0, second, Ok(page("https://second.test/"))));
+        assert_eq!(browser.tabs[0].content.as_ref().map(|c| c.url.as_str()), Some("https://second.test/"));
+        assert!(browser.tabs[0].load_handle.is_none());
+    }
+
+    #[test]
+    fn test_certificate_errors_never_fall_back_to_http() {
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+        let navigate = |browser: &mut FagaBrowser, domain: &str| {
+            let _ = browser.update(Message::UrlInputChanged(domain.to_string()));
+            let _ = browser.update(Message::Navigate);
+            assert_eq!(browser.tabs[0].http_fallback, Some(format!("http://{}", domain)));
+            browser.tabs[0].navigation_id
+        };
+
+        let navigation = navigate(&mut browser, "forged.test");
+        let rejected = HttpClientError::CertificateRejected("forged.test".to_string(), CertificateError::Untrusted);
+        let _ = browser.update(Message::PageLoaded(0, navigation, Err(rejected.clone())));
+        assert_eq!(browser.tabs[0].url, "https://forged.test");
+        assert!(matches!(&browser.tabs[0].loading_state, LoadingState::Error(e) if *e == rejected));
+        assert_eq!(browser.tabs[0].http_fallback, None);
+
+        let navigation = navigate(&mut browser, "tls.test");
+        let _ = browser.update(Message::PageLoaded(0, navigation, Err(HttpClientError::TlsError("handshake failure".to_string()))));
+        assert_eq!(browser.tabs[0].url, "https://tls.test");
+
+        // Only an unreachable HTTPS server falls back, and only for the navigation typed
+        let navigation = navigate(&mut browser, "plain.test");
+        let _ = browser.update(Message::PageLoaded(0, navigation, Err(HttpClientError::Timeout)));
+        assert_eq!(browser.tabs[0].url, "http://plain.test");
+        assert_eq!(browser.tabs[0].http_fallback, None);
+
+        navigate(&mut browser, "old.test");
+        let _ = browser.start_load(0, "https://link.test/".to_string());
+        assert_eq!(browser.tabs[0].http_fallback, None);
+    }
+
+    #[test]
+    fn test_textareas_take_several_lines() {
+        use text_editor::{Action, Edit, Motion};
+
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+        let document = HtmlParser::parse("<form><textarea name=\"note\">Hi</textarea></form>", "https://notes.test/").unwrap();
+        let mut content = page("https://notes.test/");
+        content.forms = FormSet::from_document(&document);
+        let _ = browser.start_load(0, "https://notes.test/".to_string());
+        let navigation = browser.tabs[0].navigation_id;
+        let _ = browser.update(Message::PageLoaded(0, navigation, Ok(content)));
+
+        // Enter inserts a line break instead of submitting the form
+        let _ = browser.update(Message::FormEdit(0, Action::Move(Motion::DocumentEnd)));
+        let _ = browser.update(Message::FormEdit(0, Action::Edit(Edit::Enter)));
+        for c in "there".chars() {
+            let _ = browser.update(Message::FormEdit(0, Action::Edit(Edit::Insert(c))));
+        }
+        let tab = &browser.tabs[0];
+        assert_eq!(tab.content.as_ref().unwrap().forms.controls[0].value, "Hi\nthere");
+        assert_eq!(tab.url, "https://notes.test/");
+        assert!(tab.load_handle.is_none());
+    }
+
+    #[test]
+    fn test_closing_a_tab_aborts_its_load() {
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+        let _ = browser.update(Message::NewTab);
+        let tab_id = browser.tabs[1].id;
+
+        let _ = browser.start_load(tab_id, "https://slow.test/".to_string());
+        let handle = browser.tabs[1].load_handle.clone().unwrap();
+        let _ = browser.update(Message::CloseTab(tab_id));
+        assert!(handle.is_aborted());
+        assert_eq!(browser.tabs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stylesheets_load_concurrently_in_document_order() {
+        use hyper::service::{make_service_fn, service_fn};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // The first stylesheet is the slowest: it is still loading when the last one is requested
+        let (in_flight, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let counters = (in_flight.clone(), most.clone());
+        let make_service = make_service_fn(move |_| {
+            let (in_flight, most) = counters.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |request: hyper::Request<hyper::Body>| {
+                    let (in_flight, most) = (in_flight.clone(), most.clone());
+                    async move {
+                        most.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        let (delay, css) = match request.uri().path() {
+                            "/first.css" => (200, "h1 { color: red; }"),
+                            _ => (20, "p { color: blue; }"),
+                        };
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Ok::<_, std::convert::Infallible>(hyper::Response::new(hyper::Body::from(css)))
+                    }
+                }))
+            }
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let origin = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        let page_url = format!("{}/page", origin);
+        let html = "<link rel=\"stylesheet\" href=\"/first.css\"><style>em { color: green; }</style><link rel=\"stylesheet\" href=\"/last.css\">";
+        let document = HtmlParser::parse(html, &page_url).unwrap();
+        let client = HttpClient::with_config(HttpClientConfig { cache: None, cookie_file: None, https_only_file: None, ..Default::default() }).unwrap();
+        let loader = Arc::new(ResourceLoader::new(Arc::new(client)));
+
+        let (sheets, resources) = FagaBrowser::load_stylesheets(&loader, &document, &page_url, 1024.0, 768.0).await;
+        // Root-relative links keep the port of the page
+        assert_eq!(
+            sheets,
+            vec![
+                (format!("{}/first.css", origin), "h1 { color: red; }".to_string()),
+                (page_url.clone(), "em { color: green; }".to_string()),
+                (format!("{}/last.css", origin), "p { color: blue; }".to_string()),
+            ]
+        );
+        assert_eq!(resources.len(), 2);
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_loaded_images_are_drawn_in_place() {
+        let document = HtmlParser::parse("<p>Look: <img src=\"cat.png\" alt=\"A cat\"></p>", "https://pics.test/").unwrap();
+        let rendered = flatten_render_tree_with_body(&HtmlRenderer::new().render(&document).unwrap());
+        let styled = rendered.styled_content.iter().find(|s| s.image.is_some()).unwrap();
+        assert_eq!(styled.text, "A cat");
+        let image_url = HtmlParser::resolve_url(styled.image.as_deref().unwrap(), "https://pics.test/");
+        assert_eq!(document.images, vec![image_url.clone()]);
+
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+        let _ = browser.start_load(0, "https://pics.test/".to_string());
+        let navigation_id = browser.tabs[0].navigation_id;
+        let _ = browser.update(Message::PageLoaded(0, navigation_id, Ok(page("https://pics.test/"))));
+
+        let png = network::response::Response {
+            status: 200,
+            content_type: "image/png".to_string(),
+            body: vec![0x89, b'P', b'N', b'G'],
+            url: image_url.clone(),
+            headers: Vec::new(),
+            redirects: Vec::new(),
+            retries: Vec::new(),
+            http_version: "HTTP/1.1".to_string(),
+        };
+        let resource = LoadedResource { url: image_url.clone(), kind: ResourceKind::Image, result: Ok(Arc::new(png)), queued: Duration::ZERO, elapsed: Duration::ZERO };
+        let _ = browser.update(Message::ResourceLoaded(0, navigation_id, Box::new(resource)));
+
+        let content = browser.tabs[0].content.as_ref().unwrap();
+        assert!(content.images.contains_key(&image_url));
+        assert_eq!(content.resources.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_data_images_are_drawn_in_place() {
+        let src = "data:image/png;base64,iVBORw0KGgo=";
+        let document = HtmlParser::parse(&format!("<p><img src=\"{}\" alt=\"Inline\"></p>", src), "https://pics.test/").unwrap();
+        let rendered = flatten_render_tree_with_body(&HtmlRenderer::new().render(&document).unwrap());
+        let styled = rendered.styled_content.iter().find(|s| s.image.is_some()).unwrap();
+        let image_url = HtmlParser::resolve_url(styled.image.as_deref().unwrap(), "https://pics.test/");
+        assert_eq!(document.images, vec![image_url.clone()]);
+
+        // Served by the client itself, without touching the network
+        let client = HttpClient::with_config(HttpClientConfig { cache: None, cookie_file: None, https_only_file: None, ..Default::default() }).unwrap();
+        let loader = Arc::new(ResourceLoader::new(Arc::new(client)));
+        let resource = loader.fetch(&image_url, ResourceKind::Image, "https://pics.test/").await;
+        assert_eq!(resource.result.as_ref().unwrap().body, b"\x89PNG\r\n\x1a\n");
+
+        let mut browser = FagaBrowser::with_network(NetworkSettings::default(), None);
+        let _ = browser.start_load(0, "https://pics.test/".to_string());
+        let navigation_id = browser.tabs[0].navigation_id;
+        let _ = browser.update(Message::PageLoaded(0, navigation_id, Ok(page("https://pics.test/"))));
+        let _ = browser.update(Message::ResourceLoaded(0, navigation_id, Box::new(resource)));
+
+        assert!(browser.tabs[0].content.as_ref().unwrap().images.contains_key(&image_url));
+    }
+}
@@ -0,0 +1,411 @@
+//! HTTP authentication for FAGA Browser
+//! Parses `WWW-Authenticate` challenges and answers them with Basic or
+//! Digest (RFC 7617 / RFC 7616) credentials remembered per realm for the
+//! session.
+
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use md5::{Digest, Md5};
+use url::Url;
+
+/// Authentication schemes the client can answer, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AuthScheme {
+    Basic,
+    Digest,
+}
+
+/// Hash function of a Digest challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+/// A challenge from a `WWW-Authenticate` header
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthChallenge {
+    pub scheme: AuthScheme,
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: DigestAlgorithm,
+    /// `-sess` variant of the algorithm
+    pub session: bool,
+    /// Whether `qop=auth` was offered
+    pub qop_auth: bool,
+    /// The nonce expired but the credentials were right
+    pub stale: bool,
+}
+
+impl AuthChallenge {
+    fn new(scheme: AuthScheme) -> Self {
+        Self {
+            scheme,
+            realm: String::new(),
+            nonce: String::new(),
+            opaque: None,
+            algorithm: DigestAlgorithm::Md5,
+            session: false,
+            qop_auth: false,
+            stale: false,
+        }
+    }
+
+    /// Parse every challenge of the `WWW-Authenticate` headers, keeping the
+    /// ones that can be answered
+    pub fn parse_all<'a>(headers: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        let mut challenges = Vec::new();
+        for header in headers {
+            let mut current: Option<Self> = None;
+            let mut skipping = false;
+
+            for (name, value) in tokenize(header) {
+                let value = match value {
+                    // A bare token starts a new challenge
+                    None => {
+                        if let (Some(challenge), false) = (current.take(), skipping) {
+                            challenges.push(challenge);
+                        }
+                        current = match name.to_ascii_lowercase().as_str() {
+                            "basic" => Some(Self::new(AuthScheme::Basic)),
+                            "digest" => Some(Self::new(AuthScheme::Digest)),
+                            _ => None,
+                        };
+                        skipping = current.is_none();
+                        continue;
+                    }
+                    Some(value) => value,
+                };
+                let challenge = match current.as_mut() {
+                    Some(challenge) if !skipping => challenge,
+                    _ => continue,
+                };
+
+                match name.to_ascii_lowercase().as_str() {
+                    "realm" => challenge.realm = value,
+                    "nonce" => challenge.nonce = value,
+                    "opaque" => challenge.opaque = Some(value),
+                    "stale" => challenge.stale = value.eq_ignore_ascii_case("true"),
+                    "qop" => challenge.qop_auth = value.split(',').any(|q| q.trim().eq_ignore_ascii_case("auth")),
+                    "algorithm" => {
+                        let algorithm = value.to_ascii_uppercase();
+                        let (base, session) = match algorithm.strip_suffix("-SESS") {
+                            Some(base) => (base.to_string(), true),
+                            None => (algorithm, false),
+                        };
+                        challenge.session = session;
+                        match base.as_str() {
+                            "MD5" => challenge.algorithm = DigestAlgorithm::Md5,
+                            "SHA-256" => challenge.algorithm = DigestAlgorithm::Sha256,
+                            // Unknown hash: the challenge cannot be answered
+                            _ => skipping = true,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let (Some(challenge), false) = (current, skipping) {
+                challenges.push(challenge);
+            }
+        }
+        challenges.retain(|c| c.scheme == AuthScheme::Basic || !c.nonce.is_empty());
+        challenges
+    }
+
+    /// Strongest challenge among those offered
+    pub fn best(challenges: Vec<Self>) -> Option<Self> {
+        challenges.into_iter().max_by_key(|c| (c.scheme, c.algorithm == DigestAlgorithm::Sha256))
+    }
+}
+
+/// Split a header into `token` and `name=value` items
+fn tokenize(header: &str) -> Vec<(String, Option<String>)> {
+    let mut items = Vec::new();
+    let mut chars = header.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace() || *c == ',') {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == ',' || c == '=' {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        if name.is_empty() {
+            break;
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek() != Some(&'=') {
+            items.push((name, None));
+            continue;
+        }
+        chars.next();
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            // token68 (e.g. `Negotiate abc==`) is a parameter-less value
+            if value.is_empty() || value.chars().all(|c| c == '=') {
+                name.push('=');
+                name.push_str(&value);
+                items.push((name, None));
+                continue;
+            }
+        }
+        items.push((name, Some(value)));
+    }
+    items
+}
+
+/// User name and password typed in the credentials dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Credentials accepted for a realm of an origin
+#[derive(Debug)]
+struct AuthEntry {
+    origin: String,
+    /// Directory of the URL that was challenged; deeper paths get the credentials preemptively
+    path: String,
+    challenge: AuthChallenge,
+    credentials: Credentials,
+    nonce_count: u32,
+}
+
+/// Credentials cached per realm for the lifetime of an HttpClient
+#[derive(Debug, Default)]
+pub struct AuthCache {
+    entries: Mutex<Vec<AuthEntry>>,
+}
+
+impl AuthCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember credentials for the realm of `challenge`, received for `url`
+    pub fn set_credentials(&self, url: &Url, challenge: &AuthChallenge, credentials: Credentials) {
+        let origin = url.origin().ascii_serialization();
+        let path = directory_of(url.path());
+        log::info!("🔑 Credentials saved for \"{}\" on {}", challenge.realm, origin);
+
+        let mut entries = self.lock();
+        entries.retain(|e| !(e.origin == origin && e.challenge.realm == challenge.realm));
+        entries.push(AuthEntry { origin, path, challenge: challenge.clone(), credentials, nonce_count: 0 });
+    }
+
+    /// Whether there are credentials for the realm of `challenge` on `url`'s origin
+    ///
+    /// If so, the entry now answers this (possibly newer) challenge and
+    /// covers `url`, so the request can be retried.
+    pub(crate) fn accept_challenge(&self, url: &Url, challenge: &AuthChallenge) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.lock();
+        match entries.iter_mut().find(|e| e.origin == origin && e.challenge.realm == challenge.realm) {
+            Some(entry) => {
+                entry.challenge = challenge.clone();
+                entry.nonce_count = 0;
+                let path = directory_of(url.path());
+                if !path.starts_with(&entry.path) {
+                    entry.path = common_prefix(&entry.path, &path);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the credentials of `realm` are sent with requests to `url`
+    pub(crate) fn covers(&self, url: &Url, realm: &str) -> bool {
+        let origin = url.origin().ascii_serialization();
+        self.lock()
+            .iter()
+            .filter(|e| e.origin == origin && url.path().starts_with(&e.path))
+            .max_by_key(|e| e.path.len())
+            .is_some_and(|e| e.challenge.realm == realm)
+    }
+
+    /// Drop the credentials of a realm after the server rejected them
+    pub(crate) fn forget(&self, url: &Url, realm: &str) {
+        let origin = url.origin().ascii_serialization();
+        self.lock().retain(|e| !(e.origin == origin && e.challenge.realm == realm));
+    }
+
+    /// `Authorization` header to send with a request, if `url` is in a known protection space
+    pub(crate) fn authorization(&self, method: &str, url: &Url) -> Option<String> {
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.lock();
+        let entry = entries
+            .iter_mut()
+            .filter(|e| e.origin == origin && url.path().starts_with(&e.path))
+            .max_by_key(|e| e.path.len())?;
+
+        let credentials = &entry.credentials;
+        match entry.challenge.scheme {
+            AuthScheme::Basic => {
+                let token = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", credentials.username, credentials.password));
+                Some(format!("Basic {}", token))
+            }
+            AuthScheme::Digest => {
+                entry.nonce_count += 1;
+                let uri = match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                };
+                Some(digest_authorization(&entry.challenge, credentials, method, &uri, entry.nonce_count, &new_cnonce()))
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<AuthEntry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn directory_of(path: &str) -> String {
+    match path.rfind('/') {
+        Some(pos) => path[..=pos].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+fn common_prefix(a: &str, b: &str) -> String {
+    let shared: String = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).map(|(x, _)| x).collect();
+    directory_of(&shared)
+}
+
+/// Client nonce for Digest, unpredictable enough to prevent precomputed answers
+fn new_cnonce() -> String {
+    let mut random = [0u8; 16];
+    if ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut random).is_err() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+        random = nanos.to_le_bytes();
+    }
+    to_hex(&random)
+}
+
+/// Value of the `Authorization` header answering a Digest challenge
+fn digest_authorization(challenge: &AuthChallenge, credentials: &Credentials, method: &str, uri: &str, nonce_count: u32, cnonce: &str) -> String {
+    let hash = |data: String| match challenge.algorithm {
+        // MD5 is still what most Digest servers ask for
+        DigestAlgorithm::Md5 => to_hex(&Md5::digest(data.as_bytes())),
+        DigestAlgorithm::Sha256 => to_hex(ring::digest::digest(&ring::digest::SHA256, data.as_bytes()).as_ref()),
+    };
+    let nc = format!("{:08x}", nonce_count);
+
+    let mut ha1 = hash(format!("{}:{}:{}", credentials.username, challenge.realm, credentials.password));
+    if challenge.session {
+        ha1 = hash(format!("{}:{}:{}", ha1, challenge.nonce, cnonce));
+    }
+    let ha2 = hash(format!("{}:{}", method, uri));
+    let response = if challenge.qop_auth {
+        hash(format!("{}:{}:{}:{}:auth:{}", ha1, challenge.nonce, nc, cnonce, ha2))
+    } else {
+        hash(format!("{}:{}:{}", ha1, challenge.nonce, ha2))
+    };
+
+    let algorithm = match (challenge.algorithm, challenge.session) {
+        (DigestAlgorithm::Md5, false) => "MD5",
+        (DigestAlgorithm::Md5, true) => "MD5-sess",
+        (DigestAlgorithm::Sha256, false) => "SHA-256",
+        (DigestAlgorithm::Sha256, true) => "SHA-256-sess",
+    };
+    let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut header = format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+        quote(&credentials.username), quote(&challenge.realm), quote(&challenge.nonce), quote(uri), algorithm, response
+    );
+    if challenge.qop_auth {
+        header.push_str(&format!(", qop=auth, nc={}, cnonce=\"{}\"", nc, cnonce));
+    }
+    if let Some(ref opaque) = challenge.opaque {
+        header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+    }
+    header
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenges() {
+        let challenges = AuthChallenge::parse_all([
+            "Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\", Basic realm=\"simple\"",
+            "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm=SHA-256, nonce=\"7ypf\", opaque=\"FQhe\"",
+            "Digest realm=\"x\", nonce=\"n\", algorithm=SHA-512-256",
+        ]);
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].scheme, AuthScheme::Basic);
+        assert_eq!(challenges[0].realm, "simple");
+        assert!(challenges[1].qop_auth);
+        assert_eq!(challenges[1].opaque.as_deref(), Some("FQhe"));
+
+        let best = AuthChallenge::best(challenges).unwrap();
+        assert_eq!((best.scheme, best.algorithm), (AuthScheme::Digest, DigestAlgorithm::Sha256));
+    }
+
+    #[test]
+    fn test_digest_responses() {
+        // RFC 7616 §3.9.1
+        let mufasa = Credentials { username: "Mufasa".to_string(), password: "Circle of Life".to_string() };
+        let mut challenge = AuthChallenge::parse_all([
+            "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm=MD5, \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+        ])
+        .remove(0);
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+        let md5 = digest_authorization(&challenge, &mufasa, "GET", "/dir/index.html", 1, cnonce);
+        assert!(md5.contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""), "{}", md5);
+        assert!(md5.contains("nc=00000001") && md5.contains("opaque=\"FQhe/"));
+
+        challenge.algorithm = DigestAlgorithm::Sha256;
+        let sha256 = digest_authorization(&challenge, &mufasa, "GET", "/dir/index.html", 1, cnonce);
+        assert!(sha256.contains("response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""), "{}", sha256);
+
+        let cache = AuthCache::new();
+        let url = Url::parse("http://example.org/dir/index.html").unwrap();
+        let basic = AuthChallenge { realm: "r".to_string(), ..AuthChallenge::new(AuthScheme::Basic) };
+        let aladdin = Credentials { username: "Aladdin".to_string(), password: "open sesame".to_string() };
+        cache.set_credentials(&url, &basic, aladdin);
+        assert_eq!(cache.authorization("GET", &url).as_deref(), Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="));
+        assert!(cache.authorization("GET", &Url::parse("http://example.org/dir/sub/a").unwrap()).is_some());
+        assert!(cache.authorization("GET", &Url::parse("http://example.org/other").unwrap()).is_none());
+        assert!(cache.authorization("GET", &Url::parse("https://example.org/dir/").unwrap()).is_none());
+    }
+}
@@ -0,0 +1,582 @@
+//! Content blocker for FAGA Browser
+//! Applies Adblock Plus / EasyList filter lists: network rules stop requests
+//! before they are sent, element hiding rules (`##selector`) remove page
+//! elements before rendering.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::{Position, Url};
+use crate::parser::dom::Document;
+use crate::parser::selector::Selector;
+use super::cookies::site_of;
+use super::loader::ResourceKind;
+
+// Request types a network rule applies to, as bit flags
+const STYLESHEET: u8 = 1 << 0;
+const SCRIPT: u8 = 1 << 1;
+const FONT: u8 = 1 << 2;
+const IMAGE: u8 = 1 << 3;
+const OTHER: u8 = 1 << 4;
+const DOCUMENT: u8 = 1 << 5;
+/// Types the browser never requests (frames, XHR, media, ...)
+const UNSUPPORTED: u8 = 1 << 6;
+/// Rules without type options apply to everything but top-level documents
+const DEFAULT_TYPES: u8 = !DOCUMENT;
+
+fn type_flag(kind: Option<ResourceKind>) -> u8 {
+    match kind {
+        Some(ResourceKind::Stylesheet) => STYLESHEET,
+        Some(ResourceKind::Script) => SCRIPT,
+        Some(ResourceKind::Font) => FONT,
+        Some(ResourceKind::Image) => IMAGE,
+        Some(ResourceKind::Other) => OTHER,
+        None => DOCUMENT,
+    }
+}
+
+fn option_type_flag(name: &str) -> Option<u8> {
+    match name {
+        "stylesheet" | "css" => Some(STYLESHEET),
+        "script" => Some(SCRIPT),
+        "font" => Some(FONT),
+        "image" => Some(IMAGE),
+        "other" => Some(OTHER),
+        "document" | "doc" => Some(DOCUMENT),
+        "xmlhttprequest" | "xhr" | "subdocument" | "frame" | "media" | "object" | "object-subrequest"
+        | "ping" | "websocket" | "webrtc" => Some(UNSUPPORTED),
+        _ => None,
+    }
+}
+
+/// A URL filter, e.g. `||ads.example.com^$third-party,script`
+#[derive(Debug, Clone)]
+struct NetworkRule {
+    /// The filter as written in the list, for logs
+    text: String,
+    exception: bool,
+    important: bool,
+    match_case: bool,
+    /// `||`: the pattern starts at a label of the host
+    host_anchor: bool,
+    /// `|` at the start: the pattern starts at the beginning of the URL
+    start_anchor: bool,
+    /// `|` at the end: the pattern ends at the end of the URL
+    end_anchor: bool,
+    /// Pattern with `*` wildcards and `^` separators, lowercased unless `match_case`
+    pattern: String,
+    third_party: Option<bool>,
+    types: u8,
+    domains: Vec<String>,
+    excluded_domains: Vec<String>,
+}
+
+impl NetworkRule {
+    fn parse(line: &str) -> Option<Self> {
+        let (exception, filter) = match line.strip_prefix("@@") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (filter, options) = match filter.rfind('$') {
+            Some(pos) => (&filter[..pos], Some(&filter[pos + 1..])),
+            None => (filter, None),
+        };
+
+        let mut rule = Self {
+            text: line.to_string(),
+            exception,
+            important: false,
+            match_case: false,
+            host_anchor: false,
+            start_anchor: false,
+            end_anchor: false,
+            pattern: String::new(),
+            third_party: None,
+            types: DEFAULT_TYPES,
+            domains: Vec::new(),
+            excluded_domains: Vec::new(),
+        };
+
+        let (mut included, mut excluded) = (0u8, 0u8);
+        for option in options.into_iter().flat_map(|o| o.split(',')) {
+            let option = option.trim().to_ascii_lowercase();
+            let (negated, name) = match option.strip_prefix('~') {
+                Some(name) => (true, name),
+                None => (false, option.as_str()),
+            };
+            match name.split_once('=') {
+                None if name == "third-party" || name == "3p" => rule.third_party = Some(!negated),
+                None if name == "first-party" || name == "1p" => rule.third_party = Some(negated),
+                None if name == "match-case" => rule.match_case = true,
+                None if name == "important" => rule.important = true,
+                Some(("domain", list)) => {
+                    for domain in list.split('|').filter(|d| !d.is_empty()) {
+                        match domain.strip_prefix('~') {
+                            Some(domain) => rule.excluded_domains.push(domain.to_string()),
+                            None => rule.domains.push(domain.to_string()),
+                        }
+                    }
+                }
+                // Any other option changes what the rule does (popup, csp, redirect, ...)
+                None => match option_type_flag(name) {
+                    Some(flag) if negated => excluded |= flag,
+                    Some(flag) => included |= flag,
+                    None => return None,
+                },
+                Some(_) => return None,
+            }
+        }
+        if included != 0 {
+            rule.types = included;
+        }
+        rule.types &= !excluded;
+
+        let mut filter = filter;
+        if let Some(rest) = filter.strip_prefix("||") {
+            rule.host_anchor = true;
+            filter = rest;
+        } else if let Some(rest) = filter.strip_prefix('|') {
+            rule.start_anchor = true;
+            filter = rest;
+        }
+        if let Some(rest) = filter.strip_suffix('|') {
+            rule.end_anchor = true;
+            filter = rest;
+        }
+
+        // Regular expression filters are not supported
+        if filter.len() > 1 && filter.starts_with('/') && filter.ends_with('/') {
+            return None;
+        }
+
+        let mut pattern = String::with_capacity(filter.len());
+        for c in filter.chars() {
+            if !(c == '*' && pattern.ends_with('*')) {
+                pattern.push(c);
+            }
+        }
+        if pattern.starts_with('*') {
+            rule.host_anchor = false;
+            rule.start_anchor = false;
+        }
+        if pattern.ends_with('*') {
+            rule.end_anchor = false;
+        }
+        rule.pattern = pattern.trim_matches('*').to_string();
+        if !rule.match_case {
+            rule.pattern.make_ascii_lowercase();
+        }
+
+        // A bare `*` or empty filter must at least be limited by its options
+        let unrestricted = rule.third_party.is_none() && rule.domains.is_empty() && included == 0;
+        if rule.pattern.is_empty() && unrestricted && !rule.exception {
+            return None;
+        }
+        Some(rule)
+    }
+
+    /// Longest literal token of the pattern that is whole in every matching URL
+    fn token(&self) -> Option<String> {
+        let bytes = self.pattern.as_bytes();
+        let mut best: Option<&str> = None;
+        let mut start = 0;
+        for end in 0..=bytes.len() {
+            if end < bytes.len() && is_token_byte(bytes[end]) {
+                continue;
+            }
+            if end > start {
+                let bounded_start = if start == 0 { self.host_anchor || self.start_anchor } else { bytes[start - 1] != b'*' };
+                let bounded_end = if end == bytes.len() { self.end_anchor } else { bytes[end] != b'*' };
+                if bounded_start && bounded_end && best.is_none_or(|b| end - start > b.len()) {
+                    best = Some(&self.pattern[start..end]);
+                }
+            }
+            start = end + 1;
+        }
+        best.map(|token| token.to_ascii_lowercase())
+    }
+
+    fn applies_to(&self, page_host: &str, third_party: bool, flag: u8) -> bool {
+        self.types & flag != 0
+            && self.third_party.is_none_or(|tp| tp == third_party)
+            && (self.domains.is_empty() || self.domains.iter().any(|d| domain_matches(page_host, d)))
+            && !self.excluded_domains.iter().any(|d| domain_matches(page_host, d))
+    }
+
+    fn matches_url(&self, url: &Url, lowercase: &str) -> bool {
+        let text = if self.match_case { url.as_str() } else { lowercase }.as_bytes();
+        let pattern = self.pattern.as_bytes();
+
+        if self.host_anchor {
+            let (host_start, host_end) = (url[..Position::BeforeHost].len(), url[..Position::AfterHost].len());
+            return (host_start..host_end)
+                .filter(|&i| i == host_start || text[i - 1] == b'.')
+                .any(|i| match_at(pattern, &text[i..], self.end_anchor));
+        }
+        if self.start_anchor {
+            return match_at(pattern, text, self.end_anchor);
+        }
+        (0..=text.len()).any(|i| match_at(pattern, &text[i..], self.end_anchor))
+    }
+}
+
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'%'
+}
+
+/// `^` matches anything but a letter, a digit, `_`, `-`, `.` or `%`
+fn is_separator(b: u8) -> bool {
+    !(b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.' | b'%'))
+}
+
+/// Whether `pattern` matches the start of `text` (all of it with `to_end`)
+fn match_at(pattern: &[u8], text: &[u8], to_end: bool) -> bool {
+    match pattern.split_first() {
+        None => !to_end || text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| match_at(rest, &text[i..], to_end)),
+        // `^` also matches the end of the address
+        Some((b'^', rest)) => match text.split_first() {
+            Some((&c, tail)) if is_separator(c) => match_at(rest, tail, to_end),
+            Some(_) => false,
+            None => match_at(rest, text, to_end),
+        },
+        Some((&c, rest)) => text.first() == Some(&c) && match_at(rest, &text[1..], to_end),
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// An element hiding rule, e.g. `example.com,~shop.example.com##.ad-banner`
+#[derive(Debug, Clone)]
+struct CosmeticRule {
+    selector_text: String,
+    selectors: Vec<Selector>,
+    domains: Vec<String>,
+    excluded_domains: Vec<String>,
+}
+
+impl CosmeticRule {
+    fn parse(domains: &str, selector: &str) -> Self {
+        let mut rule = Self {
+            selector_text: selector.trim().to_string(),
+            selectors: Selector::parse_list(selector),
+            domains: Vec::new(),
+            excluded_domains: Vec::new(),
+        };
+        for domain in domains.split(',').map(|d| d.trim().to_ascii_lowercase()).filter(|d| !d.is_empty()) {
+            match domain.strip_prefix('~') {
+                Some(domain) => rule.excluded_domains.push(domain.to_string()),
+                None => rule.domains.push(domain),
+            }
+        }
+        rule
+    }
+
+    fn applies_to(&self, host: &str) -> bool {
+        (self.domains.is_empty() || self.domains.iter().any(|d| domain_matches(host, d)))
+            && !self.excluded_domains.iter().any(|d| domain_matches(host, d))
+    }
+}
+
+/// Filter lists loaded into the browser
+#[derive(Default)]
+pub struct ContentBlocker {
+    rules: Vec<NetworkRule>,
+    /// Rule indices by the token every URL they match contains
+    by_token: HashMap<String, Vec<usize>>,
+    /// Rules without a usable token, tried on every request
+    untokenized: Vec<usize>,
+    cosmetic: Vec<CosmeticRule>,
+    cosmetic_exceptions: Vec<CosmeticRule>,
+    /// File name and number of rules of each loaded list
+    lists: Vec<(String, usize)>,
+}
+
+impl std::fmt::Debug for ContentBlocker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentBlocker")
+            .field("network_rules", &self.rules.len())
+            .field("cosmetic_rules", &self.cosmetic.len())
+            .field("lists", &self.lists)
+            .finish()
+    }
+}
+
+impl ContentBlocker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `*.txt` filter list of a folder, in file name order
+    pub fn load_dir(dir: &Path) -> Self {
+        let mut blocker = Self::new();
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "txt"));
+        files.sort();
+
+        for path in files {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    let count = blocker.add_list(&text);
+                    log::info!("🛡️ Loaded filter list {} ({} rules)", name, count);
+                    blocker.lists.push((name, count));
+                }
+                Err(e) => log::warn!("⚠️ Failed to read filter list {}: {}", path.display(), e),
+            }
+        }
+        blocker
+    }
+
+    /// Add the rules of a filter list, returning how many were understood
+    pub fn add_list(&mut self, text: &str) -> usize {
+        let (mut added, mut skipped) = (0, 0);
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+                continue;
+            }
+            if self.add_rule(line) {
+                added += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+        if skipped > 0 {
+            log::debug!("🛡️ Skipped {} unsupported filters", skipped);
+        }
+        added
+    }
+
+    fn add_rule(&mut self, line: &str) -> bool {
+        if let Some((domains, selector)) = line.split_once("#@#") {
+            self.cosmetic_exceptions.push(CosmeticRule::parse(domains, selector));
+            return true;
+        }
+        if let Some((domains, selector)) = line.split_once("##") {
+            let rule = CosmeticRule::parse(domains, selector);
+            if rule.selectors.is_empty() {
+                return false;
+            }
+            self.cosmetic.push(rule);
+            return true;
+        }
+        // Extended syntaxes (`#?#`, `#$#`, scriptlets, ...)
+        if line.contains("#?#") || line.contains("#$#") || line.contains("#%#") {
+            return false;
+        }
+
+        let rule = match NetworkRule::parse(line) {
+            Some(rule) => rule,
+            None => return false,
+        };
+        let index = self.rules.len();
+        match rule.token() {
+            Some(token) => self.by_token.entry(token).or_default().push(index),
+            None => self.untokenized.push(index),
+        }
+        self.rules.push(rule);
+        true
+    }
+
+    /// Number of network and element hiding rules
+    pub fn rule_count(&self) -> (usize, usize) {
+        (self.rules.len(), self.cosmetic.len())
+    }
+
+    /// File name and rule count of each list loaded by `load_dir`
+    pub fn lists(&self) -> &[(String, usize)] {
+        &self.lists
+    }
+
+    /// Filter blocking a request for `url`, if any
+    ///
+    /// `first_party` is the page the request comes from (None for the page
+    /// itself) and `kind` what it fetches (None for a document).
+    pub fn check(&self, url: &Url, first_party: Option<&Url>, kind: Option<ResourceKind>) -> Option<&str> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        if first_party.is_some_and(|page| self.page_allowed(page)) {
+            return None;
+        }
+
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let page_host = first_party.and_then(|page| page.host_str()).map_or(host.clone(), |h| h.to_ascii_lowercase());
+        let third_party = site_of(&host) != site_of(&page_host);
+
+        let mut blocking = None;
+        let mut excepted = false;
+        for rule in self.candidates(url, type_flag(kind)) {
+            if !rule.applies_to(&page_host, third_party, type_flag(kind)) {
+                continue;
+            }
+            match (rule.exception, rule.important) {
+                (false, true) => return Some(&rule.text),
+                (false, false) => blocking = blocking.or(Some(rule.text.as_str())),
+                (true, _) => excepted = true,
+            }
+        }
+        if excepted {
+            None
+        } else {
+            blocking
+        }
+    }
+
+    /// Whether an `@@...$document` exception turns blocking off on `page`
+    fn page_allowed(&self, page: &Url) -> bool {
+        let host = page.host_str().unwrap_or_default().to_ascii_lowercase();
+        self.candidates(page, DOCUMENT)
+            .into_iter()
+            .any(|rule| rule.exception && rule.types & DOCUMENT != 0 && rule.applies_to(&host, false, DOCUMENT))
+    }
+
+    /// Rules whose pattern matches `url`, found through the token index
+    fn candidates(&self, url: &Url, flag: u8) -> Vec<&NetworkRule> {
+        let lowercase = url.as_str().to_ascii_lowercase();
+        let mut seen = HashSet::new();
+        let tokens: Vec<&str> = lowercase
+            .split(|c: char| !c.is_ascii() || !is_token_byte(c as u8))
+            .filter(|t| !t.is_empty() && seen.insert(*t))
+            .collect();
+
+        let mut indices: Vec<usize> = tokens
+            .iter()
+            .filter_map(|token| self.by_token.get(*token))
+            .flatten()
+            .chain(&self.untokenized)
+            .copied()
+            .filter(|&i| self.rules[i].types & flag != 0)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices
+            .into_iter()
+            .map(|i| &self.rules[i])
+            .filter(|rule| rule.matches_url(url, &lowercase))
+            .collect()
+    }
+
+    /// Remove the elements hidden by the element hiding rules for `page`
+    ///
+    /// Returns the number of elements removed.
+    pub fn hide_elements(&self, document: &mut Document, page: &Url) -> usize {
+        if self.cosmetic.is_empty() || self.page_allowed(page) {
+            return 0;
+        }
+        let host = page.host_str().unwrap_or_default().to_ascii_lowercase();
+
+        let excepted: HashSet<&str> = self.cosmetic_exceptions
+            .iter()
+            .filter(|rule| rule.applies_to(&host))
+            .map(|rule| rule.selector_text.as_str())
+            .collect();
+        let selectors: Vec<Selector> = self.cosmetic
+            .iter()
+            .filter(|rule| rule.applies_to(&host) && !excepted.contains(rule.selector_text.as_str()))
+            .flat_map(|rule| rule.selectors.iter().cloned())
+            .collect();
+
+        let hidden = document.remove_matching(&selectors);
+        if hidden > 0 {
+            log::info!("🛡️ Hid {} elements on {}", hidden, host);
+        }
+        hidden
+    }
+}
+
+/// Folder whose `*.txt` filter lists are loaded at startup (e.g. `~/.local/share/faga/filters`)
+pub fn default_filters_dir() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("filters"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::HtmlParser;
+
+    const LIST: &str = "\
+[Adblock Plus 2.0]
+! Title: test list
+||ads.example.net^
+||tracker.com^$third-party
+/banner/*.gif$image
+&adserver=
+||cdn.example.org/ads/$script,domain=news.com|~sports.news.com
+@@||ads.example.net/allowed.js
+||evil.com^$document
+||popup.com^$popup
+/ad[0-9]+/
+@@||trusted.org^$document
+##.ad-banner
+news.com##aside > .promo
+~sports.news.com##div[id^=\"div-gpt-ad\"]
+shop.com#@#.ad-banner
+example.com#?#div:-abp-has(.ad)
+";
+
+    fn blocked(blocker: &ContentBlocker, url: &str, page: Option<&str>, kind: Option<ResourceKind>) -> bool {
+        let url = Url::parse(url).unwrap();
+        let page = page.map(|p| Url::parse(p).unwrap());
+        blocker.check(&url, page.as_ref(), kind).is_some()
+    }
+
+    #[test]
+    fn test_network_rules() {
+        let mut blocker = ContentBlocker::new();
+        assert_eq!(blocker.add_list(LIST), 12);
+        let script = Some(ResourceKind::Script);
+        let page = Some("https://news.com/article");
+
+        assert!(blocked(&blocker, "https://ads.example.net/x.js", page, script));
+        assert!(blocked(&blocker, "https://static.ads.example.net/x.js", page, script));
+        assert!(!blocked(&blocker, "https://badads.example.net/x.js", page, script));
+        assert!(!blocked(&blocker, "https://ads.example.net/allowed.js", page, script));
+        // Blocking a top-level document needs `$document`
+        assert!(!blocked(&blocker, "https://ads.example.net/", None, None));
+        assert!(blocked(&blocker, "https://evil.com/", None, None));
+
+        assert!(blocked(&blocker, "https://tracker.com/t.js", page, script));
+        assert!(!blocked(&blocker, "https://tracker.com/t.js", Some("https://www.tracker.com/"), script));
+
+        assert!(blocked(&blocker, "https://img.net/banner/top.gif", page, Some(ResourceKind::Image)));
+        assert!(!blocked(&blocker, "https://img.net/banner/top.gif", page, Some(ResourceKind::Stylesheet)));
+        assert!(blocked(&blocker, "https://x.net/q?a=1&adserver=2", page, Some(ResourceKind::Other)));
+
+        assert!(blocked(&blocker, "https://cdn.example.org/ads/a.js", page, script));
+        assert!(!blocked(&blocker, "https://cdn.example.org/ads/a.js", Some("https://sports.news.com/"), script));
+        assert!(!blocked(&blocker, "https://cdn.example.org/ads/a.js", Some("https://other.com/"), script));
+
+        assert!(!blocked(&blocker, "https://popup.com/", page, script));
+        assert!(!blocked(&blocker, "https://ads.example.net/x.js", Some("https://trusted.org/"), script));
+    }
+
+    #[test]
+    fn test_element_hiding() {
+        let mut blocker = ContentBlocker::new();
+        blocker.add_list(LIST);
+        let html = r#"<html><body>
+            <div class="ad-banner">ad</div>
+            <aside><p class="promo">promo</p></aside>
+            <div id="div-gpt-ad-1">gpt</div>
+            <p class="promo">kept</p>
+        </body></html>"#;
+
+        let hidden_on = |page: &str| {
+            let mut document = HtmlParser::parse(html, page).unwrap();
+            let hidden = blocker.hide_elements(&mut document, &Url::parse(page).unwrap());
+            (hidden, document.root.as_ref().unwrap().text_content())
+        };
+
+        let (hidden, text) = hidden_on("https://www.news.com/");
+        assert_eq!(hidden, 3);
+        assert!(text.contains("kept") && !text.contains("promo") && !text.contains("gpt"));
+        assert_eq!(hidden_on("https://sports.news.com/").0, 2);
+        assert_eq!(hidden_on("https://shop.com/").0, 1);
+    }
+}
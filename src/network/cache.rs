@@ -0,0 +1,595 @@
+//! HTTP Cache for FAGA Browser
+//! Private RFC 9111 cache with a memory tier and an optional disk tier
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use super::response::Response;
+
+/// Status codes that are cacheable by default (RFC 9110 §15.1)
+const HEURISTICALLY_CACHEABLE: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Upper bound for heuristic freshness, whatever Last-Modified says
+const MAX_HEURISTIC_LIFETIME: u64 = 24 * 60 * 60;
+
+/// Configuration for the HTTP cache
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Maximum number of body bytes kept in memory
+    pub memory_capacity: usize,
+    /// Maximum number of body bytes kept on disk
+    pub disk_capacity: usize,
+    /// Directory of the disk tier (None = memory only)
+    pub disk_dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            memory_capacity: 32 * 1024 * 1024,
+            disk_capacity: 256 * 1024 * 1024,
+            disk_dir: default_cache_dir(),
+        }
+    }
+}
+
+/// Platform cache directory for FAGA (e.g. `~/.cache/faga/http`)
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.map(|dir| dir.join("faga").join("http"))
+}
+
+/// Parsed Cache-Control directives
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheControl {
+    pub max_age: Option<u64>,
+    pub no_store: bool,
+    pub no_cache: bool,
+    pub must_revalidate: bool,
+    pub private: bool,
+    pub public: bool,
+}
+
+impl CacheControl {
+    /// Parse a Cache-Control header value
+    pub fn parse(value: &str) -> Self {
+        let mut cc = Self::default();
+        for directive in value.split(',') {
+            let (name, arg) = match directive.split_once('=') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "max-age" => cc.max_age = arg.and_then(|a| a.parse().ok()),
+                "no-store" => cc.no_store = true,
+                "no-cache" => cc.no_cache = true,
+                "must-revalidate" => cc.must_revalidate = true,
+                "private" => cc.private = true,
+                "public" => cc.public = true,
+                _ => {}
+            }
+        }
+        cc
+    }
+}
+
+/// Result of looking a request up in the cache
+#[derive(Debug)]
+pub enum CacheLookup {
+    /// A fresh response that can be used without contacting the server
+    Fresh(Response),
+    /// A stored response that must be revalidated with these validators
+    Stale {
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+    Miss,
+}
+
+/// Metadata of a stored response (the body is kept separately on disk)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EntryMeta {
+    key: String,
+    status: u16,
+    content_type: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    http_version: String,
+    /// Request header values selected by Vary when the response was stored
+    vary: Vec<(String, Option<String>)>,
+    /// Local time (unix seconds) at which the response was received
+    response_time: u64,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    meta: EntryMeta,
+    body: Vec<u8>,
+}
+
+impl CacheEntry {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.meta.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn cache_control(&self) -> CacheControl {
+        self.header("cache-control").map(CacheControl::parse).unwrap_or_default()
+    }
+
+    fn size(&self) -> usize {
+        self.body.len()
+    }
+
+    /// Freshness lifetime in seconds (RFC 9111 §4.2.1)
+    fn freshness_lifetime(&self) -> u64 {
+        if let Some(max_age) = self.cache_control().max_age {
+            return max_age;
+        }
+
+        let date = self.header("date").and_then(parse_http_date);
+        if let Some(expires) = self.header("expires") {
+            // An invalid Expires (e.g. "0") means already expired
+            return match (parse_http_date(expires), date) {
+                (Some(expires), Some(date)) => expires.saturating_sub(date),
+                (Some(expires), None) => expires.saturating_sub(self.meta.response_time),
+                _ => 0,
+            };
+        }
+
+        // Heuristic freshness: 10% of the time since last modification
+        if HEURISTICALLY_CACHEABLE.contains(&self.meta.status) {
+            if let Some(last_modified) = self.header("last-modified").and_then(parse_http_date) {
+                let date = date.unwrap_or(self.meta.response_time);
+                return (date.saturating_sub(last_modified) / 10).min(MAX_HEURISTIC_LIFETIME);
+            }
+        }
+
+        0
+    }
+
+    /// Current age in seconds (RFC 9111 §4.2.3, simplified)
+    fn current_age(&self, now: u64) -> u64 {
+        let age_value = self.header("age").and_then(|a| a.trim().parse().ok()).unwrap_or(0);
+        age_value + now.saturating_sub(self.meta.response_time)
+    }
+
+    fn matches_vary(&self, request_headers: &HeaderMap) -> bool {
+        self.meta.vary
+            .iter()
+            .all(|(name, value)| request_header(request_headers, name).as_deref() == value.as_deref())
+    }
+
+    fn to_response(&self) -> Response {
+        Response {
+            status: self.meta.status,
+            content_type: self.meta.content_type.clone(),
+            body: self.body.clone(),
+            url: self.meta.url.clone(),
+            headers: self.meta.headers.clone(),
+            redirects: Vec::new(),
+            retries: Vec::new(),
+            http_version: self.meta.http_version.clone(),
+        }
+    }
+}
+
+/// Least-recently-used store bounded by total body size
+#[derive(Debug, Default)]
+struct MemoryTier {
+    entries: HashMap<String, (CacheEntry, u64)>,
+    size: usize,
+    clock: u64,
+}
+
+impl MemoryTier {
+    fn get(&mut self, key: &str) -> Option<CacheEntry> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|(entry, last_access)| {
+            *last_access = clock;
+            entry.clone()
+        })
+    }
+
+    fn insert(&mut self, entry: CacheEntry, capacity: usize) {
+        self.remove(&entry.meta.key);
+        if entry.size() > capacity {
+            return;
+        }
+
+        while self.size + entry.size() > capacity {
+            let oldest = self.entries
+                .iter()
+                .min_by_key(|(_, (_, last_access))| *last_access)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.remove(&key),
+                None => break,
+            }
+        }
+
+        self.clock += 1;
+        self.size += entry.size();
+        self.entries.insert(entry.meta.key.clone(), (entry, self.clock));
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((entry, _)) = self.entries.remove(key) {
+            self.size -= entry.size();
+        }
+    }
+}
+
+/// Files `<hash>.json` (metadata) and `<hash>.body` in a directory, bounded by total size
+#[derive(Debug)]
+struct DiskTier {
+    dir: PathBuf,
+    /// hash -> (body size, last access)
+    index: HashMap<u64, (usize, u64)>,
+    size: usize,
+}
+
+impl DiskTier {
+    fn open(dir: PathBuf) -> Option<Self> {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            log::warn!("⚠️ Disk cache disabled, cannot create {}: {}", dir.display(), e);
+            return None;
+        }
+
+        let mut index = HashMap::new();
+        let mut size = 0;
+        for entry in fs::read_dir(&dir).ok()?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("body") {
+                continue;
+            }
+            let hash = path.file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| u64::from_str_radix(s, 16).ok());
+            let metadata = entry.metadata().ok();
+            if let (Some(hash), Some(metadata)) = (hash, metadata) {
+                let accessed = metadata.modified().ok().map(unix_seconds).unwrap_or(0);
+                index.insert(hash, (metadata.len() as usize, accessed));
+                size += metadata.len() as usize;
+            }
+        }
+
+        log::info!("💾 Disk cache: {} entries, {} bytes in {}", index.len(), size, dir.display());
+        Some(Self { dir, index, size })
+    }
+
+    fn paths(&self, hash: u64) -> (PathBuf, PathBuf) {
+        (self.dir.join(format!("{:016x}.json", hash)), self.dir.join(format!("{:016x}.body", hash)))
+    }
+
+    fn get(&mut self, key: &str, now: u64) -> Option<CacheEntry> {
+        let hash = fnv1a(key);
+        if !self.index.contains_key(&hash) {
+            return None;
+        }
+
+        let (meta_path, body_path) = self.paths(hash);
+        let meta: Option<EntryMeta> = fs::read(&meta_path).ok().and_then(|m| serde_json::from_slice(&m).ok());
+        let body = fs::read(&body_path).ok();
+
+        match (meta, body) {
+            (Some(meta), Some(body)) if meta.key == key => {
+                if let Some(entry) = self.index.get_mut(&hash) {
+                    entry.1 = now;
+                }
+                Some(CacheEntry { meta, body })
+            }
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, entry: &CacheEntry, capacity: usize, now: u64) {
+        let hash = fnv1a(&entry.meta.key);
+        self.remove_hash(hash);
+        if entry.size() > capacity {
+            return;
+        }
+
+        while self.size + entry.size() > capacity {
+            let oldest = self.index
+                .iter()
+                .min_by_key(|(_, (_, last_access))| *last_access)
+                .map(|(hash, _)| *hash);
+            match oldest {
+                Some(hash) => self.remove_hash(hash),
+                None => break,
+            }
+        }
+
+        let (meta_path, body_path) = self.paths(hash);
+        let meta = match serde_json::to_vec(&entry.meta) {
+            Ok(meta) => meta,
+            Err(_) => return,
+        };
+        if fs::write(&body_path, &entry.body).and_then(|_| fs::write(&meta_path, meta)).is_err() {
+            log::warn!("⚠️ Failed to write disk cache entry for {}", entry.meta.key);
+            let _ = fs::remove_file(&body_path);
+            return;
+        }
+
+        self.index.insert(hash, (entry.size(), now));
+        self.size += entry.size();
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.remove_hash(fnv1a(key));
+    }
+
+    fn remove_hash(&mut self, hash: u64) {
+        if let Some((size, _)) = self.index.remove(&hash) {
+            self.size -= size;
+            let (meta_path, body_path) = self.paths(hash);
+            let _ = fs::remove_file(meta_path);
+            let _ = fs::remove_file(body_path);
+        }
+    }
+}
+
+/// Two-tier HTTP cache shared by every request of an HttpClient
+#[derive(Debug)]
+pub struct HttpCache {
+    config: CacheConfig,
+    memory: Mutex<MemoryTier>,
+    disk: Option<Mutex<DiskTier>>,
+}
+
+impl HttpCache {
+    pub fn new(config: CacheConfig) -> Self {
+        let disk = config.disk_dir.clone().and_then(DiskTier::open).map(Mutex::new);
+        Self {
+            config,
+            memory: Mutex::new(MemoryTier::default()),
+            disk,
+        }
+    }
+
+    /// Look up a GET request
+    pub fn lookup(&self, url: &str, request_headers: &HeaderMap) -> CacheLookup {
+        let request_cc = request_header(request_headers, "cache-control")
+            .map(|v| CacheControl::parse(&v))
+            .unwrap_or_default();
+        if request_cc.no_store {
+            return CacheLookup::Miss;
+        }
+
+        let now = now();
+        let entry = match self.get(url, now) {
+            Some(entry) if entry.matches_vary(request_headers) => entry,
+            _ => return CacheLookup::Miss,
+        };
+
+        let response_cc = entry.cache_control();
+        let fresh = entry.current_age(now) < entry.freshness_lifetime();
+        let force_revalidate = response_cc.no_cache || request_cc.no_cache || request_cc.max_age == Some(0);
+
+        if fresh && !force_revalidate {
+            log::info!("💾 Cache hit: {}", url);
+            return CacheLookup::Fresh(entry.to_response());
+        }
+
+        let etag = entry.header("etag").map(|s| s.to_string());
+        let last_modified = entry.header("last-modified").map(|s| s.to_string());
+        if etag.is_none() && last_modified.is_none() {
+            return CacheLookup::Miss;
+        }
+
+        log::info!("💾 Cache entry stale, revalidating: {}", url);
+        CacheLookup::Stale { etag, last_modified }
+    }
+
+    /// Store a response to a GET request if it is cacheable
+    pub fn store(&self, url: &str, request_headers: &HeaderMap, response: &Response) {
+        let response_cc = response.header("cache-control").map(CacheControl::parse).unwrap_or_default();
+        let request_cc = request_header(request_headers, "cache-control")
+            .map(|v| CacheControl::parse(&v))
+            .unwrap_or_default();
+
+        if response_cc.no_store || request_cc.no_store || !HEURISTICALLY_CACHEABLE.contains(&response.status) {
+            return;
+        }
+
+        let vary_names: Vec<String> = response.header("vary")
+            .map(|v| v.split(',').map(|n| n.trim().to_ascii_lowercase()).filter(|n| !n.is_empty()).collect())
+            .unwrap_or_default();
+        if vary_names.iter().any(|n| n == "*") {
+            return;
+        }
+
+        let entry = CacheEntry {
+            meta: EntryMeta {
+                key: url.to_string(),
+                status: response.status,
+                content_type: response.content_type.clone(),
+                url: response.url.clone(),
+                headers: response.headers.clone(),
+                http_version: response.http_version.clone(),
+                vary: vary_names
+                    .into_iter()
+                    .map(|name| {
+                        let value = request_header(request_headers, &name);
+                        (name, value)
+                    })
+                    .collect(),
+                response_time: now(),
+            },
+            body: response.body.clone(),
+        };
+
+        // Useless to keep something that can be neither reused nor revalidated
+        let has_validator = entry.header("etag").is_some() || entry.header("last-modified").is_some();
+        if entry.freshness_lifetime() == 0 && !has_validator {
+            return;
+        }
+
+        log::debug!("💾 Caching {} ({} bytes)", url, entry.size());
+        self.put(entry);
+    }
+
+    /// Merge the headers of a 304 Not Modified into the stored entry and return the updated response
+    pub fn freshen(&self, url: &str, not_modified: &Response) -> Option<Response> {
+        let mut entry = self.get(url, now())?;
+
+        for (name, value) in &not_modified.headers {
+            // Content-Length of a 304 describes nothing useful
+            if name.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            entry.meta.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+            entry.meta.headers.push((name.clone(), value.clone()));
+        }
+        entry.meta.response_time = now();
+
+        let response = entry.to_response();
+        self.put(entry);
+        Some(response)
+    }
+
+    /// Drop a stored response, e.g. after a successful unsafe request (RFC 9111 §4.4)
+    pub fn invalidate(&self, url: &str) {
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.remove(url);
+        }
+        if let Some(Ok(mut disk)) = self.disk.as_ref().map(|d| d.lock()) {
+            disk.remove(url);
+        }
+    }
+
+    fn get(&self, key: &str, now: u64) -> Option<CacheEntry> {
+        if let Some(entry) = self.memory.lock().ok()?.get(key) {
+            return Some(entry);
+        }
+
+        let entry = self.disk.as_ref()?.lock().ok()?.get(key, now)?;
+        // Promote to the memory tier
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(entry.clone(), self.config.memory_capacity);
+        }
+        Some(entry)
+    }
+
+    fn put(&self, entry: CacheEntry) {
+        if let Some(Ok(mut disk)) = self.disk.as_ref().map(|d| d.lock()) {
+            disk.insert(&entry, self.config.disk_capacity, now());
+        }
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(entry, self.config.memory_capacity);
+        }
+    }
+}
+
+fn request_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(|s| s.to_string())
+}
+
+fn parse_http_date(value: &str) -> Option<u64> {
+    httpdate::parse_http_date(value.trim()).ok().map(unix_seconds)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_secs()
+}
+
+fn now() -> u64 {
+    unix_seconds(SystemTime::now())
+}
+
+/// FNV-1a, stable across builds (unlike the std hasher) so disk file names survive upgrades
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[(&str, &str)], body: &str) -> Response {
+        Response {
+            status: 200,
+            content_type: "text/html".to_string(),
+            body: body.as_bytes().to_vec(),
+            url: "https://example.com/".to_string(),
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            redirects: Vec::new(),
+            retries: Vec::new(),
+            http_version: "HTTP/1.1".to_string(),
+        }
+    }
+
+    fn memory_cache(capacity: usize) -> HttpCache {
+        HttpCache::new(CacheConfig { memory_capacity: capacity, disk_capacity: 0, disk_dir: None })
+    }
+
+    #[test]
+    fn test_parse_cache_control() {
+        let cc = CacheControl::parse("public, max-age=\"600\", No-Cache");
+        assert_eq!(cc.max_age, Some(600));
+        assert!(cc.public && cc.no_cache && !cc.no_store);
+    }
+
+    #[test]
+    fn test_freshness_and_revalidation() {
+        let cache = memory_cache(1024);
+        let url = "https://example.com/";
+        let headers = HeaderMap::new();
+
+        cache.store(url, &headers, &response(&[("cache-control", "max-age=60")], "fresh"));
+        assert!(matches!(cache.lookup(url, &headers), CacheLookup::Fresh(r) if r.body == b"fresh"));
+
+        cache.store(url, &headers, &response(&[("cache-control", "no-cache"), ("etag", "\"v1\"")], "etag"));
+        assert!(matches!(cache.lookup(url, &headers), CacheLookup::Stale { etag: Some(ref e), .. } if e == "\"v1\""));
+
+        cache.invalidate(url);
+        assert!(matches!(cache.lookup(url, &headers), CacheLookup::Miss));
+
+        cache.store(url, &headers, &response(&[("cache-control", "no-store")], "secret"));
+        assert!(matches!(cache.lookup(url, &headers), CacheLookup::Miss));
+    }
+
+    #[test]
+    fn test_vary_and_eviction() {
+        let cache = memory_cache(10);
+        let mut french = HeaderMap::new();
+        french.insert("accept-language", "fr".parse().unwrap());
+
+        cache.store("https://a/", &french, &response(&[("cache-control", "max-age=60"), ("vary", "Accept-Language")], "bonjour"));
+        assert!(matches!(cache.lookup("https://a/", &french), CacheLookup::Fresh(_)));
+        assert!(matches!(cache.lookup("https://a/", &HeaderMap::new()), CacheLookup::Miss));
+
+        // 7 + 7 bytes exceed the 10 byte budget: the least recently used entry goes
+        cache.store("https://b/", &HeaderMap::new(), &response(&[("cache-control", "max-age=60")], "goodbye"));
+        assert!(matches!(cache.lookup("https://a/", &french), CacheLookup::Miss));
+        assert!(matches!(cache.lookup("https://b/", &HeaderMap::new()), CacheLookup::Fresh(_)));
+    }
+
+    #[test]
+    fn test_disk_tier_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CacheConfig { memory_capacity: 1024, disk_capacity: 1024, disk_dir: Some(dir.path().to_path_buf()) };
+
+        HttpCache::new(config.clone())
+            .store("https://example.com/", &HeaderMap::new(), &response(&[("cache-control", "max-age=60")], "persisted"));
+
+        let reopened = HttpCache::new(config);
+        assert!(matches!(reopened.lookup("https://example.com/", &HeaderMap::new()), CacheLookup::Fresh(r) if r.body == b"persisted"));
+    }
+}
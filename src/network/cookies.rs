@@ -0,0 +1,482 @@
+//! Cookie Jar for FAGA Browser
+//! Persistent cookie storage with third-party blocking and per-site rules
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Two-label public suffixes common enough to matter for the site heuristic
+const MULTI_LABEL_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.jp", "ne.jp",
+    "co.nz", "com.br", "com.cn", "com.mx", "co.in", "co.za", "com.tr", "gouv.fr", "github.io",
+];
+
+/// A cookie held by the jar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    /// Domain without leading dot
+    pub domain: String,
+    /// True when no Domain attribute was given (exact host match only)
+    pub host_only: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// Expiry as unix seconds, None for session cookies
+    pub expires: Option<u64>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, url: &Url, now: u64) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+
+        domain_ok
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && !self.is_expired(now)
+    }
+}
+
+/// Per-site cookie rule, overriding the global third-party setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CookiePolicy {
+    Allow,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CookieSettings {
+    block_third_party: bool,
+    /// Domain (and its subdomains) -> policy
+    site_rules: HashMap<String, CookiePolicy>,
+}
+
+impl Default for CookieSettings {
+    fn default() -> Self {
+        Self {
+            block_third_party: true,
+            site_rules: HashMap::new(),
+        }
+    }
+}
+
+/// On-disk representation of the jar (session cookies are never written)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JarFile {
+    cookies: Vec<StoredCookie>,
+    settings: CookieSettings,
+}
+
+/// Cookie store shared by every request of an HttpClient
+#[derive(Debug)]
+pub struct CookieJar {
+    cookies: Mutex<Vec<StoredCookie>>,
+    settings: Mutex<CookieSettings>,
+    path: Option<PathBuf>,
+}
+
+impl CookieJar {
+    /// Create an in-memory jar (private browsing)
+    pub fn new() -> Self {
+        Self {
+            cookies: Mutex::new(Vec::new()),
+            settings: Mutex::new(CookieSettings::default()),
+            path: None,
+        }
+    }
+
+    /// Load a jar from disk, starting empty if the file is missing or unreadable
+    pub fn open(path: PathBuf) -> Self {
+        let file: JarFile = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        let now = now();
+        let cookies: Vec<StoredCookie> = file.cookies.into_iter().filter(|c| !c.is_expired(now)).collect();
+        log::info!("🍪 Loaded {} cookies from {}", cookies.len(), path.display());
+
+        Self {
+            cookies: Mutex::new(cookies),
+            settings: Mutex::new(file.settings),
+            path: Some(path),
+        }
+    }
+
+    /// Build the Cookie header for a request, if any cookie applies
+    ///
+    /// `first_party` is the URL of the top-level document (None for a navigation).
+    pub fn cookie_header(&self, url: &Url, first_party: Option<&Url>) -> Option<String> {
+        if !self.is_allowed(url, first_party) {
+            return None;
+        }
+
+        let now = now();
+        let cookies = self.cookies.lock().ok()?;
+        let mut matching: Vec<&StoredCookie> = cookies.iter().filter(|c| c.matches(url, now)).collect();
+        // Longer paths first (RFC 6265 §5.4)
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+
+        if matching.is_empty() {
+            return None;
+        }
+
+        Some(matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<_>>()
+            .join("; "))
+    }
+
+    /// Store the cookies of the Set-Cookie headers of a response
+    pub fn store_response_cookies<'a>(&self, url: &Url, first_party: Option<&Url>, set_cookies: impl IntoIterator<Item = &'a str>) {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return,
+        };
+
+        let set_cookies: Vec<&str> = set_cookies.into_iter().collect();
+        if set_cookies.is_empty() {
+            return;
+        }
+        if !self.is_allowed(url, first_party) {
+            log::debug!("🍪 Blocked {} cookie(s) from {}", set_cookies.len(), host);
+            return;
+        }
+
+        let now = now();
+        let mut changed = false;
+
+        if let Ok(mut cookies) = self.cookies.lock() {
+            for header in set_cookies {
+                let cookie = match Self::parse_set_cookie(header, url, &host, now) {
+                    Some(cookie) => cookie,
+                    None => continue,
+                };
+
+                cookies.retain(|c| !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path));
+                // An expiry in the past is how servers delete cookies
+                if !cookie.is_expired(now) {
+                    cookies.push(cookie);
+                }
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.save();
+        }
+    }
+
+    /// List every live cookie
+    pub fn list(&self) -> Vec<StoredCookie> {
+        let now = now();
+        self.cookies
+            .lock()
+            .map(|cookies| cookies.iter().filter(|c| !c.is_expired(now)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// List the cookies set for a domain or its subdomains
+    pub fn list_for_domain(&self, domain: &str) -> Vec<StoredCookie> {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        self.list()
+            .into_iter()
+            .filter(|c| domain_matches(&c.domain, &domain))
+            .collect()
+    }
+
+    /// Delete a single cookie
+    pub fn delete(&self, domain: &str, name: &str, path: &str) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            cookies.retain(|c| !(c.domain == domain && c.name == name && c.path == path));
+        }
+        self.save();
+    }
+
+    /// Delete every cookie of a domain and its subdomains
+    pub fn delete_domain(&self, domain: &str) {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        if let Ok(mut cookies) = self.cookies.lock() {
+            cookies.retain(|c| !domain_matches(&c.domain, &domain));
+        }
+        self.save();
+    }
+
+    /// Delete every cookie
+    pub fn clear(&self) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            cookies.clear();
+        }
+        self.save();
+    }
+
+    /// Always allow or always block cookies for a domain and its subdomains
+    pub fn set_site_policy(&self, domain: &str, policy: CookiePolicy) {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.site_rules.insert(domain.clone(), policy);
+        }
+        if policy == CookiePolicy::Block {
+            self.delete_domain(&domain);
+        } else {
+            self.save();
+        }
+    }
+
+    /// Remove a per-site rule, falling back to the global behaviour
+    pub fn remove_site_policy(&self, domain: &str) {
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.site_rules.remove(&domain.trim_start_matches('.').to_ascii_lowercase());
+        }
+        self.save();
+    }
+
+    /// Get the rule that applies to a host, if any
+    pub fn site_policy(&self, host: &str) -> Option<CookiePolicy> {
+        let host = host.to_ascii_lowercase();
+        let settings = self.settings.lock().ok()?;
+        // The most specific rule wins
+        settings.site_rules
+            .iter()
+            .filter(|(domain, _)| domain_matches(&host, domain))
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(_, policy)| *policy)
+    }
+
+    pub fn blocks_third_party(&self) -> bool {
+        self.settings.lock().map(|s| s.block_third_party).unwrap_or(true)
+    }
+
+    pub fn set_block_third_party(&self, block: bool) {
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.block_third_party = block;
+        }
+        self.save();
+    }
+
+    /// Write persistent cookies and settings to disk (no-op for in-memory jars)
+    pub fn save(&self) {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        let file = JarFile {
+            cookies: self.cookies
+                .lock()
+                .map(|cookies| cookies.iter().filter(|c| c.expires.is_some()).cloned().collect())
+                .unwrap_or_default(),
+            settings: self.settings.lock().map(|s| s.clone()).unwrap_or_default(),
+        };
+
+        let result = serde_json::to_vec_pretty(&file)
+            .map_err(std::io::Error::from)
+            .and_then(|data| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, data)
+            });
+        if let Err(e) = result {
+            log::warn!("⚠️ Failed to save cookies to {}: {}", path.display(), e);
+        }
+    }
+
+    fn is_allowed(&self, url: &Url, first_party: Option<&Url>) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return false,
+        };
+
+        match self.site_policy(host) {
+            Some(CookiePolicy::Allow) => return true,
+            Some(CookiePolicy::Block) => return false,
+            None => {}
+        }
+
+        let third_party = first_party
+            .and_then(|fp| fp.host_str())
+            .is_some_and(|fp_host| site_of(fp_host) != site_of(host));
+
+        !(third_party && self.blocks_third_party())
+    }
+
+    fn parse_set_cookie(header: &str, url: &Url, host: &str, now: u64) -> Option<StoredCookie> {
+        let cookie = cookie::Cookie::parse(header).ok()?;
+
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                // Reject cookies for another site or for a whole public suffix
+                if !domain_matches(host, &domain)
+                    || !domain.contains('.')
+                    || MULTI_LABEL_SUFFIXES.contains(&domain.as_str())
+                {
+                    return None;
+                }
+                (domain, false)
+            }
+            None => (host.to_string(), true),
+        };
+
+        // Only secure origins may set Secure cookies
+        if cookie.secure() == Some(true) && url.scheme() != "https" {
+            return None;
+        }
+
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_string(),
+            _ => default_path(url.path()),
+        };
+
+        // Max-Age takes precedence over Expires
+        let expires = if let Some(max_age) = cookie.max_age() {
+            Some(now.saturating_add_signed(max_age.whole_seconds()))
+        } else {
+            cookie.expires_datetime().map(|dt| dt.unix_timestamp().max(0) as u64)
+        };
+
+        Some(StoredCookie {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain,
+            host_only,
+            path,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expires,
+        })
+    }
+}
+
+impl Default for CookieJar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Platform data location of the cookie jar (e.g. `~/.local/share/faga/cookies.json`)
+pub fn default_cookie_path() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("cookies.json"))
+}
+
+/// Approximate registrable domain ("site") of a host
+pub fn site_of(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    // IP addresses are their own site
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = if labels.len() >= 3 && MULTI_LABEL_SUFFIXES.contains(&labels[labels.len() - 2..].join(".").as_str()) {
+        3
+    } else {
+        2
+    };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+/// RFC 6265 §5.1.3 domain matching
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+/// RFC 6265 §5.1.4 path matching
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// RFC 6265 §5.1.4 default-path
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(pos) => request_path[..pos].to_string(),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_cookie_matching() {
+        let jar = CookieJar::new();
+        jar.store_response_cookies(&url("https://www.example.com/account/login"), None, [
+            "sid=abc; Path=/; Secure; HttpOnly",
+            "theme=dark; Domain=example.com; Path=/; Max-Age=3600",
+            "tracker=1; Domain=com",
+        ]);
+
+        assert_eq!(jar.cookie_header(&url("https://www.example.com/"), None).as_deref(), Some("sid=abc; theme=dark"));
+        assert_eq!(jar.cookie_header(&url("http://shop.example.com/"), None).as_deref(), Some("theme=dark"));
+        assert_eq!(jar.list().len(), 2);
+
+        jar.store_response_cookies(&url("https://www.example.com/"), None, ["theme=; Domain=example.com; Path=/; Max-Age=0"]);
+        assert_eq!(jar.cookie_header(&url("http://shop.example.com/"), None), None);
+    }
+
+    #[test]
+    fn test_third_party_and_site_rules() {
+        let jar = CookieJar::new();
+        let page = url("https://news.example.co.uk/");
+        let tracker = url("https://ads.tracker.net/pixel");
+
+        jar.store_response_cookies(&tracker, Some(&page), ["id=42; Max-Age=60"]);
+        assert!(jar.list().is_empty());
+
+        jar.set_site_policy("tracker.net", CookiePolicy::Allow);
+        jar.store_response_cookies(&tracker, Some(&page), ["id=42; Max-Age=60"]);
+        assert_eq!(jar.cookie_header(&tracker, Some(&page)).as_deref(), Some("id=42"));
+
+        jar.set_site_policy("ads.tracker.net", CookiePolicy::Block);
+        assert!(jar.list().is_empty());
+        assert_eq!(site_of("news.example.co.uk"), "example.co.uk");
+    }
+
+    #[test]
+    fn test_persistence_skips_session_cookies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+
+        let jar = CookieJar::open(path.clone());
+        jar.store_response_cookies(&url("https://example.com/"), None, ["session=1", "remember=1; Max-Age=3600"]);
+
+        let reopened = CookieJar::open(path);
+        let names: Vec<String> = reopened.list().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["remember".to_string()]);
+
+        // Clearing also empties the file
+        reopened.clear();
+        assert!(CookieJar::open(dir.path().join("cookies.json")).list().is_empty());
+    }
+}
@@ -0,0 +1,550 @@
+//! Host name resolution for FAGA Browser
+//! Pluggable resolvers (system or DNS-over-HTTPS) behind a cache that honours
+//! record TTLs, with optional fallback to the system resolver.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+
+use super::proxy::ProxyConfig;
+
+/// Endpoint used when DNS-over-HTTPS is enabled without choosing a server
+pub const DEFAULT_DOH_ENDPOINT: &str = "https://cloudflare-dns.com/dns-query";
+
+/// TTL assumed when the resolver does not report one (system lookups)
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+/// Bounds applied to record TTLs before caching
+const MIN_TTL: Duration = Duration::from_secs(5);
+const MAX_TTL: Duration = Duration::from_secs(3600);
+/// Entries kept in the cache before expired ones are swept
+const CACHE_CAPACITY: usize = 1024;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// Addresses of a host and how long they may be cached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsAnswer {
+    pub addrs: Vec<IpAddr>,
+    pub ttl: Duration,
+}
+
+/// Errors that can occur while resolving a host name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsError {
+    /// The name does not exist (an authoritative answer, never retried elsewhere)
+    NotFound(String),
+    /// The resolver could not be queried or sent an unusable answer
+    Failed(String),
+}
+
+impl std::fmt::Display for DnsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(host) => write!(f, "No address found for {}", host),
+            Self::Failed(e) => write!(f, "DNS lookup failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DnsError {}
+
+/// DNS-over-HTTPS settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DohConfig {
+    /// RFC 8484 endpoint, e.g. `https://dns.quad9.net/dns-query`
+    pub endpoint: String,
+    /// Use the system resolver when the endpoint cannot be reached
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+impl Default for DohConfig {
+    fn default() -> Self {
+        Self { endpoint: DEFAULT_DOH_ENDPOINT.to_string(), fallback: false }
+    }
+}
+
+/// How an HttpClient resolves host names
+#[derive(Debug, Clone, Default)]
+pub enum DnsConfig {
+    /// The operating system resolver
+    #[default]
+    System,
+    /// DNS-over-HTTPS
+    Doh(DohConfig),
+    /// Any other resolver, with no fallback
+    Custom(Arc<dyn DnsResolver>),
+}
+
+impl DnsConfig {
+    /// Build the cached resolver to install on a client
+    ///
+    /// DoH queries go through `proxy` so that they leave by the same route
+    /// as the pages they are made for, and trust the same extra `root_certificates` (PEM).
+    pub fn resolver(&self, proxy: Option<&ProxyConfig>, root_certificates: &[Vec<u8>], timeout: Duration) -> Result<CachingResolver, DnsError> {
+        Ok(match self {
+            Self::System => CachingResolver::new(Arc::new(SystemResolver), None),
+            Self::Doh(doh) => {
+                let fallback: Option<Arc<dyn DnsResolver>> = if doh.fallback { Some(Arc::new(SystemResolver)) } else { None };
+                CachingResolver::new(Arc::new(DohResolver::new(&doh.endpoint, proxy, root_certificates, timeout)?), fallback)
+            }
+            Self::Custom(resolver) => CachingResolver::new(resolver.clone(), None),
+        })
+    }
+}
+
+/// A way of turning host names into addresses
+pub trait DnsResolver: Debug + Send + Sync {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<DnsAnswer, DnsError>>;
+}
+
+/// The operating system resolver
+#[derive(Debug, Default)]
+pub struct SystemResolver;
+
+impl DnsResolver for SystemResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<DnsAnswer, DnsError>> {
+        async move {
+            let addrs: Vec<IpAddr> = tokio::net::lookup_host((host, 0))
+                .await
+                .map_err(|e| DnsError::Failed(e.to_string()))?
+                .map(|addr| addr.ip())
+                .collect();
+            if addrs.is_empty() {
+                return Err(DnsError::NotFound(host.to_string()));
+            }
+            Ok(DnsAnswer { addrs, ttl: DEFAULT_TTL })
+        }
+        .boxed()
+    }
+}
+
+/// DNS-over-HTTPS (RFC 8484) resolver
+///
+/// Queries are POSTed in DNS wire format, so the endpoint's operator sees
+/// the looked-up names but the network in between does not.
+#[derive(Debug)]
+pub struct DohResolver {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl DohResolver {
+    /// Resolver for `endpoint`, reached through `proxy` when there is one
+    ///
+    /// Only `https://` endpoints are accepted: over plain HTTP the queries
+    /// would be readable, and forgeable, by anyone on the path.
+    pub fn new(endpoint: &str, proxy: Option<&ProxyConfig>, root_certificates: &[Vec<u8>], timeout: Duration) -> Result<Self, DnsError> {
+        let url = url::Url::parse(endpoint).map_err(|e| DnsError::Failed(format!("{}: {}", endpoint, e)))?;
+        if url.scheme() != "https" {
+            return Err(DnsError::Failed(format!("{}: not an HTTPS endpoint", endpoint)));
+        }
+
+        let mut builder = reqwest::Client::builder().timeout(timeout).https_only(true);
+        for pem in root_certificates {
+            let certificate = reqwest::Certificate::from_pem(pem).map_err(|e| DnsError::Failed(format!("invalid root certificate: {}", e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy.to_reqwest().map_err(|e| DnsError::Failed(e.to_string()))?);
+        }
+        let client = builder.build().map_err(|e| DnsError::Failed(e.to_string()))?;
+
+        Ok(Self { endpoint: endpoint.to_string(), client })
+    }
+
+    async fn query(&self, host: &str, record_type: u16) -> Result<DnsAnswer, DnsError> {
+        let response = self.client
+            .post(&self.endpoint)
+            .header("content-type", "application/dns-message")
+            .header("accept", "application/dns-message")
+            .body(encode_query(host, record_type)?)
+            .send()
+            .await
+            .map_err(|e| DnsError::Failed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(DnsError::Failed(format!("{} answered HTTP {}", self.endpoint, response.status())));
+        }
+
+        let body = response.bytes().await.map_err(|e| DnsError::Failed(e.to_string()))?;
+        decode_answer(&body, host)
+    }
+}
+
+impl DnsResolver for DohResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<DnsAnswer, DnsError>> {
+        async move {
+            let (v4, v6) = futures::future::join(self.query(host, TYPE_A), self.query(host, TYPE_AAAA)).await;
+
+            // One family missing is fine; IPv4 goes first as it is more often routable
+            let answers: Vec<DnsAnswer> = [v4.clone(), v6.clone()].into_iter().filter_map(Result::ok).collect();
+            let addrs: Vec<IpAddr> = answers.iter().flat_map(|a| a.addrs.iter().copied()).collect();
+            if !addrs.is_empty() {
+                let ttl = answers.iter().filter(|a| !a.addrs.is_empty()).map(|a| a.ttl).min().unwrap_or(DEFAULT_TTL);
+                return Ok(DnsAnswer { addrs, ttl });
+            }
+
+            match (v4, v6) {
+                (Err(DnsError::Failed(e)), _) | (_, Err(DnsError::Failed(e))) => Err(DnsError::Failed(e)),
+                _ => Err(DnsError::NotFound(host.to_string())),
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Build a recursive query for `host` in DNS wire format
+fn encode_query(host: &str, record_type: u16) -> Result<Vec<u8>, DnsError> {
+    // ID 0 keeps identical queries cacheable by HTTP caches (RFC 8484 §4.1)
+    let mut message = vec![0, 0, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 || !label.is_ascii() {
+            return Err(DnsError::Failed(format!("invalid host name: {}", host)));
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&record_type.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+/// Extract the A/AAAA records (and their smallest TTL) from a DNS response
+fn decode_answer(message: &[u8], host: &str) -> Result<DnsAnswer, DnsError> {
+    let malformed = || DnsError::Failed("malformed DNS response".to_string());
+    let u16_at = |pos: usize| message.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or_else(malformed);
+
+    let flags = u16_at(2)?;
+    match flags & 0x000f {
+        0 => {}
+        RCODE_NXDOMAIN => return Err(DnsError::NotFound(host.to_string())),
+        rcode => return Err(DnsError::Failed(format!("DNS server returned error code {}", rcode))),
+    }
+
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(message, pos).ok_or_else(malformed)? + 4;
+    }
+
+    let mut addrs = Vec::new();
+    let mut ttl: Option<Duration> = None;
+    for _ in 0..answers {
+        pos = skip_name(message, pos).ok_or_else(malformed)?;
+        let record_type = u16_at(pos)?;
+        let record_ttl = message.get(pos + 4..pos + 8).ok_or_else(malformed)?;
+        let record_ttl = Duration::from_secs(u32::from_be_bytes([record_ttl[0], record_ttl[1], record_ttl[2], record_ttl[3]]) as u64);
+        let length = u16_at(pos + 8)? as usize;
+        let data = message.get(pos + 10..pos + 10 + length).ok_or_else(malformed)?;
+        pos += 10 + length;
+
+        // CNAME records lead to the A/AAAA records that follow them
+        let addr = match (record_type, data.len()) {
+            (TYPE_A, 4) => IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => continue,
+        };
+        addrs.push(addr);
+        ttl = Some(ttl.map_or(record_ttl, |t| t.min(record_ttl)));
+    }
+
+    Ok(DnsAnswer { addrs, ttl: ttl.unwrap_or(DEFAULT_TTL) })
+}
+
+/// Position just after the (possibly compressed) name starting at `pos`
+fn skip_name(message: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let length = *message.get(pos)?;
+        match length {
+            0 => return Some(pos + 1),
+            // A compression pointer ends the name
+            l if l & 0xc0 == 0xc0 => return Some(pos + 2),
+            l => pos += 1 + l as usize,
+        }
+    }
+}
+
+/// Platform location of the saved DoH settings (e.g. `~/.local/share/faga/dns.json`)
+pub fn default_dns_path() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("dns.json"))
+}
+
+/// Read the saved DoH settings; `None` means the system resolver
+pub fn load_doh(path: &Path) -> Option<DohConfig> {
+    let data = fs::read(path).ok()?;
+    serde_json::from_slice(&data)
+        .map_err(|_| log::warn!("⚠️ Ignoring invalid DNS settings in {}", path.display()))
+        .ok()
+}
+
+/// Save the DoH settings, removing the file when DoH is disabled
+pub fn save_doh(path: &Path, doh: Option<&DohConfig>) -> std::io::Result<()> {
+    match doh {
+        Some(doh) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let data = serde_json::to_vec_pretty(doh).map_err(std::io::Error::from)?;
+            fs::write(path, data)
+        }
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Resolver installed on the HttpClient: cache, primary resolver, fallback
+///
+/// Clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct CachingResolver {
+    inner: Arc<ResolverState>,
+}
+
+#[derive(Debug)]
+struct ResolverState {
+    primary: Arc<dyn DnsResolver>,
+    fallback: Option<Arc<dyn DnsResolver>>,
+    cache: Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>,
+}
+
+impl CachingResolver {
+    pub fn new(primary: Arc<dyn DnsResolver>, fallback: Option<Arc<dyn DnsResolver>>) -> Self {
+        Self {
+            inner: Arc::new(ResolverState { primary, fallback, cache: Mutex::new(HashMap::new()) }),
+        }
+    }
+
+    /// Resolve `host`, from the cache while its records are still valid
+    pub async fn resolve_host(&self, host: &str) -> Result<Vec<IpAddr>, DnsError> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+
+        if let Some((addrs, expires)) = self.lock().get(&host) {
+            if *expires > Instant::now() {
+                return Ok(addrs.clone());
+            }
+        }
+
+        let answer = match (self.inner.primary.lookup(&host).await, &self.inner.fallback) {
+            (Ok(answer), _) => answer,
+            // A missing name is an answer; only an unreachable resolver falls back
+            (Err(DnsError::Failed(e)), Some(fallback)) => {
+                log::warn!("⚠️ DNS lookup of {} failed ({}), falling back", host, e);
+                fallback.lookup(&host).await?
+            }
+            (Err(e), _) => return Err(e),
+        };
+        if answer.addrs.is_empty() {
+            return Err(DnsError::NotFound(host));
+        }
+
+        let ttl = answer.ttl.clamp(MIN_TTL, MAX_TTL);
+        let mut cache = self.lock();
+        if cache.len() >= CACHE_CAPACITY {
+            let now = Instant::now();
+            cache.retain(|_, (_, expires)| *expires > now);
+        }
+        if cache.len() < CACHE_CAPACITY {
+            cache.insert(host, (answer.addrs.clone(), Instant::now() + ttl));
+        }
+        Ok(answer.addrs)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (Vec<IpAddr>, Instant)>> {
+        self.inner.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.resolve_host(name.as_str()).await?;
+            // The connector replaces port 0 with the URL's port
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Server};
+    use crate::network::http_client::{HttpClient, HttpClientConfig, HttpClientError};
+
+    /// Answer a DNS query in wire format from a table of IPv4 records
+    fn answer(query: &[u8], records: &[(&str, Ipv4Addr)]) -> Vec<u8> {
+        let question_end = skip_name(query, 12).unwrap() + 4;
+        let record_type = u16::from_be_bytes([query[question_end - 4], query[question_end - 3]]);
+
+        let mut labels = Vec::new();
+        let mut pos = 12;
+        while query[pos] != 0 {
+            let len = query[pos] as usize;
+            labels.push(String::from_utf8_lossy(&query[pos + 1..pos + 1 + len]).into_owned());
+            pos += 1 + len;
+        }
+        let name = labels.join(".");
+
+        let mut message = query[..question_end].to_vec();
+        message[2] = 0x81;
+        match records.iter().find(|(host, _)| *host == name) {
+            None => message[3] = 0x83,
+            Some((_, ip)) if record_type == TYPE_A => {
+                message[3] = 0x80;
+                message[7] = 1;
+                message.extend_from_slice(&[0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0x01, 0x2c, 0, 4]);
+                message.extend_from_slice(&ip.octets());
+            }
+            // Known name without AAAA records
+            Some(_) => message[3] = 0x80,
+        }
+        message
+    }
+
+    /// The test CA, which signed the `localhost` certificate of the stub DoH server
+    const TEST_CA: &[u8] = include_bytes!("../../tests/fixtures/tls/ca.pem");
+
+    /// Stub DoH server for `faga.test` over HTTPS, counting the queries it receives
+    async fn serve_doh() -> (String, Arc<AtomicUsize>) {
+        use tokio_rustls::rustls;
+
+        let certs = rustls_pemfile::certs(&mut &include_bytes!("../../tests/fixtures/tls/localhost.pem")[..]).unwrap();
+        let mut keys = rustls_pemfile::pkcs8_private_keys(&mut &include_bytes!("../../tests/fixtures/tls/localhost-key.pem")[..]).unwrap();
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs.into_iter().map(rustls::Certificate).collect(), rustls::PrivateKey(keys.remove(0)))
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let queries = Arc::new(AtomicUsize::new(0));
+        let counter = queries.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://localhost:{}/dns-query", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let Ok(tls) = acceptor.accept(tcp).await else { return };
+                    let service = service_fn(move |request: hyper::Request<Body>| {
+                        let counter = counter.clone();
+                        async move {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            let query = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                            let body = answer(&query, &[("faga.test", Ipv4Addr::LOCALHOST)]);
+                            Ok::<_, Infallible>(hyper::Response::new(Body::from(body)))
+                        }
+                    });
+                    let _ = hyper::server::conn::Http::new().serve_connection(tls, service).await;
+                });
+            }
+        });
+        (url, queries)
+    }
+
+    fn doh(endpoint: &str) -> DohResolver {
+        DohResolver::new(endpoint, None, &[TEST_CA.to_vec()], Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn test_doh_endpoint_must_be_https() {
+        assert!(matches!(DohResolver::new("http://127.0.0.1:1/dns-query", None, &[], Duration::from_secs(5)), Err(DnsError::Failed(_))));
+        assert!(matches!(DohResolver::new("ftp://127.0.0.1/dns-query", None, &[], Duration::from_secs(5)), Err(DnsError::Failed(_))));
+        assert!(DohResolver::new(DEFAULT_DOH_ENDPOINT, None, &[], Duration::from_secs(5)).is_ok());
+
+        let plain = DnsConfig::Doh(DohConfig { endpoint: "http://dns.example/dns-query".to_string(), fallback: true });
+        assert!(plain.resolver(None, &[], Duration::from_secs(5)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_doh_lookup_is_cached() {
+        let (endpoint, queries) = serve_doh().await;
+        let doh = doh(&endpoint);
+
+        let answer = doh.lookup("faga.test").await.unwrap();
+        assert_eq!(answer, DnsAnswer { addrs: vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], ttl: Duration::from_secs(300) });
+
+        let resolver = CachingResolver::new(Arc::new(doh), None);
+        queries.store(0, Ordering::SeqCst);
+        assert_eq!(resolver.resolve_host("FAGA.test.").await.unwrap(), vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+        assert_eq!(resolver.resolve_host("faga.test").await.unwrap(), vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+        assert_eq!(queries.load(Ordering::SeqCst), 2);
+
+        assert_eq!(resolver.resolve_host("missing.test").await, Err(DnsError::NotFound("missing.test".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_only_when_doh_is_unreachable() {
+        let (endpoint, _) = serve_doh().await;
+        let fallback = || -> Option<Arc<dyn DnsResolver>> { Some(Arc::new(SystemResolver)) };
+
+        let down = doh("https://127.0.0.1:1/dns-query");
+        let strict = CachingResolver::new(Arc::new(down), None);
+        assert!(matches!(strict.resolve_host("localhost").await, Err(DnsError::Failed(_))));
+
+        let down = doh("https://127.0.0.1:1/dns-query");
+        let lenient = CachingResolver::new(Arc::new(down), fallback());
+        assert!(lenient.resolve_host("localhost").await.unwrap().iter().all(|ip| ip.is_loopback()));
+
+        // NXDOMAIN from a working DoH server is final
+        let up = doh(&endpoint);
+        let resolver = CachingResolver::new(Arc::new(up), fallback());
+        assert_eq!(resolver.resolve_host("localhost").await, Err(DnsError::NotFound("localhost".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_client_resolves_through_doh() {
+        let (endpoint, _) = serve_doh().await;
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_| async { Ok::<_, Infallible>(hyper::Response::new(Body::from("hello"))) }))
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Doh(DohConfig { endpoint, fallback: false }),
+            root_certificates: vec![TEST_CA.to_vec()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(client.get(&format!("http://faga.test:{}/", port)).await.unwrap().text(), "hello");
+        assert!(matches!(
+            client.get(&format!("http://missing.test:{}/", port)).await,
+            Err(HttpClientError::DnsFailure(host)) if host == "missing.test"
+        ));
+    }
+}
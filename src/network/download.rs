@@ -0,0 +1,294 @@
+//! File downloads for FAGA Browser
+//! Streams non-renderable responses to the downloads directory
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use super::http_client::HttpClientError;
+use super::response::Response;
+use super::stream::{Progress, ResponseStream};
+
+/// Content types the browser renders itself instead of downloading
+const RENDERABLE_TYPES: &[&str] = &["text/html", "application/xhtml+xml", "text/plain"];
+
+/// Platform downloads folder (e.g. `~/Downloads`)
+pub fn default_download_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        std::env::var_os("USERPROFILE").map(|home| PathBuf::from(home).join("Downloads"))
+    } else {
+        std::env::var_os("XDG_DOWNLOAD_DIR")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Downloads")))
+    }
+}
+
+/// Whether a response should be saved to disk rather than displayed
+pub fn is_download(response: &Response) -> bool {
+    let attachment = response
+        .header("content-disposition")
+        .is_some_and(|value| value.trim_start().to_ascii_lowercase().starts_with("attachment"));
+    let mime = response.content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+
+    attachment || !RENDERABLE_TYPES.contains(&mime.as_str())
+}
+
+/// File name for a download: Content-Disposition, then the last URL segment
+pub fn file_name(response: &Response) -> String {
+    let from_header = response
+        .header("content-disposition")
+        .and_then(disposition_file_name);
+    let from_url = url::Url::parse(&response.url).ok().and_then(|url| {
+        url.path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()).map(|s| s.to_string()))
+            .map(|segment| String::from_utf8_lossy(&percent_decode(&segment)).into_owned())
+    });
+
+    let name = from_header.or(from_url).unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+
+    if name.is_empty() {
+        "download".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Extract `filename*=UTF-8''...` or `filename="..."` from a Content-Disposition value
+fn disposition_file_name(value: &str) -> Option<String> {
+    let params: Vec<(String, String)> = value
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().trim_matches('"').to_string()))
+        .collect();
+
+    // RFC 6266: the extended form wins
+    let extended = params.iter().find(|(name, _)| name == "filename*").and_then(|(_, value)| {
+        let (charset, rest) = value.split_once('\'')?;
+        let (_, encoded) = rest.split_once('\'')?;
+        if !charset.eq_ignore_ascii_case("utf-8") {
+            return None;
+        }
+        String::from_utf8(percent_decode(encoded)).ok()
+    });
+
+    extended.or_else(|| params.into_iter().find(|(name, _)| name == "filename").map(|(_, value)| value))
+}
+
+pub(crate) fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&input[i + 1..i + 3], 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// First free path for `name` in `dir`, adding " (1)", " (2)"... before the extension
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(pos) if pos > 0 => (&name[..pos], &name[pos..]),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+/// Errors that can occur while downloading a file
+#[derive(Debug, Clone)]
+pub enum DownloadError {
+    Http(HttpClientError),
+    Io(String),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(e) => write!(f, "{}", e),
+            Self::Io(e) => write!(f, "Could not write file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<HttpClientError> for DownloadError {
+    fn from(error: HttpClientError) -> Self {
+        Self::Http(error)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error.to_string())
+    }
+}
+
+/// A response that turned out to be a file, on its way to the downloads list
+///
+/// Clones share the stream, which the download takes once: the body is read
+/// from the request already made instead of fetching the URL again.
+#[derive(Debug, Clone)]
+pub struct PendingDownload {
+    /// URL of the response (after redirects)
+    pub url: String,
+    stream: Arc<Mutex<Option<ResponseStream>>>,
+}
+
+impl PendingDownload {
+    pub fn new(stream: ResponseStream) -> Self {
+        Self { url: stream.head.url.clone(), stream: Arc::new(Mutex::new(Some(stream))) }
+    }
+
+    /// The open response, the first time only
+    pub fn take(&self) -> Option<ResponseStream> {
+        self.stream.lock().ok()?.take()
+    }
+}
+
+/// A file being written to disk as its body arrives
+///
+/// Data goes to a `.part` file that is renamed once the body is complete.
+#[derive(Debug)]
+pub struct Download {
+    stream: ResponseStream,
+    file: tokio::fs::File,
+    part_path: PathBuf,
+    path: PathBuf,
+    finished: bool,
+}
+
+impl Download {
+    /// Create the file for an open response in `dir`, its body still to be read
+    pub async fn start(stream: ResponseStream, dir: &Path) -> Result<Self, DownloadError> {
+        if !stream.head.is_success() {
+            return Err(HttpClientError::HttpStatus(stream.head.status).into());
+        }
+
+        tokio::fs::create_dir_all(dir).await?;
+        let path = unique_path(dir, &file_name(&stream.head));
+        let part_path = path.with_file_name(format!(
+            "{}.part",
+            path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+        ));
+        let file = tokio::fs::File::create(&part_path).await?;
+
+        log::info!("⬇️ Downloading {} to {}", stream.head.url, path.display());
+
+        Ok(Self { stream, file, part_path, path, finished: false })
+    }
+
+    /// Final location of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.stream.progress()
+    }
+
+    /// Write the next chunk to disk; returns `false` once the file is complete
+    pub async fn advance(&mut self) -> Result<bool, DownloadError> {
+        match self.stream.chunk().await {
+            Ok(Some(chunk)) => {
+                self.file.write_all(&chunk).await?;
+                Ok(true)
+            }
+            Ok(None) => {
+                self.file.flush().await?;
+                tokio::fs::rename(&self.part_path, &self.path).await?;
+                self.finished = true;
+                log::info!("✅ Download complete: {}", self.path.display());
+                Ok(false)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&self.part_path).await;
+                Err(e.into())
+            }
+        }
+    }
+}
+
+impl Drop for Download {
+    /// A download dropped before completion (cancelled) leaves no partial file behind
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = std::fs::remove_file(&self.part_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(url: &str, content_type: &str, headers: &[(&str, &str)]) -> Response {
+        Response {
+            status: 200,
+            content_type: content_type.to_string(),
+            body: Vec::new(),
+            url: url.to_string(),
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            redirects: Vec::new(),
+            retries: Vec::new(),
+            http_version: "HTTP/1.1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_download_detection_and_names() {
+        let page = response("https://example.com/", "text/html; charset=utf-8", &[]);
+        assert!(!is_download(&page));
+
+        let archive = response("https://example.com/files/release%20v1.zip", "application/zip", &[]);
+        assert!(is_download(&archive));
+        assert_eq!(file_name(&archive), "release v1.zip");
+
+        let report = response("https://example.com/export", "text/html", &[
+            ("content-disposition", "attachment; filename=\"report.html\"; filename*=UTF-8''r%C3%A9sum%C3%A9.html"),
+        ]);
+        assert!(is_download(&report));
+        assert_eq!(file_name(&report), "résumé.html");
+
+        let sneaky = response("https://example.com/", "application/octet-stream", &[
+            ("content-disposition", "attachment; filename=\"../../.bashrc\""),
+        ]);
+        assert_eq!(file_name(&sneaky), "_.._.bashrc");
+    }
+
+    #[tokio::test]
+    async fn test_download_reads_the_open_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut export = response("https://example.com/export", "text/csv", &[("content-disposition", "attachment; filename=\"report.csv\"")]);
+        export.body = b"a,b\n1,2\n".to_vec();
+
+        let pending = PendingDownload::new(ResponseStream::buffered(export));
+        let mut download = Download::start(pending.take().unwrap(), dir.path()).await.unwrap();
+        assert!(pending.take().is_none());
+        while download.advance().await.unwrap() {}
+
+        assert_eq!(download.path(), dir.path().join("report.csv"));
+        assert_eq!(std::fs::read(download.path()).unwrap(), b"a,b\n1,2\n");
+        assert!(!dir.path().join("report.csv.part").exists());
+    }
+}
@@ -0,0 +1,442 @@
+//! HTTP Archive (HAR 1.2) support for FAGA Browser
+//! Records the exchanges the client has with the network, and replays them
+//! from an archive instead of the network so page loads can be reproduced offline.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use base64::Engine;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use super::response::Response;
+
+/// Root of a HAR file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+/// One request and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    /// ISO 8601 date, e.g. "2024-05-01T12:00:00.000Z"
+    pub started_date_time: String,
+    /// Total time in milliseconds
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    #[serde(default)]
+    pub cache: HarCache,
+    pub timings: HarTimings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    #[serde(default)]
+    pub query_string: Vec<HarNameValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL", default)]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// Response body: UTF-8 bodies are stored as is, others in base64
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// Cache state of an entry; left empty since only network exchanges are recorded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarCache {}
+
+/// Phases of the exchange in milliseconds, -1 when not measured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarTimings {
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+/// Errors that can occur while loading a HAR file
+#[derive(Debug, Clone, PartialEq)]
+pub enum HarError {
+    Io(String),
+    Invalid(String),
+}
+
+impl std::fmt::Display for HarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Could not read HAR file: {}", e),
+            Self::Invalid(e) => write!(f, "Invalid HAR file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HarError {}
+
+/// A request on its way to the network, completed into an entry once the body is read
+#[derive(Debug)]
+pub(crate) struct HarExchange {
+    started: SystemTime,
+    clock: Instant,
+    /// Time until the response headers arrived
+    wait: Option<Duration>,
+    request: HarRequest,
+}
+
+impl HarExchange {
+    pub(crate) fn new(method: &Method, url: &Url, headers: &HeaderMap, body: &Option<Vec<u8>>) -> Self {
+        let post_data = body.as_ref().map(|body| HarPostData {
+            mime_type: headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string(),
+            text: String::from_utf8_lossy(body).into_owned(),
+        });
+        let request = HarRequest {
+            method: method.to_string(),
+            url: archive_url(url),
+            http_version: String::new(),
+            cookies: Vec::new(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| HarNameValue { name: name.to_string(), value: String::from_utf8_lossy(value.as_bytes()).into_owned() })
+                .collect(),
+            query_string: url
+                .query_pairs()
+                .map(|(name, value)| HarNameValue { name: name.into_owned(), value: value.into_owned() })
+                .collect(),
+            post_data,
+            headers_size: -1,
+            body_size: body.as_ref().map_or(0, |b| b.len() as i64),
+        };
+        Self { started: SystemTime::now(), clock: Instant::now(), wait: None, request }
+    }
+
+    /// Mark the arrival of the response headers
+    pub(crate) fn head_received(&mut self) {
+        self.wait = Some(self.clock.elapsed());
+    }
+
+    fn into_entry(self, head: &Response, body: &[u8]) -> HarEntry {
+        let total = self.clock.elapsed();
+        let wait = self.wait.unwrap_or(total);
+        let (text, encoding) = match std::str::from_utf8(body) {
+            Ok(text) => (text.to_string(), None),
+            Err(_) => (base64::engine::general_purpose::STANDARD.encode(body), Some("base64".to_string())),
+        };
+        let status_text = StatusCode::from_u16(head.status).ok().and_then(|s| s.canonical_reason()).unwrap_or_default();
+
+        HarEntry {
+            started_date_time: iso8601(self.started),
+            time: millis(total),
+            request: HarRequest { http_version: head.http_version.clone(), ..self.request },
+            response: HarResponse {
+                status: head.status,
+                status_text: status_text.to_string(),
+                http_version: head.http_version.clone(),
+                cookies: Vec::new(),
+                headers: head.headers.iter().map(|(name, value)| HarNameValue { name: name.clone(), value: value.clone() }).collect(),
+                content: HarContent { size: body.len() as i64, mime_type: head.content_type.clone(), text: Some(text), encoding },
+                redirect_url: head.header("location").unwrap_or_default().to_string(),
+                headers_size: -1,
+                body_size: body.len() as i64,
+            },
+            cache: HarCache::default(),
+            timings: HarTimings { send: 0.0, wait: millis(wait), receive: millis(total.saturating_sub(wait)) },
+        }
+    }
+}
+
+/// Collects the exchanges of a client while recording is on
+#[derive(Debug, Default)]
+pub struct HarRecorder {
+    recording: AtomicBool,
+    entries: Mutex<Vec<HarEntry>>,
+}
+
+impl HarRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start recording, keeping the entries recorded so far
+    pub fn start(&self) {
+        self.recording.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.recording.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Number of entries recorded so far
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    pub(crate) fn record(&self, exchange: HarExchange, head: &Response, body: &[u8]) {
+        let entry = exchange.into_entry(head, body);
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// The recorded entries as a HAR document, oldest first
+    pub fn to_har(&self) -> Har {
+        let mut entries = self.entries.lock().unwrap().clone();
+        // Entries are added when their body ends, which is not the order requests started in
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+        Har {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator { name: "FAGA Browser".to_string(), version: env!("CARGO_PKG_VERSION").to_string() },
+                entries,
+            },
+        }
+    }
+
+    /// Write the recorded entries to a HAR file
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec_pretty(&self.to_har()).map_err(std::io::Error::from)?;
+        fs::write(path, data)
+    }
+}
+
+/// Responses served from a HAR file instead of the network
+///
+/// Requests are matched on method and URL. When a URL was recorded several
+/// times, its entries are served in order and the last one is then repeated.
+#[derive(Debug)]
+pub struct HarReplay {
+    entries: Vec<HarEntry>,
+    /// Entries already served for each method and URL
+    served: Mutex<HashMap<(String, String), usize>>,
+}
+
+impl HarReplay {
+    pub fn new(har: Har) -> Self {
+        Self { entries: har.log.entries, served: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, HarError> {
+        let data = fs::read(path).map_err(|e| HarError::Io(e.to_string()))?;
+        let har: Har = serde_json::from_slice(&data).map_err(|e| HarError::Invalid(e.to_string()))?;
+        Ok(Self::new(har))
+    }
+
+    /// Number of entries in the archive
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The recorded response to `method` `url`, if the archive has one
+    pub fn lookup(&self, method: &Method, url: &Url) -> Option<Response> {
+        let url = archive_url(url);
+        let matches: Vec<&HarEntry> = self.entries
+            .iter()
+            .filter(|e| e.request.method.eq_ignore_ascii_case(method.as_str()) && same_url(&e.request.url, &url))
+            .collect();
+        if matches.is_empty() {
+            return None;
+        }
+
+        let mut served = self.served.lock().unwrap();
+        let count = served.entry((method.to_string(), url)).or_insert(0);
+        let entry = matches[(*count).min(matches.len() - 1)];
+        *count += 1;
+        Some(to_response(entry))
+    }
+}
+
+fn to_response(entry: &HarEntry) -> Response {
+    let content = &entry.response.content;
+    let text = content.text.as_deref().unwrap_or_default();
+    let body = match content.encoding.as_deref() {
+        Some("base64") => base64::engine::general_purpose::STANDARD.decode(text.trim()).unwrap_or_default(),
+        _ => text.as_bytes().to_vec(),
+    };
+    let headers: Vec<(String, String)> = entry.response.headers
+        .iter()
+        .map(|h| (h.name.to_ascii_lowercase(), h.value.clone()))
+        .collect();
+    let content_type = Some(content.mime_type.clone())
+        .filter(|m| !m.is_empty())
+        .or_else(|| headers.iter().find(|(n, _)| n == "content-type").map(|(_, v)| v.clone()))
+        .unwrap_or_else(|| "text/html".to_string());
+
+    Response {
+        status: entry.response.status,
+        content_type,
+        body,
+        url: entry.request.url.clone(),
+        headers,
+        redirects: Vec::new(),
+        retries: Vec::new(),
+        http_version: entry.response.http_version.clone(),
+    }
+}
+
+/// URL as stored in the archive: fragments are never sent to servers
+fn archive_url(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Compare URLs written by other tools, which may not be normalized like ours
+fn same_url(recorded: &str, url: &str) -> bool {
+    match Url::parse(recorded) {
+        Ok(parsed) => archive_url(&parsed) == url,
+        Err(_) => recorded == url,
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Format a time as ISO 8601 in UTC, with milliseconds
+fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, rest / 3600, rest % 3600 / 60, rest % 60, since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_round_trip_and_replay_in_order() {
+        assert_eq!(iso8601(SystemTime::UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(SystemTime::UNIX_EPOCH + Duration::from_millis(951_782_400_250)), "2000-02-29T00:00:00.250Z");
+
+        let url = Url::parse("https://example.com/logo.png?v=2#top").unwrap();
+        let recorder = HarRecorder::new();
+        for body in [vec![0x89, b'P', b'N', b'G', 0xff], b"second".to_vec()] {
+            let mut exchange = HarExchange::new(&Method::GET, &url, &HeaderMap::new(), &None);
+            exchange.head_received();
+            let head = Response {
+                status: 200,
+                content_type: "image/png".to_string(),
+                body: Vec::new(),
+                url: url.to_string(),
+                headers: vec![("content-type".to_string(), "image/png".to_string())],
+                redirects: Vec::new(),
+                retries: Vec::new(),
+                http_version: "HTTP/1.1".to_string(),
+            };
+            recorder.record(exchange, &head, &body);
+        }
+
+        let har = recorder.to_har();
+        assert_eq!(har.log.entries[0].request.url, "https://example.com/logo.png?v=2");
+        assert_eq!(har.log.entries[0].request.query_string, vec![HarNameValue { name: "v".to_string(), value: "2".to_string() }]);
+        assert_eq!(har.log.entries[0].response.content.encoding.as_deref(), Some("base64"));
+
+        let json = serde_json::to_string(&har).unwrap();
+        assert!(json.contains("\"startedDateTime\"") && json.contains("\"redirectURL\""));
+        let replay = HarReplay::new(serde_json::from_str(&json).unwrap());
+
+        let first = replay.lookup(&Method::GET, &url).unwrap();
+        assert_eq!(first.body, vec![0x89, b'P', b'N', b'G', 0xff]);
+        assert_eq!(first.content_type, "image/png");
+        // The last entry is repeated once every recorded one has been served
+        assert_eq!(replay.lookup(&Method::GET, &url).unwrap().body, b"second");
+        assert_eq!(replay.lookup(&Method::GET, &url).unwrap().body, b"second");
+        assert!(replay.lookup(&Method::POST, &url).is_none());
+    }
+}
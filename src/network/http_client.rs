@@ -1,18 +1,64 @@
 //! HTTP/HTTPS Client for FAGA Browser
 //! Handles all network requests with proper error handling and caching support
 
-use reqwest::{Client, header::{HeaderMap, HeaderValue, USER_AGENT, ACCEPT, ACCEPT_LANGUAGE, ACCEPT_ENCODING}};
+use reqwest::{Client, Method, StatusCode, header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH}};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
-use super::response::Response;
+use super::auth::{AuthCache, AuthChallenge};
+use super::blocker::ContentBlocker;
+use super::cache::{CacheConfig, CacheLookup, HttpCache};
+use super::cookies::{default_cookie_path, CookieJar};
+use super::dns::{CachingResolver, DnsConfig, DnsError};
+use super::har::{HarExchange, HarRecorder, HarReplay};
+use super::https_only::{default_https_only_path, HttpsOnlyPolicy};
+use super::intercept::Interceptor;
+use super::loader::ResourceKind;
+use super::local;
+use super::privacy::PrivacyProfile;
+use super::protocol::{AltSvcCache, HttpProtocols};
+use super::proxy::ProxyConfig;
+use super::response::{RedirectHop, Response};
+use super::retry::{self, RetryAttempt, RetryPolicy};
 use super::request::Request;
+use super::stream::ResponseStream;
+use super::tls::{self, CertificateError, CertificateStore};
 
 /// Configuration for the HTTP client
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct HttpClientConfig {
     pub timeout: Duration,
     pub max_redirects: usize,
-    pub user_agent: String,
+    /// User-agent, languages, Referer policy and header order
+    pub privacy: PrivacyProfile,
+    /// How long an idle keep-alive connection stays in the pool
+    pub pool_idle_timeout: Duration,
+    /// Maximum number of idle connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// HTTP cache settings (None disables caching)
+    pub cache: Option<CacheConfig>,
+    /// File where persistent cookies are saved (None keeps them in memory)
+    pub cookie_file: Option<PathBuf>,
+    /// Proxy for all requests (None connects directly)
+    pub proxy: Option<ProxyConfig>,
+    /// Host name resolution (system resolver or DNS-over-HTTPS)
+    pub dns: DnsConfig,
+    /// File where the HTTPS-only mode and its exceptions are saved (None keeps them in memory)
+    pub https_only_file: Option<PathBuf>,
+    /// Filter lists checked before every request (None blocks nothing)
+    pub blocker: Option<Arc<ContentBlocker>>,
+    /// HTTP versions offered to servers
+    pub protocols: HttpProtocols,
+    /// Extra trusted CA certificates (PEM), on top of the bundled Mozilla roots
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Which failures are retried, and how long to wait in between (idempotent methods only)
+    pub retry: RetryPolicy,
+    /// HTTP Archive answering every request instead of the network (None uses the network)
+    pub replay: Option<Arc<HarReplay>>,
+    /// Hooks run around every request, in this order before sending and in reverse after receiving
+    pub interceptors: Vec<Arc<dyn Interceptor>>,
 }
 
 impl Default for HttpClientConfig {
@@ -20,15 +66,56 @@ impl Default for HttpClientConfig {
         Self {
             timeout: Duration::from_secs(30),
             max_redirects: 10,
-            user_agent: format!("FAGA Browser/0.1.0 (Windows NT 10.0; Win64; x64)"),
+            privacy: PrivacyProfile::default(),
+            pool_idle_timeout: Duration::from_secs(90),
+            pool_max_idle_per_host: 6,
+            cache: Some(CacheConfig::default()),
+            cookie_file: default_cookie_path(),
+            proxy: None,
+            dns: DnsConfig::default(),
+            https_only_file: default_https_only_path(),
+            blocker: None,
+            protocols: HttpProtocols::default(),
+            root_certificates: Vec::new(),
+            retry: RetryPolicy::default(),
+            replay: None,
+            interceptors: Vec::new(),
         }
     }
 }
 
+/// What `HttpClient::transport` builds from a configuration
+struct Transport {
+    client: Client,
+    default_headers: HeaderMap,
+    resolver: CachingResolver,
+    tls_config: Arc<rustls::ClientConfig>,
+}
+
 /// Main HTTP client for the browser
+///
+/// A single instance is meant to be shared (behind an `Arc`) by every tab so
+/// that the connection pool, TLS sessions and cookie jar survive navigations.
+#[derive(Debug)]
 pub struct HttpClient {
     client: Client,
     config: HttpClientConfig,
+    /// The resolver and TLS configuration of `client`, for our own TLS handshakes
+    resolver: CachingResolver,
+    tls_config: Arc<rustls::ClientConfig>,
+    /// Headers from the privacy profile, added to requests that lack them
+    default_headers: HeaderMap,
+    cache: Option<Arc<HttpCache>>,
+    cookies: Arc<CookieJar>,
+    https_only: Arc<HttpsOnlyPolicy>,
+    /// Credentials typed for HTTP authentication realms, kept for the session
+    auth: Arc<AuthCache>,
+    /// Origins that advertised HTTP/3
+    alt_svc: AltSvcCache,
+    /// Certificates presented by each host, and user exceptions
+    certificates: Arc<CertificateStore>,
+    /// Exchanges recorded for HAR export, while recording is on
+    har: Arc<HarRecorder>,
 }
 
 impl HttpClient {
@@ -39,103 +126,684 @@ impl HttpClient {
 
     /// Create a new HTTP client with custom configuration
     pub fn with_config(config: HttpClientConfig) -> Result<Self, HttpClientError> {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_str(&config.user_agent)
-            .map_err(|_| HttpClientError::InvalidHeader)?);
-        headers.insert(ACCEPT, HeaderValue::from_static("text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8"));
-        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.5,fr;q=0.3"));
-        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip, deflate, br"));
-
-        let client = Client::builder()
-            .default_headers(headers)
+        Self::build(config, Arc::new(CertificateStore::new()))
+    }
+
+    fn build(config: HttpClientConfig, certificates: Arc<CertificateStore>) -> Result<Self, HttpClientError> {
+        let Transport { client, default_headers, resolver, tls_config } = Self::transport(&config, &certificates)?;
+
+        let cache = config.cache.clone().map(|c| Arc::new(HttpCache::new(c)));
+        let cookies = Arc::new(match config.cookie_file {
+            Some(ref path) => CookieJar::open(path.clone()),
+            None => CookieJar::new(),
+        });
+
+        let https_only = Arc::new(match config.https_only_file {
+            Some(ref path) => HttpsOnlyPolicy::open(path.clone()),
+            None => HttpsOnlyPolicy::new(),
+        });
+
+        Ok(Self {
+            client,
+            config,
+            resolver,
+            tls_config,
+            default_headers,
+            cache,
+            cookies,
+            https_only,
+            auth: Arc::new(AuthCache::new()),
+            alt_svc: AltSvcCache::new(),
+            certificates,
+            har: Arc::new(HarRecorder::new()),
+        })
+    }
+
+    /// Build the reqwest client (proxy, DNS, TLS, protocols) and the privacy headers for `config`
+    fn transport(config: &HttpClientConfig, certificates: &Arc<CertificateStore>) -> Result<Transport, HttpClientError> {
+        let default_headers = config.privacy.default_headers()?;
+
+        let resolver = config.dns
+            .resolver(config.proxy.as_ref(), &config.root_certificates, config.timeout)
+            .map_err(|e| HttpClientError::ClientBuildError(e.to_string()))?;
+        let tls_config = tls::client_config(&config.root_certificates, config.protocols.alpn_protocols(), certificates.clone())
+            .map_err(HttpClientError::ClientBuildError)?;
+        let mut builder = Client::builder()
+            .use_preconfigured_tls(tls_config.clone())
+            .dns_resolver(Arc::new(resolver.clone()));
+        if let Some(ref proxy) = config.proxy {
+            log::info!("🧅 Using proxy {}", proxy);
+            builder = builder.proxy(proxy.to_reqwest()?);
+        }
+
+        // ALPN is set in the TLS configuration; this only tells hyper what to expect
+        builder = match config.protocols {
+            HttpProtocols::Http1Only => builder.http1_only(),
+            HttpProtocols::Http2 => builder.http2_adaptive_window(true),
+            HttpProtocols::Http2PriorKnowledge => builder.http2_prior_knowledge().http2_adaptive_window(true),
+            // HTTP/3 requests are marked one by one, everything else negotiates as usual
+            #[cfg(feature = "http3")]
+            HttpProtocols::Http3 => builder.http2_adaptive_window(true),
+            #[cfg(feature = "http3")]
+            HttpProtocols::Http3PriorKnowledge => builder.http3_prior_knowledge(),
+        };
+
+        let client = builder
             .timeout(config.timeout)
-            .redirect(reqwest::redirect::Policy::limited(config.max_redirects))
-            .cookie_store(true)
+            .pool_idle_timeout(config.pool_idle_timeout)
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .redirect(reqwest::redirect::Policy::none())
             .gzip(true)
             .brotli(true)
             .build()
             .map_err(|e| HttpClientError::ClientBuildError(e.to_string()))?;
+        Ok(Transport { client, default_headers, resolver, tls_config: Arc::new(tls_config) })
+    }
+
+    /// Create a client for new network settings (proxy, DNS, protocols,
+    /// privacy, filters...) that keeps this one's session: cookies, cache,
+    /// credentials, HTTPS-only exceptions, certificate exceptions and HAR recording
+    ///
+    /// The storage settings of `config` (cache, cookie and HTTPS-only files) are ignored.
+    pub fn reconfigured(&self, mut config: HttpClientConfig) -> Result<Self, HttpClientError> {
+        let Transport { client, default_headers, resolver, tls_config } = Self::transport(&config, &self.certificates)?;
+        config.cache = self.config.cache.clone();
+        config.cookie_file = self.config.cookie_file.clone();
+        config.https_only_file = self.config.https_only_file.clone();
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            resolver,
+            tls_config,
+            default_headers,
+            cache: self.cache.clone(),
+            cookies: self.cookies.clone(),
+            https_only: self.https_only.clone(),
+            auth: self.auth.clone(),
+            alt_svc: AltSvcCache::new(),
+            certificates: self.certificates.clone(),
+            har: self.har.clone(),
+        })
     }
 
-    /// Perform a GET request
-    pub async fn get(&self, url: &str) -> Result<Response, HttpClientError> {
-        let parsed_url = Url::parse(url)
-            .map_err(|e| HttpClientError::InvalidUrl(e.to_string()))?;
+    /// Create a client with the same configuration but its own cookie jar,
+    /// connection pool and memory-only cache, for private tabs
+    pub fn isolated(&self) -> Result<Self, HttpClientError> {
+        let mut config = self.config.clone();
+        if let Some(ref mut cache) = config.cache {
+            cache.disk_dir = None;
+        }
+        config.cookie_file = None;
+        // Certificate exceptions stay shared with normal tabs; HTTPS-only
+        // exceptions start from theirs but are kept in memory
+        Ok(Self { https_only: Arc::new(self.https_only.private_copy()), ..Self::build(config, self.certificates.clone())? })
+    }
 
-        log::info!("🌐 GET request to: {}", url);
+    /// HTTPS-only mode settings, for toggling it and managing exceptions
+    pub fn https_only(&self) -> &HttpsOnlyPolicy {
+        &self.https_only
+    }
 
-        let response = self.client
-            .get(parsed_url.as_str())
-            .send()
-            .await
-            .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
+    /// Certificates seen for each host, and the rejected ones trusted anyway
+    pub fn certificates(&self) -> &CertificateStore {
+        &self.certificates
+    }
 
-        let status = response.status().as_u16();
-        let headers = response.headers().clone();
-        let content_type = headers
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("text/html")
-            .to_string();
+    /// Whether requests for `url` go through the proxy
+    pub fn is_proxied(&self, url: &Url) -> bool {
+        self.config.proxy.as_ref().is_some_and(|proxy| !proxy.bypasses(url))
+    }
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| HttpClientError::ResponseReadError(e.to_string()))?;
+    /// Read the TLS version and cipher suite the server of `url` negotiates
+    ///
+    /// They are recorded in the certificate store for the origin, with the
+    /// chain. The handshake goes through the proxy like the requests do, or
+    /// else to the server the pages of the origin came from.
+    pub async fn inspect_tls(&self, url: &Url) -> Result<(), HttpClientError> {
+        let host = match (url.scheme(), url.host_str()) {
+            ("https", Some(host)) => host.trim_start_matches('[').trim_end_matches(']'),
+            _ => return Ok(()),
+        };
+        let port = url.port_or_known_default().unwrap_or(443);
 
-        log::info!("✅ Response received: {} bytes, status: {}", body.len(), status);
+        let handshake = async {
+            let tcp = match self.config.proxy {
+                Some(ref proxy) if self.is_proxied(url) => proxy.tunnel(host, port, &self.resolver).await?,
+                _ => {
+                    let addrs = match self.certificates.address(host, port) {
+                        Some(address) => vec![address],
+                        None => socket_addrs(&self.resolver, host, port).await?,
+                    };
+                    tokio::net::TcpStream::connect(&addrs[..])
+                        .await
+                        .map_err(|e| HttpClientError::ConnectionRefused(format!("{}: {}", url, e)))?
+                }
+            };
+            tls::inspect(self.tls_config.clone(), &self.certificates, host, port, tcp).await.map_err(HttpClientError::TlsError)
+        };
+        tokio::time::timeout(self.config.timeout, handshake).await.map_err(|_| HttpClientError::Timeout)?
+    }
 
-        Ok(Response {
-            status,
-            content_type,
-            body,
-            url: url.to_string(),
-        })
+    /// Recorder of the network exchanges, for exporting them as a HAR file
+    pub fn har(&self) -> &HarRecorder {
+        &self.har
+    }
+
+    /// HTTP authentication credentials, filled in from the credentials dialog
+    pub fn auth(&self) -> &AuthCache {
+        &self.auth
+    }
+
+    /// Filter lists applied by this client, if content blocking is on
+    pub fn blocker(&self) -> Option<&ContentBlocker> {
+        self.config.blocker.as_deref()
+    }
+
+    /// Cookie jar used by this client, for listing and deleting cookies
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookies
+    }
+
+    /// Perform a GET request
+    pub async fn get(&self, url: &str) -> Result<Response, HttpClientError> {
+        self.execute(Request::get(url)).await
     }
 
     /// Perform a POST request
     pub async fn post(&self, url: &str, body: &str) -> Result<Response, HttpClientError> {
-        let parsed_url = Url::parse(url)
+        self.execute(Request::post(url, body)).await
+    }
+
+    /// Perform a request from a Request object
+    pub async fn execute(&self, mut request: Request) -> Result<Response, HttpClientError> {
+        let (passed, answer) = self.before_send(&mut request)?;
+
+        let mut response = match answer {
+            Some(response) => response,
+            None => {
+                let (method, url, headers, body, context) = Self::prepare(&request)?;
+                match self.send(method, url, headers, body, &context, false).await? {
+                    Fetched::Complete(response) => response,
+                    Fetched::Streaming(stream) => stream.collect().await?,
+                }
+            }
+        };
+        self.after_receive(&request, &mut response, passed)?;
+
+        log::info!("✅ Response received: {} bytes, status: {}", response.body.len(), response.status);
+
+        Ok(response)
+    }
+
+    /// Send a request and return as soon as the final headers arrive
+    ///
+    /// The body is then read chunk by chunk from the returned stream. Fresh
+    /// cache hits come back as an already complete stream.
+    pub async fn open(&self, mut request: Request) -> Result<ResponseStream, HttpClientError> {
+        let (passed, answer) = self.before_send(&mut request)?;
+
+        let mut stream = match answer {
+            Some(response) => ResponseStream::buffered(response),
+            None => {
+                let (method, url, headers, body, context) = Self::prepare(&request)?;
+                match self.send(method, url, headers, body, &context, true).await? {
+                    Fetched::Complete(response) => ResponseStream::buffered(response),
+                    Fetched::Streaming(stream) => *stream,
+                }
+            }
+        };
+        self.after_receive(&request, &mut stream.head, passed)?;
+        Ok(stream)
+    }
+
+    /// Run the interceptors on a request, in order, until one answers it
+    ///
+    /// Returns how many interceptors let the request through, and the answer if one gave it.
+    fn before_send(&self, request: &mut Request) -> Result<(usize, Option<Response>), HttpClientError> {
+        for (index, interceptor) in self.config.interceptors.iter().enumerate() {
+            if let Some(response) = interceptor.before_send(request)? {
+                log::debug!("🪝 {} {} answered by {:?}", request.method, request.url, interceptor);
+                return Ok((index, Some(response)));
+            }
+        }
+        Ok((self.config.interceptors.len(), None))
+    }
+
+    /// Run the first `passed` interceptors on a response, last one first
+    fn after_receive(&self, request: &Request, response: &mut Response, passed: usize) -> Result<(), HttpClientError> {
+        for interceptor in self.config.interceptors[..passed].iter().rev() {
+            interceptor.after_receive(request, response)?;
+        }
+        Ok(())
+    }
+
+    /// Validate a Request and convert it to reqwest types
+    fn prepare(request: &Request) -> Result<PreparedRequest, HttpClientError> {
+        let parsed_url = Url::parse(&request.url)
             .map_err(|e| HttpClientError::InvalidUrl(e.to_string()))?;
 
-        log::info!("📤 POST request to: {}", url);
+        let method = match request.method.to_ascii_uppercase().as_str() {
+            "GET" => Method::GET,
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            "DELETE" => Method::DELETE,
+            "PATCH" => Method::PATCH,
+            "HEAD" => Method::HEAD,
+            "OPTIONS" => Method::OPTIONS,
+            _ => return Err(HttpClientError::UnsupportedMethod(request.method.clone())),
+        };
 
-        let response = self.client
-            .post(parsed_url.as_str())
-            .body(body.to_string())
-            .send()
-            .await
-            .map_err(|e| HttpClientError::RequestFailed(e.to_string()))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &request.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpClientError::InvalidHeader)?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| HttpClientError::InvalidHeader)?;
+            headers.insert(name, value);
+        }
+
+        if method == Method::GET {
+            log::info!("🌐 GET request to: {}", parsed_url);
+        } else {
+            log::info!("📤 {} request to: {}", method, parsed_url);
+        }
+
+        let first_party = request.first_party
+            .as_deref()
+            .and_then(|fp| Url::parse(fp).ok());
+
+        Ok((method, parsed_url, headers, request.body.clone(), RequestContext { first_party, kind: request.kind }))
+    }
+
+    /// Send a request, following redirects manually so every hop is recorded
+    ///
+    /// With `stream`, the final body is left unread and returned alongside the headers.
+    async fn send(&self, method: Method, url: Url, headers: HeaderMap, body: Option<Vec<u8>>, context: &RequestContext, stream: bool) -> Result<Fetched, HttpClientError> {
+        let first_party = context.first_party.as_ref();
+
+        // file: and data: URLs never reach the network
+        if local::is_local(&url) {
+            return local::fetch(&url, first_party).await.map(Fetched::Complete);
+        }
+
+        let mut method = method;
+        let mut headers = headers;
+        let mut body = body;
+        let mut current_url = url;
+        let mut redirects = Vec::new();
+        let mut retries = Vec::new();
+        // Times the current URL was retried with cached credentials
+        let mut auth_attempts = 0;
+
+        loop {
+            // In HTTPS-only mode, plain HTTP URLs are requested over HTTPS
+            let upgraded = self.https_only.upgrade(&current_url);
+            if let Some(ref https) = upgraded {
+                log::info!("🔒 Upgrading {} to HTTPS", current_url);
+                if redirects.last().is_some_and(|hop: &RedirectHop| hop.url == https.as_str()) {
+                    // The HTTPS page redirected back to HTTP
+                    return Err(HttpClientError::HttpsUpgradeFailed(current_url.to_string()));
+                }
+            }
+            let target = upgraded.unwrap_or_else(|| current_url.clone());
+
+            // Redirects are checked too, so a filter cannot be dodged through one
+            if let Some(filter) = self.blocker().and_then(|b| b.check(&target, first_party, context.kind)) {
+                log::info!("🛡️ Blocked {} ({})", target, filter);
+                return Err(HttpClientError::Blocked(target.to_string()));
+            }
+
+            // A failed HTTPS upgrade falls back to HTTP instead of being retried
+            let result = if target != current_url {
+                self.send_once(&method, &target, &headers, &body, first_party, stream).await
+            } else {
+                let (result, attempts) = self.send_with_retries(&method, &target, &headers, &body, first_party, stream).await;
+                retries.extend(attempts);
+                result.map_err(|e| e.after_retries(&retries))
+            };
+            let fetched = match result {
+                // An upgrade that failed while recording left only the HTTP exchange in the archive
+                Err(HttpClientError::NotInArchive(missing)) if target != current_url => {
+                    let recorded = self.config.replay.as_ref().is_some_and(|replay| replay.lookup(&method, &current_url).is_some());
+                    return Err(if recorded {
+                        HttpClientError::HttpsUpgradeFailed(current_url.to_string())
+                    } else {
+                        HttpClientError::NotInArchive(missing)
+                    });
+                }
+                Err(e) if target != current_url && e.is_connection_failure() => {
+                    log::warn!("⚠️ HTTPS upgrade of {} failed: {}", current_url, e);
+                    return Err(HttpClientError::HttpsUpgradeFailed(current_url.to_string()));
+                }
+                result => result?,
+            };
+
+            // A 401 is answered with cached credentials, or the user is asked for some
+            if fetched.head().status == StatusCode::UNAUTHORIZED.as_u16() {
+                let challenges = AuthChallenge::parse_all(fetched.head().header_all("www-authenticate"));
+                if let Some(challenge) = AuthChallenge::best(challenges) {
+                    // Cached credentials went with this request, preemptively or on a retry
+                    let sent = !headers.contains_key(AUTHORIZATION) && self.auth.covers(&target, &challenge.realm);
+                    // Only a stale nonce gets the same credentials sent again
+                    let retry = if sent { challenge.stale && auth_attempts < 2 } else { auth_attempts == 0 };
+                    if retry && self.auth.accept_challenge(&target, &challenge) {
+                        log::info!("🔑 Retrying {} with credentials for \"{}\"", target, challenge.realm);
+                        auth_attempts += 1;
+                        continue;
+                    }
+                    if sent {
+                        log::warn!("⚠️ Credentials for \"{}\" rejected by {}", challenge.realm, target);
+                        self.auth.forget(&target, &challenge.realm);
+                    }
+                    return Err(HttpClientError::AuthRequired(target.to_string(), Box::new(challenge)));
+                }
+            }
+
+            let response = match fetched {
+                Fetched::Complete(response) => response,
+                Fetched::Streaming(mut stream) => {
+                    stream.head.redirects = redirects;
+                    stream.head.retries = retries;
+                    return Ok(Fetched::Streaming(stream));
+                }
+            };
+
+            let location = match response.header("location") {
+                Some(location) if response.is_redirect() => location.to_string(),
+                _ => return Ok(Fetched::Complete(Response { redirects, retries, ..response })),
+            };
+            let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::FOUND);
+
+            if redirects.len() >= self.config.max_redirects {
+                return Err(HttpClientError::TooManyRedirects(redirects.len()));
+            }
+
+            let next_url = target.join(&location)
+                .map_err(|e| HttpClientError::InvalidUrl(e.to_string()))?;
 
+            if local::is_local(&next_url) {
+                return Err(HttpClientError::InvalidUrl(format!("refusing to redirect {} to {}", target, next_url)));
+            }
+
+            log::info!("↪️ Redirect {} → {}", status.as_u16(), next_url);
+
+            redirects.push(RedirectHop {
+                url: target.to_string(),
+                status: status.as_u16(),
+            });
+
+            // 303 always switches to GET, 301/302 do so for POST (like every browser)
+            if status == StatusCode::SEE_OTHER
+                || (method == Method::POST && (status == StatusCode::MOVED_PERMANENTLY || status == StatusCode::FOUND))
+            {
+                method = Method::GET;
+                body = None;
+                headers.remove(CONTENT_TYPE);
+                headers.remove(CONTENT_LENGTH);
+            }
+
+            // Never leak credentials to another origin
+            if next_url.origin() != target.origin() {
+                headers.remove(AUTHORIZATION);
+                headers.remove(COOKIE);
+            }
+
+            current_url = next_url;
+            auth_attempts = 0;
+        }
+    }
+
+    /// Send a request, again after a retryable failure if the method is idempotent
+    ///
+    /// Returns the last result, with the attempts that were retried.
+    async fn send_with_retries(&self, method: &Method, url: &Url, headers: &HeaderMap, body: &Option<Vec<u8>>, first_party: Option<&Url>, stream: bool) -> (Result<Fetched, HttpClientError>, Vec<RetryAttempt>) {
+        let policy = &self.config.retry;
+        let mut attempts = Vec::new();
+        let mut attempt = 1;
+
+        loop {
+            let result = self.send_once(method, url, headers, body, first_party, stream).await;
+            let (outcome, retry_after) = match &result {
+                Ok(fetched) if policy.retries_status(fetched.head().status) => (
+                    fetched.head().status.to_string(),
+                    fetched.head().header("retry-after").and_then(|v| retry::parse_retry_after(v, std::time::SystemTime::now())),
+                ),
+                Err(e) if policy.retries_error(e) => (e.to_string(), None),
+                _ => return (result, attempts),
+            };
+            let delay = match policy.delay(attempt, retry_after) {
+                Some(delay) if retry::is_idempotent(method) => delay,
+                _ => {
+                    log::warn!("⚠️ {} {} failed after {} attempt(s): {}", method, url, attempt, outcome);
+                    return (result, attempts);
+                }
+            };
+
+            log::warn!("🔁 {} {} failed ({}), attempt {} of {} in {} ms", method, url, outcome, attempt + 1, policy.max_attempts, delay.as_millis());
+            attempts.push(RetryAttempt { attempt, outcome, delay });
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// HTTP/3 version marker for a request to `url`, if it should go over QUIC
+    ///
+    /// QUIC cannot go through the proxy, so proxied requests always use TCP.
+    #[cfg(feature = "http3")]
+    fn http3_version(&self, url: &Url) -> Option<reqwest::Version> {
+        let direct = self.config.proxy.as_ref().is_none_or(|proxy| proxy.bypasses(url));
+        let wanted = match self.config.protocols {
+            HttpProtocols::Http3PriorKnowledge => true,
+            HttpProtocols::Http3 => self.alt_svc.has_h3(url),
+            _ => false,
+        };
+        (wanted && direct && url.scheme() == "https").then_some(reqwest::Version::HTTP_3)
+    }
+
+    #[cfg(not(feature = "http3"))]
+    fn http3_version(&self, _url: &Url) -> Option<reqwest::Version> {
+        None
+    }
+
+    /// Classify a send failure, blaming the proxy when it was in the way
+    fn classify(&self, error: reqwest::Error, url: &Url) -> HttpClientError {
+        let error = HttpClientError::from_reqwest(error, url);
+        match self.config.proxy {
+            Some(ref proxy) if !proxy.bypasses(url) => error.through_proxy(proxy),
+            _ => error,
+        }
+    }
+
+    /// Perform a single request/response exchange, going through the cache
+    ///
+    /// With `stream`, a non-redirect response is returned with its body still unread.
+    async fn send_once(&self, method: &Method, url: &Url, headers: &HeaderMap, body: &Option<Vec<u8>>, first_party: Option<&Url>, stream: bool) -> Result<Fetched, HttpClientError> {
+        if let Some(ref replay) = self.config.replay {
+            let response = replay.lookup(method, url).ok_or_else(|| HttpClientError::NotInArchive(url.to_string()))?;
+            log::debug!("📼 Replayed {} {} from the archive", method, url);
+            self.cookies.store_response_cookies(url, first_party, response.header_all("set-cookie"));
+            return Ok(Fetched::Complete(response));
+        }
+
+        let mut request_headers = headers.clone();
+        if !request_headers.contains_key(AUTHORIZATION) {
+            if let Some(value) = self.auth.authorization(method.as_str(), url).and_then(|a| HeaderValue::from_str(&a).ok()) {
+                request_headers.insert(AUTHORIZATION, value);
+            }
+        }
+        if !request_headers.contains_key(COOKIE) {
+            if let Some(value) = self.cookies.cookie_header(url, first_party).and_then(|c| HeaderValue::from_str(&c).ok()) {
+                request_headers.insert(COOKIE, value);
+            }
+        }
+        let mut request_headers = self.config.privacy.arrange(&request_headers, &self.default_headers, url);
+
+        // Vary is matched against the headers actually sent, defaults and cookies included
+        let vary_headers = request_headers.clone();
+        let mut revalidating = false;
+
+        if let (Some(cache), &Method::GET) = (&self.cache, method) {
+            match cache.lookup(url.as_str(), &vary_headers) {
+                CacheLookup::Fresh(response) => {
+                    if self.har.is_recording() {
+                        self.har.record(HarExchange::new(method, url, &request_headers, body), &response, &response.body);
+                    }
+                    return Ok(Fetched::Complete(response));
+                }
+                CacheLookup::Stale { etag, last_modified } => {
+                    if let Some(value) = etag.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                        request_headers.insert(IF_NONE_MATCH, value);
+                    }
+                    if let Some(value) = last_modified.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                        request_headers.insert(IF_MODIFIED_SINCE, value);
+                    }
+                    revalidating = true;
+                }
+                CacheLookup::Miss => {}
+            }
+        }
+
+        let build = |version: Option<reqwest::Version>| {
+            let mut builder = self.client
+                .request(method.clone(), url.as_str())
+                .headers(request_headers.clone());
+            if let Some(version) = version {
+                builder = builder.version(version);
+            }
+            if let Some(ref body) = body {
+                builder = builder.body(body.clone());
+            }
+            builder
+        };
+
+        let mut exchange = self.har.is_recording().then(|| HarExchange::new(method, url, &request_headers, body));
+        let raw = match self.http3_version(url) {
+            Some(version) => match build(Some(version)).send().await {
+                // QUIC may be blocked on this network: the origin goes back to TCP
+                Err(e) if self.alt_svc.has_h3(url) && (e.is_connect() || e.is_timeout()) => {
+                    log::warn!("⚠️ HTTP/3 to {} failed ({}), retrying over TCP", url, e);
+                    self.alt_svc.mark_broken(url);
+                    build(None).send().await
+                }
+                result => result,
+            },
+            None => build(None).send().await,
+        }
+        .map_err(|e| self.classify(e, url))?;
+        let mut response = Self::read_head(&raw);
+        if let (Some(address), "https", false) = (raw.remote_addr(), url.scheme(), self.is_proxied(url)) {
+            let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+            self.certificates.record_address(host, url.port_or_known_default().unwrap_or(443), address);
+        }
+        if let Some(ref mut exchange) = exchange {
+            exchange.head_received();
+        }
+        log::debug!("🔌 {} over {}", url, response.http_version);
+        self.alt_svc.store(url, response.header_all("alt-svc"));
+
+        self.cookies.store_response_cookies(url, first_party, response.header_all("set-cookie"));
+
+        let not_modified = revalidating && response.status == StatusCode::NOT_MODIFIED.as_u16();
+
+        if let Some(ref cache) = self.cache {
+            if !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) && response.status < 400 {
+                cache.invalidate(url.as_str());
+            }
+        }
+
+        if stream && !response.is_redirect() && !not_modified {
+            let mut stream = ResponseStream::new(response, raw);
+            if let (Some(cache), &Method::GET) = (&self.cache, method) {
+                // Stored once the caller has read the whole body
+                stream = stream.caching(cache.clone(), vary_headers);
+            }
+            if let Some(exchange) = exchange {
+                stream = stream.recording(self.har.clone(), exchange);
+            }
+            return Ok(Fetched::Streaming(Box::new(stream)));
+        }
+        response.body = Self::read_body(raw).await?;
+
+        let response = match self.cache {
+            Some(ref cache) if not_modified => {
+                log::info!("💾 Not modified, reusing cached copy: {}", url);
+                cache.freshen(url.as_str(), &response).unwrap_or(response)
+            }
+            Some(ref cache) if method == Method::GET => {
+                cache.store(url.as_str(), &vary_headers, &response);
+                response
+            }
+            _ => response,
+        };
+        // The archive gets the page as served, cached body included after a 304
+        if let Some(exchange) = exchange {
+            self.har.record(exchange, &response, &response.body);
+        }
+
+        Ok(Fetched::Complete(response))
+    }
+
+    /// Convert the status line and headers of a reqwest response into our Response structure
+    fn read_head(response: &reqwest::Response) -> Response {
         let status = response.status().as_u16();
-        let headers = response.headers().clone();
-        let content_type = headers
-            .get("content-type")
+        let url = response.url().to_string();
+        let http_version = format!("{:?}", response.version());
+        let headers: Vec<(String, String)> = response.headers()
+            .iter()
+            .map(|(name, value)| (name.as_str().to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+            .collect();
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
             .unwrap_or("text/html")
             .to_string();
 
-        let response_body = response
-            .text()
-            .await
-            .map_err(|e| HttpClientError::ResponseReadError(e.to_string()))?;
-
-        Ok(Response {
+        Response {
             status,
             content_type,
-            body: response_body,
-            url: url.to_string(),
-        })
+            body: Vec::new(),
+            url,
+            headers,
+            redirects: Vec::new(),
+            retries: Vec::new(),
+            http_version,
+        }
     }
 
-    /// Perform a request from a Request object
-    pub async fn execute(&self, request: Request) -> Result<Response, HttpClientError> {
-        match request.method.as_str() {
-            "GET" => self.get(&request.url).await,
-            "POST" => self.post(&request.url, &request.body.unwrap_or_default()).await,
-            _ => Err(HttpClientError::UnsupportedMethod(request.method)),
+    /// Read a whole response body
+    async fn read_body(response: reqwest::Response) -> Result<Vec<u8>, HttpClientError> {
+        response
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(HttpClientError::from_body)
+    }
+}
+
+/// Method, URL, headers, body and context of a validated Request
+type PreparedRequest = (Method, Url, HeaderMap, Option<Vec<u8>>, RequestContext);
+
+/// Where a request comes from and what it fetches
+#[derive(Debug)]
+struct RequestContext {
+    /// Top-level document, for third-party cookies and filters
+    first_party: Option<Url>,
+    kind: Option<ResourceKind>,
+}
+
+/// Outcome of an exchange: a fully read response, or one whose body is still arriving
+enum Fetched {
+    Complete(Response),
+    Streaming(Box<ResponseStream>),
+}
+
+impl Fetched {
+    /// Status line and headers
+    fn head(&self) -> &Response {
+        match self {
+            Self::Complete(response) => response,
+            Self::Streaming(stream) => &stream.head,
         }
     }
 }
@@ -146,8 +814,17 @@ impl Default for HttpClient {
     }
 }
 
+/// Addresses of `host:port`, for the connections made without reqwest
+pub(crate) async fn socket_addrs(resolver: &CachingResolver, host: &str, port: u16) -> Result<Vec<SocketAddr>, HttpClientError> {
+    let addrs = resolver.resolve_host(host).await.map_err(|e| match e {
+        DnsError::NotFound(name) => HttpClientError::DnsFailure(name),
+        DnsError::Failed(_) => HttpClientError::DnsFailure(host.to_string()),
+    })?;
+    Ok(addrs.into_iter().map(|ip| SocketAddr::new(ip, port)).collect())
+}
+
 /// Errors that can occur during HTTP operations
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HttpClientError {
     InvalidUrl(String),
     InvalidHeader,
@@ -155,8 +832,150 @@ pub enum HttpClientError {
     RequestFailed(String),
     ResponseReadError(String),
     UnsupportedMethod(String),
+    TooManyRedirects(usize),
     Timeout,
     NetworkError(String),
+    /// The host name could not be resolved
+    DnsFailure(String),
+    /// The TLS handshake failed
+    TlsError(String),
+    /// The certificate of this host was rejected, and the user has not trusted it
+    CertificateRejected(String, CertificateError),
+    /// Nothing is listening on the remote port
+    ConnectionRefused(String),
+    /// The server answered with an error status
+    HttpStatus(u16),
+    /// The proxy settings are invalid
+    InvalidProxy(String),
+    /// The proxy could not be reached or could not connect to the site
+    ProxyFailed(String),
+    /// HTTPS-only mode could not upgrade this plain HTTP URL
+    HttpsUpgradeFailed(String),
+    /// A content blocker filter matched the URL
+    Blocked(String),
+    /// The server at this URL asked for credentials the client does not have (or rejected them)
+    AuthRequired(String, Box<AuthChallenge>),
+    /// Replay mode is on and the archive has no response for this URL
+    NotInArchive(String),
+    /// The last error of a request that was retried, after the failed attempts before it
+    Retried(Box<HttpClientError>, Vec<RetryAttempt>),
+}
+
+impl HttpClientError {
+    /// The error of the last attempt, without the retries that came before it
+    pub fn last(&self) -> &Self {
+        match self {
+            Self::Retried(error, _) => error,
+            error => error,
+        }
+    }
+
+    /// Attempts that failed and were retried before this error
+    pub fn retries(&self) -> &[RetryAttempt] {
+        match self {
+            Self::Retried(_, attempts) => attempts,
+            _ => &[],
+        }
+    }
+
+    /// Keep the attempts that were retried with the error that ended them
+    fn after_retries(self, attempts: &[RetryAttempt]) -> Self {
+        if attempts.is_empty() {
+            self
+        } else {
+            Self::Retried(Box::new(self), attempts.to_vec())
+        }
+    }
+
+    /// Classify a failure while reading a response body
+    pub(crate) fn from_body(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::ResponseReadError(error.to_string())
+        }
+    }
+
+    /// Whether the server could not be talked to at all (as opposed to answering an error)
+    fn is_connection_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::TlsError(_) | Self::ConnectionRefused(_) | Self::Timeout | Self::NetworkError(_) | Self::RequestFailed(_)
+        )
+    }
+
+    /// Reword a connection failure that happened while going through `proxy`
+    fn through_proxy(self, proxy: &ProxyConfig) -> Self {
+        match self {
+            // The only connection made is to the proxy. A DNS failure stays one:
+            // it names the host that could not be resolved, proxy or page.
+            Self::ConnectionRefused(_) => Self::ProxyFailed(format!("{} refused the connection", proxy)),
+            Self::RequestFailed(e) | Self::NetworkError(e)
+                if ["socks", "tunnel", "proxy"].iter().any(|w| e.to_ascii_lowercase().contains(w)) =>
+            {
+                Self::ProxyFailed(e)
+            }
+            other => other,
+        }
+    }
+
+    /// Classify a reqwest failure by the types in its source chain
+    ///
+    /// Messages are only kept for display: they contain the URL, so a host
+    /// such as `tls.example.com` must not decide the kind of error.
+    fn from_reqwest(error: reqwest::Error, url: &Url) -> Self {
+        if error.is_timeout() {
+            return Self::Timeout;
+        }
+
+        let host = url.host_str().unwrap_or_default().to_string();
+        let mut chain = error.to_string();
+        let mut source = std::error::Error::source(&error);
+        while let Some(err) = source {
+            if let Some(dns) = err.downcast_ref::<DnsError>() {
+                return match dns {
+                    // The name that failed may be the proxy's rather than the page's
+                    DnsError::NotFound(name) => Self::DnsFailure(name.clone()),
+                    DnsError::Failed(_) => Self::DnsFailure(host),
+                };
+            }
+            if let Some(io) = err.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    std::io::ErrorKind::ConnectionRefused => {
+                        let port = url.port_or_known_default().unwrap_or_default();
+                        return Self::ConnectionRefused(format!("{}:{}", host, port));
+                    }
+                    std::io::ErrorKind::TimedOut => return Self::Timeout,
+                    _ => {}
+                }
+            }
+            match Self::rustls_error(err) {
+                Some(rustls::Error::InvalidCertificate(e)) => return Self::CertificateRejected(host, CertificateError::from(e)),
+                Some(tls) => return Self::TlsError(tls.to_string()),
+                None => {}
+            }
+            chain.push_str(": ");
+            chain.push_str(&err.to_string());
+            source = err.source();
+        }
+
+        if error.is_connect() {
+            Self::NetworkError(chain)
+        } else {
+            Self::RequestFailed(chain)
+        }
+    }
+
+    /// The rustls error wrapped in (possibly nested) io::Errors, whose source() skips it
+    fn rustls_error<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a rustls::Error> {
+        let mut inner = error;
+        loop {
+            if let Some(tls) = inner.downcast_ref::<rustls::Error>() {
+                return Some(tls);
+            }
+            inner = inner.downcast_ref::<std::io::Error>()?.get_ref()?;
+        }
+    }
 }
 
 impl std::fmt::Display for HttpClientError {
@@ -168,10 +987,1001 @@ impl std::fmt::Display for HttpClientError {
             Self::RequestFailed(e) => write!(f, "Request failed: {}", e),
             Self::ResponseReadError(e) => write!(f, "Response read error: {}", e),
             Self::UnsupportedMethod(m) => write!(f, "Unsupported HTTP method: {}", m),
+            Self::TooManyRedirects(n) => write!(f, "Too many redirects ({})", n),
             Self::Timeout => write!(f, "Request timeout"),
             Self::NetworkError(e) => write!(f, "Network error: {}", e),
+            Self::DnsFailure(host) => write!(f, "Could not resolve host: {}", host),
+            Self::TlsError(e) => write!(f, "TLS error: {}", e),
+            Self::CertificateRejected(host, e) => write!(f, "Certificate rejected for {}: {}", host, e),
+            Self::ConnectionRefused(addr) => write!(f, "Connection refused: {}", addr),
+            Self::HttpStatus(status) => write!(f, "HTTP error: {}", status),
+            Self::InvalidProxy(e) => write!(f, "Invalid proxy: {}", e),
+            Self::ProxyFailed(e) => write!(f, "Proxy error: {}", e),
+            Self::HttpsUpgradeFailed(url) => write!(f, "HTTPS is not available for {}", url),
+            Self::Blocked(url) => write!(f, "Blocked by a filter: {}", url),
+            Self::AuthRequired(url, challenge) => write!(f, "Authentication required for \"{}\" at {}", challenge.realm, url),
+            Self::NotInArchive(url) => write!(f, "Not in the HTTP archive: {}", url),
+            Self::Retried(error, attempts) => write!(f, "{} (after {} attempts)", error, attempts.len() + 1),
         }
     }
 }
 
 impl std::error::Error for HttpClientError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Server};
+    use crate::network::auth::Credentials;
+
+    /// Spawn a local HTTP server answering with `handler`, counting the requests it gets
+    fn serve<F>(handler: F) -> (String, Arc<AtomicUsize>)
+    where
+        F: Fn(hyper::Request<Body>) -> hyper::Response<Body> + Send + Sync + 'static,
+    {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(handler);
+        let counter = hits.clone();
+
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let response = handler(request);
+                    async move { Ok::<_, Infallible>(response) }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        (url, hits)
+    }
+
+    fn test_client() -> HttpClient {
+        HttpClient::with_config(HttpClientConfig {
+            cache: Some(CacheConfig { memory_capacity: 1 << 20, disk_capacity: 0, disk_dir: None }),
+            cookie_file: None,
+            https_only_file: None,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_methods_headers_and_bodies() {
+        // Echo the method, a custom header, the content type and the body
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|request: hyper::Request<Body>| async move {
+                let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or("-").to_string();
+                let mut echo = format!("{} {} {} ", request.method(), header("x-faga"), header("content-type")).into_bytes();
+                echo.extend_from_slice(&hyper::body::to_bytes(request.into_body()).await.unwrap());
+                Ok::<_, Infallible>(hyper::Response::new(Body::from(echo)))
+            }))
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}/", server.local_addr());
+        tokio::spawn(server);
+        let client = test_client();
+
+        for method in ["PUT", "DELETE", "PATCH", "OPTIONS"] {
+            let request = Request { method: method.to_string(), ..Request::get(&url) }.with_header("X-Faga", "1");
+            assert_eq!(client.execute(request).await.unwrap().text(), format!("{} 1 - ", method));
+        }
+
+        let request = Request::get(&url).with_bytes(vec![0xde, 0xad], "application/octet-stream");
+        let request = Request { method: "post".to_string(), ..request };
+        let response = client.execute(request).await.unwrap();
+        assert_eq!(response.body, b"POST - application/octet-stream \xde\xad");
+
+        // HEAD has no body, whatever the server writes
+        let head = client.execute(Request { method: "HEAD".to_string(), ..Request::get(&url) }).await.unwrap();
+        assert_eq!(head.status, 200);
+        assert!(head.body.is_empty());
+
+        let unknown = Request { method: "BREW".to_string(), ..Request::get(&url) };
+        assert_eq!(client.execute(unknown).await.unwrap_err(), HttpClientError::UnsupportedMethod("BREW".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy() {
+        // A plain HTTP proxy receives the absolute URL in the request line
+        let (proxy_url, proxied) = serve(|request| hyper::Response::new(Body::from(request.uri().to_string())));
+        let (direct_url, _) = serve(|_| hyper::Response::new(Body::from("direct")));
+
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            proxy: Some(ProxyConfig::new(&proxy_url).unwrap().with_bypass(["127.0.0.1"])),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let response = client.get("http://faga.test/page?q=1").await.unwrap();
+        assert_eq!(response.text(), "http://faga.test/page?q=1");
+        assert_eq!(client.get(&direct_url).await.unwrap().text(), "direct");
+        assert_eq!(proxied.load(Ordering::SeqCst), 1);
+
+        let unreachable = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            proxy: Some(ProxyConfig::new("socks5h://127.0.0.1:1").unwrap()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(unreachable.get("http://faga.test/").await, Err(HttpClientError::ProxyFailed(_))));
+
+        // A name that does not resolve stays a DNS failure, naming the proxy's host
+        let unresolved = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Custom(Arc::new(NoSuchHost)),
+            proxy: Some(ProxyConfig::new("http://proxy.faga.test:8080").unwrap()),
+            retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            unresolved.get("http://faga.test/").await.unwrap_err(),
+            HttpClientError::DnsFailure("proxy.faga.test".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_survives_a_proxy_change() {
+        let (url, _) = serve(|request| match request.headers().get("cookie") {
+            Some(cookie) => hyper::Response::new(Body::from(cookie.to_str().unwrap().to_string())),
+            // No Max-Age nor Expires: a session cookie, never written to disk
+            None => hyper::Response::builder().header("set-cookie", "session=42; Path=/").body(Body::empty()).unwrap(),
+        });
+        let (proxy_url, proxied) = serve(|_| hyper::Response::new(Body::from("proxied")));
+        let client = test_client();
+        client.get(&url).await.unwrap();
+
+        let proxied_client = client
+            .reconfigured(HttpClientConfig {
+                proxy: Some(ProxyConfig::new(&proxy_url).unwrap().with_bypass(["127.0.0.1"])),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(proxied_client.get(&url).await.unwrap().text(), "session=42");
+        assert_eq!(proxied_client.get("http://faga.test/").await.unwrap().text(), "proxied");
+        assert_eq!(proxied.load(Ordering::SeqCst), 1);
+        // The storage settings are the original client's, not the defaults
+        assert!(Arc::ptr_eq(proxied_client.cache.as_ref().unwrap(), client.cache.as_ref().unwrap()));
+        assert_eq!(proxied_client.config.cookie_file, None);
+    }
+
+    #[tokio::test]
+    async fn test_uniform_profile_headers() {
+        let (url, _) = serve(|request| {
+            let names: Vec<&str> = request.headers().keys().map(|name| name.as_str()).collect();
+            let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or("-").to_string();
+            hyper::Response::new(Body::from(format!(
+                "{}\n{}\n{}\n{}",
+                names.join(","), header("user-agent"), header("accept-language"), header("referer")
+            )))
+        });
+        let client = test_client();
+
+        let request = Request::get(&url).with_referrer("https://news.example/story?id=7#top");
+        let text = client.execute(request).await.unwrap().text();
+        let lines: Vec<&str> = text.lines().collect();
+
+        let names: Vec<&str> = lines[0].split(',').filter(|n| *n != "host").collect();
+        assert_eq!(&names[..4], &["user-agent", "accept", "accept-language", "accept-encoding"]);
+        assert_eq!(lines[1], crate::network::privacy::UNIFORM_USER_AGENT);
+        assert_eq!(lines[2], "en-US,en;q=0.5");
+        // Cross-site and HTTPS → HTTP: no Referer at all
+        assert_eq!(lines[3], "-");
+
+        let text = client.execute(Request::get(&url).with_referrer("http://news.example/story?id=7")).await.unwrap().text();
+        assert_eq!(text.lines().nth(3), Some("http://news.example/"));
+    }
+
+    /// Resolves every host to the loopback address
+    #[derive(Debug)]
+    struct Loopback;
+
+    impl crate::network::dns::DnsResolver for Loopback {
+        fn lookup<'a>(&'a self, _host: &'a str) -> futures::future::BoxFuture<'a, Result<crate::network::dns::DnsAnswer, crate::network::dns::DnsError>> {
+            Box::pin(async {
+                Ok(crate::network::dns::DnsAnswer { addrs: vec![std::net::Ipv4Addr::LOCALHOST.into()], ttl: Duration::from_secs(60) })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_https_only_upgrade_and_exceptions() {
+        // A plain HTTP server cannot complete the TLS handshake of the upgraded request
+        let (url, hits) = serve(|_| hyper::Response::new(Body::from("insecure")));
+        let url = url.replace("127.0.0.1", "plain.test");
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Custom(Arc::new(Loopback)),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(client.get(&url).await.unwrap().text(), "insecure");
+
+        client.https_only().set_enabled(true);
+        assert_eq!(client.get(&url).await.err(), Some(HttpClientError::HttpsUpgradeFailed(format!("{}/", url))));
+
+        client.https_only().allow_http("plain.test");
+        assert_eq!(client.get(&url).await.unwrap().text(), "insecure");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // A certificate that is not trusted is a warning, not an unavailable HTTPS
+        let forged = serve_tls(&[b"http/1.1"]).await.replace("https://localhost", "http://forged.test");
+        assert_eq!(
+            client.get(&forged).await.err(),
+            Some(HttpClientError::CertificateRejected("forged.test".to_string(), CertificateError::Untrusted))
+        );
+    }
+
+    #[test]
+    fn test_private_https_exceptions_stay_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("https_only.json");
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: Some(path.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        client.https_only().set_enabled(true);
+        client.https_only().allow_http("public.test");
+        let saved = std::fs::read_to_string(&path).unwrap();
+
+        // A private tab starts from the saved settings, and keeps its own exceptions to itself
+        let private = client.isolated().unwrap();
+        assert!(private.https_only().is_enabled());
+        private.https_only().allow_http("private.test");
+        assert_eq!(private.https_only().exceptions(), vec!["private.test", "public.test"]);
+        assert_eq!(client.https_only().exceptions(), vec!["public.test"]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), saved);
+        assert!(!saved.contains("private.test"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_fresh_responses() {
+        let (url, hits) = serve(|_| {
+            hyper::Response::builder()
+                .header("cache-control", "max-age=60")
+                .body(Body::from("cached"))
+                .unwrap()
+        });
+        let client = test_client();
+
+        assert_eq!(client.get(&url).await.unwrap().text(), "cached");
+        assert_eq!(client.get(&url).await.unwrap().text(), "cached");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_revalidates_with_etag() {
+        let (url, hits) = serve(|request| {
+            if request.headers().get("if-none-match").map(|v| v.as_bytes()) == Some(b"\"v1\"") {
+                return hyper::Response::builder().status(304).body(Body::empty()).unwrap();
+            }
+            hyper::Response::builder()
+                .header("cache-control", "no-cache")
+                .header("etag", "\"v1\"")
+                .body(Body::from("validated"))
+                .unwrap()
+        });
+        let client = test_client();
+
+        assert_eq!(client.get(&url).await.unwrap().text(), "validated");
+        let revalidated = client.get(&url).await.unwrap();
+        assert_eq!(revalidated.status, 200);
+        assert_eq!(revalidated.text(), "validated");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_varies_on_sent_cookies() {
+        let (url, hits) = serve(|request| match request.uri().path() {
+            "/login" => hyper::Response::builder().header("set-cookie", "session=1; Path=/").body(Body::empty()).unwrap(),
+            _ => {
+                let cookie = request.headers().get("cookie").map(|v| v.to_str().unwrap().to_string());
+                hyper::Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .header("vary", "Cookie")
+                    .body(Body::from(cookie.unwrap_or_else(|| "anonymous".to_string())))
+                    .unwrap()
+            }
+        });
+        let client = test_client();
+        let page = format!("{}/page", url);
+
+        assert_eq!(client.get(&page).await.unwrap().text(), "anonymous");
+        client.get(&format!("{}/login", url)).await.unwrap();
+        // The cookie added by the jar makes the stored copy unsuitable
+        assert_eq!(client.get(&page).await.unwrap().text(), "session=1");
+        assert_eq!(client.get(&page).await.unwrap().text(), "session=1");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cookies_round_trip() {
+        let (url, _) = serve(|request| match request.headers().get("cookie") {
+            Some(cookie) => hyper::Response::new(Body::from(cookie.to_str().unwrap().to_string())),
+            None => hyper::Response::builder().header("set-cookie", "visited=yes; Path=/").body(Body::empty()).unwrap(),
+        });
+        let client = test_client();
+
+        client.get(&url).await.unwrap();
+        assert_eq!(client.get(&url).await.unwrap().text(), "visited=yes");
+        assert_eq!(client.cookie_jar().list().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_challenge_and_retry() {
+        let (url, hits) = serve(|request| match request.headers().get("authorization").map(|v| v.to_str().unwrap()) {
+            // "faga:secret"
+            Some("Basic ZmFnYTpzZWNyZXQ=") => hyper::Response::new(Body::from("welcome")),
+            _ => hyper::Response::builder()
+                .status(401)
+                .header("www-authenticate", "Basic realm=\"admin\", charset=\"UTF-8\"")
+                .body(Body::empty())
+                .unwrap(),
+        });
+        let client = test_client();
+        let page = format!("{}/admin/index.html", url);
+
+        let challenge = match client.get(&page).await {
+            Err(HttpClientError::AuthRequired(challenged, challenge)) if challenged == page => challenge,
+            other => panic!("expected an auth challenge, got {:?}", other.map(|r| r.status)),
+        };
+        assert_eq!(challenge.realm, "admin");
+
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // Wrong credentials go out preemptively once; the 401 they get is final
+        let page_url = Url::parse(&page).unwrap();
+        let wrong = Credentials { username: "faga".to_string(), password: "nope".to_string() };
+        client.auth().set_credentials(&page_url, &challenge, wrong);
+        assert!(matches!(client.get(&page).await, Err(HttpClientError::AuthRequired(..))));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(client.auth().authorization("GET", &page_url), None);
+
+        let right = Credentials { username: "faga".to_string(), password: "secret".to_string() };
+        client.auth().set_credentials(&page_url, &challenge, right);
+        assert_eq!(client.get(&format!("{}/admin/users", url)).await.unwrap().text(), "welcome");
+        // Sent preemptively inside the protection space
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Outside it, the challenge is answered with the realm's credentials
+        assert_eq!(client.get(&format!("{}/reports/today", url)).await.unwrap().text(), "welcome");
+        assert_eq!(hits.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_recorded_and_limited() {
+        let (url, hits) = serve(|request| {
+            let path = request.uri().path().to_string();
+            let redirect = |location: String| hyper::Response::builder().status(301).header("location", location).body(Body::empty()).unwrap();
+            match path.as_str() {
+                "/old" => redirect("/new".to_string()),
+                "/loop-a" => redirect("/loop-b".to_string()),
+                "/loop-b" => redirect("/loop-a".to_string()),
+                _ => match path.strip_prefix("/hop/").and_then(|n| n.parse::<u32>().ok()) {
+                    Some(n) => redirect(format!("/hop/{}", n + 1)),
+                    None => hyper::Response::builder().header("x-page", "new").body(Body::from("moved")).unwrap(),
+                },
+            }
+        });
+        let client = HttpClient::with_config(HttpClientConfig { cache: None, cookie_file: None, https_only_file: None, max_redirects: 3, ..Default::default() }).unwrap();
+
+        let response = client.get(&format!("{}/old", url)).await.unwrap();
+        assert_eq!(response.url, format!("{}/new", url));
+        assert_eq!(response.redirects.len(), 1);
+        assert_eq!(response.redirects[0].status, 301);
+        assert_eq!(response.requested_url(), format!("{}/old", url));
+        assert_eq!(response.header("X-Page"), Some("new"));
+        assert_eq!(response.http_version, "HTTP/1.1");
+
+        // The redirect limit stops endless chains after max_redirects hops
+        hits.store(0, Ordering::SeqCst);
+        assert_eq!(client.get(&format!("{}/hop/0", url)).await.unwrap_err(), HttpClientError::TooManyRedirects(3));
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        // and redirect loops the same way
+        hits.store(0, Ordering::SeqCst);
+        assert_eq!(client.get(&format!("{}/loop-a", url)).await.unwrap_err(), HttpClientError::TooManyRedirects(3));
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_har_record_and_replay() {
+        let (url, hits) = serve(|request| match request.uri().path() {
+            "/old" => hyper::Response::builder().status(302).header("location", "/page").body(Body::empty()).unwrap(),
+            _ => hyper::Response::builder().header("content-type", "text/html").body(Body::from("<p>recorded</p>")).unwrap(),
+        });
+        let client = HttpClient::with_config(HttpClientConfig { cache: None, cookie_file: None, https_only_file: None, ..Default::default() }).unwrap();
+
+        client.get(&format!("{}/ignored", url)).await.unwrap();
+        client.har().start();
+        client.get(&format!("{}/old", url)).await.unwrap();
+        client.post(&format!("{}/form", url), "a=1").await.unwrap();
+        client.open(Request::get(&format!("{}/streamed", url))).await.unwrap().collect().await.unwrap();
+        assert_eq!(client.har().len(), 4);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.har");
+        client.har().save(&path).unwrap();
+        let requests = hits.load(Ordering::SeqCst);
+
+        let replaying = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            replay: Some(Arc::new(crate::network::har::HarReplay::load(&path).unwrap())),
+            ..Default::default()
+        })
+        .unwrap();
+        let response = replaying.get(&format!("{}/old", url)).await.unwrap();
+        assert_eq!(response.body, b"<p>recorded</p>");
+        assert_eq!(response.redirects.len(), 1);
+        assert_eq!(replaying.post(&format!("{}/form", url), "a=1").await.unwrap().status, 200);
+        assert_eq!(
+            replaying.get(&format!("{}/ignored", url)).await.unwrap_err(),
+            HttpClientError::NotInArchive(format!("{}/ignored", url))
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), requests);
+    }
+
+    #[tokio::test]
+    async fn test_har_records_cache_hits_and_revalidations() {
+        let (url, hits) = serve(|request| {
+            if request.uri().path() == "/fresh" {
+                return hyper::Response::builder().header("cache-control", "max-age=60").body(Body::from("cached")).unwrap();
+            }
+            if request.headers().contains_key("if-none-match") {
+                return hyper::Response::builder().status(304).body(Body::empty()).unwrap();
+            }
+            hyper::Response::builder()
+                .header("cache-control", "no-cache")
+                .header("etag", "\"v1\"")
+                .body(Body::from("validated"))
+                .unwrap()
+        });
+        let client = test_client();
+        client.get(&format!("{}/fresh", url)).await.unwrap();
+        client.get(&format!("{}/etag", url)).await.unwrap();
+
+        client.har().start();
+        client.get(&format!("{}/fresh", url)).await.unwrap();
+        client.get(&format!("{}/etag", url)).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Both pages are archived as the browser got them
+        let entries = client.har().to_har().log.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].request.url, format!("{}/fresh", url));
+        assert_eq!(entries[0].response.content.text.as_deref(), Some("cached"));
+        assert_eq!(entries[1].response.status, 200);
+        assert_eq!(entries[1].response.content.text.as_deref(), Some("validated"));
+    }
+
+    #[tokio::test]
+    async fn test_har_replays_failed_https_upgrades() {
+        let (url, _) = serve(|_| hyper::Response::new(Body::from("insecure")));
+        let url = format!("{}/", url.replace("127.0.0.1", "plain.test"));
+        let config = || HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Custom(Arc::new(Loopback)),
+            ..Default::default()
+        };
+        let client = HttpClient::with_config(config()).unwrap();
+        client.https_only().set_enabled(true);
+        client.har().start();
+        assert_eq!(client.get(&url).await.err(), Some(HttpClientError::HttpsUpgradeFailed(url.clone())));
+        client.https_only().allow_http("plain.test");
+        client.get(&url).await.unwrap();
+
+        let replaying = HttpClient::with_config(HttpClientConfig {
+            replay: Some(Arc::new(crate::network::har::HarReplay::new(client.har().to_har()))),
+            ..config()
+        })
+        .unwrap();
+        replaying.https_only().set_enabled(true);
+        // Only the HTTP page was recorded: the upgrade failed then too
+        assert_eq!(replaying.get(&url).await.err(), Some(HttpClientError::HttpsUpgradeFailed(url.clone())));
+        assert_eq!(
+            replaying.get(&format!("{}other", url)).await.err(),
+            Some(HttpClientError::NotInArchive(format!("{}other", url.replace("http:", "https:"))))
+        );
+        replaying.https_only().allow_http("plain.test");
+        assert_eq!(replaying.get(&url).await.unwrap().text(), "insecure");
+    }
+
+    /// Interceptor logging its calls, adding a header, and answering or blocking some paths itself
+    #[derive(Debug)]
+    struct Hook {
+        name: &'static str,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl Interceptor for Hook {
+        fn before_send(&self, request: &mut Request) -> Result<Option<Response>, HttpClientError> {
+            self.log.lock().unwrap().push(format!("{} before", self.name));
+            request.headers.insert(format!("x-{}", self.name), "1".to_string());
+            if request.url.ends_with(&format!("/blocked-by-{}", self.name)) {
+                return Err(HttpClientError::Blocked(request.url.clone()));
+            }
+            if !request.url.ends_with(&format!("/mocked-by-{}", self.name)) {
+                return Ok(None);
+            }
+            Ok(Some(Response {
+                status: 200,
+                content_type: "text/plain".to_string(),
+                body: b"mocked".to_vec(),
+                url: request.url.clone(),
+                headers: Vec::new(),
+                redirects: Vec::new(),
+                retries: Vec::new(),
+                http_version: "HTTP/1.1".to_string(),
+            }))
+        }
+
+        fn after_receive(&self, _request: &Request, response: &mut Response) -> Result<(), HttpClientError> {
+            self.log.lock().unwrap().push(format!("{} after", self.name));
+            response.headers.push(("x-seen-by".to_string(), self.name.to_string()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_run_in_order_and_short_circuit() {
+        // Echo the headers added by the interceptors
+        let (url, hits) = serve(|request| {
+            let mut added: Vec<&str> = request.headers().keys().map(|n| n.as_str()).filter(|n| n.starts_with("x-")).collect();
+            added.sort();
+            hyper::Response::new(Body::from(added.join(",")))
+        });
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let hook = |name| -> Arc<dyn Interceptor> { Arc::new(Hook { name, log: log.clone() }) };
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            interceptors: vec![hook("outer"), hook("inner")],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.body, b"x-inner,x-outer");
+        assert_eq!(response.header_all("x-seen-by"), vec!["inner", "outer"]);
+        assert_eq!(*log.lock().unwrap(), vec!["outer before", "inner before", "inner after", "outer after"]);
+
+        // The interceptors before the one that answered still see the response
+        log.lock().unwrap().clear();
+        let stream = client.open(Request::get(&format!("{}/mocked-by-inner", url))).await.unwrap();
+        assert_eq!(stream.head.header_all("x-seen-by"), vec!["outer"]);
+        assert_eq!(stream.collect().await.unwrap().body, b"mocked");
+        assert_eq!(*log.lock().unwrap(), vec!["outer before", "inner before", "outer after"]);
+
+        let blocked = format!("{}/blocked-by-outer", url);
+        assert_eq!(client.get(&blocked).await.unwrap_err(), HttpClientError::Blocked(blocked));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    /// Knows no host at all
+    #[derive(Debug)]
+    struct NoSuchHost;
+
+    impl crate::network::dns::DnsResolver for NoSuchHost {
+        fn lookup<'a>(&'a self, host: &'a str) -> futures::future::BoxFuture<'a, Result<crate::network::dns::DnsAnswer, DnsError>> {
+            Box::pin(async move { Err(DnsError::NotFound(host.to_string())) })
+        }
+    }
+
+    /// A client that keeps nothing on disk and never retries
+    fn classifying_client(dns: DnsConfig, timeout: Duration) -> HttpClient {
+        HttpClient::with_config(HttpClientConfig {
+            timeout,
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns,
+            retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_classified() {
+        // Bind then drop a listener so the port is known to be closed
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let client = classifying_client(DnsConfig::Custom(Arc::new(Loopback)), Duration::from_secs(5));
+        assert_eq!(
+            client.get(&format!("http://{}/", addr)).await.unwrap_err(),
+            HttpClientError::ConnectionRefused(format!("127.0.0.1:{}", addr.port()))
+        );
+
+        // A server that closes every connection without answering; the words in
+        // the host name do not make it a TLS error
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                drop(socket);
+            }
+        });
+        let error = client.get(&format!("http://tls.certificate.test:{}/", port)).await.unwrap_err();
+        assert!(matches!(error, HttpClientError::RequestFailed(_)), "{:?}", error);
+
+        let unknown = classifying_client(DnsConfig::Custom(Arc::new(NoSuchHost)), Duration::from_secs(5));
+        assert_eq!(
+            unknown.get("https://tls.example.test/").await.unwrap_err(),
+            HttpClientError::DnsFailure("tls.example.test".to_string())
+        );
+
+        // A server that accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+        let client = classifying_client(DnsConfig::System, Duration::from_millis(200));
+        assert_eq!(client.get(&format!("http://{}/", addr)).await.unwrap_err(), HttpClientError::Timeout);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_requests_are_retried() {
+        // Unavailable on the first request, asking to retry right away
+        let (url, hits) = serve({
+            let calls = AtomicUsize::new(0);
+            move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => hyper::Response::builder().status(503).header("retry-after", "0").body(Body::empty()).unwrap(),
+                _ => hyper::Response::new(Body::from("back")),
+            }
+        });
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            retry: RetryPolicy { base_delay: Duration::from_millis(10), ..Default::default() },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.text(), "back");
+        assert_eq!(response.retries, vec![RetryAttempt { attempt: 1, outcome: "503".to_string(), delay: Duration::ZERO }]);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // A POST is not sent twice
+        let (url, hits) = serve(|_| hyper::Response::builder().status(503).body(Body::empty()).unwrap());
+        assert_eq!(client.post(&url, "order=1").await.unwrap().status, 503);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // Refused connections are retried until max_attempts, waiting longer each time
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let started = std::time::Instant::now();
+        let error = client.get(&format!("http://{}/", addr)).await.unwrap_err();
+        assert!(matches!(error.last(), HttpClientError::ConnectionRefused(_)));
+        let attempts = error.retries();
+        assert_eq!(attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(attempts.iter().all(|a| a.outcome.starts_with("Connection refused")));
+        // Half fixed, half random: 5-10 ms, then 10-20 ms
+        assert!((Duration::from_millis(5)..=Duration::from_millis(10)).contains(&attempts[0].delay), "{:?}", attempts[0]);
+        assert!((Duration::from_millis(10)..=Duration::from_millis(20)).contains(&attempts[1].delay), "{:?}", attempts[1]);
+        assert!(started.elapsed() >= attempts[0].delay + attempts[1].delay);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_reports_progress() {
+        let (url, hits) = serve(|_| {
+            hyper::Response::builder()
+                .header("cache-control", "max-age=60")
+                .body(Body::from("x".repeat(64 * 1024)))
+                .unwrap()
+        });
+        let client = test_client();
+
+        let mut stream = client.open(Request::get(&url)).await.unwrap();
+        assert_eq!(stream.progress().total, Some(64 * 1024));
+        let mut received = 0;
+        while let Some(chunk) = stream.chunk().await.unwrap() {
+            received += chunk.len();
+            assert_eq!(stream.progress().received, received as u64);
+        }
+        assert_eq!(stream.progress().fraction(), Some(1.0));
+
+        let response = client.open(Request::get(&url)).await.unwrap().collect().await.unwrap();
+        assert_eq!(response.body.len(), 64 * 1024);
+
+        // Collecting the stream stored it in the cache
+        assert_eq!(client.get(&url).await.unwrap().body.len(), 64 * 1024);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    /// Spawn a local HTTPS server (certificate for `localhost`, signed by
+    /// `tests/fixtures/tls/ca.pem`) that answers with the request's HTTP version
+    /// Server side of the `localhost` test certificate, for the given TLS versions
+    fn tls_server_config(versions: &[&'static rustls::SupportedProtocolVersion]) -> rustls::ServerConfig {
+        let certs = rustls_pemfile::certs(&mut &include_bytes!("../../tests/fixtures/tls/localhost.pem")[..]).unwrap();
+        let mut keys = rustls_pemfile::pkcs8_private_keys(&mut &include_bytes!("../../tests/fixtures/tls/localhost-key.pem")[..]).unwrap();
+        rustls::ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(versions)
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(certs.into_iter().map(rustls::Certificate).collect(), rustls::PrivateKey(keys.remove(0)))
+            .unwrap()
+    }
+
+    async fn serve_tls(alpn: &[&[u8]]) -> String {
+        let mut config = tls_server_config(rustls::DEFAULT_VERSIONS);
+        config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+        serve_tls_with(config).await
+    }
+
+    /// Serve HTTPS with `config`, answering with the request's HTTP version
+    async fn serve_tls_with(config: rustls::ServerConfig) -> String {
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://localhost:{}/", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(tls) = acceptor.accept(tcp).await else { return };
+                    let service = service_fn(|request: hyper::Request<Body>| async move {
+                        Ok::<_, Infallible>(hyper::Response::new(Body::from(format!("{:?}", request.version()))))
+                    });
+                    let _ = hyper::server::conn::Http::new().serve_connection(tls, service).await;
+                });
+            }
+        });
+        url
+    }
+
+    fn tls_client(protocols: HttpProtocols) -> HttpClient {
+        HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Custom(Arc::new(Loopback)),
+            protocols,
+            root_certificates: vec![include_bytes!("../../tests/fixtures/tls/ca.pem").to_vec()],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http2_is_negotiated_with_alpn() {
+        let url = serve_tls(&[b"h2", b"http/1.1"]).await;
+
+        let response = tls_client(HttpProtocols::Http2).get(&url).await.unwrap();
+        assert_eq!(response.http_version, "HTTP/2.0");
+        assert_eq!(response.text(), "HTTP/2.0");
+
+        let response = tls_client(HttpProtocols::Http1Only).get(&url).await.unwrap();
+        assert_eq!(response.http_version, "HTTP/1.1");
+
+        // A server that only speaks HTTP/1.1 is not offered h2
+        let url = serve_tls(&[b"http/1.1"]).await;
+        assert_eq!(tls_client(HttpProtocols::Http2).get(&url).await.unwrap().http_version, "HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_http2_prior_knowledge_over_cleartext() {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_| async { Ok::<_, Infallible>(hyper::Response::new(Body::from("h2c"))) }))
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).http2_only(true).serve(make_service);
+        let url = format!("http://{}/", server.local_addr());
+        tokio::spawn(server);
+
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            protocols: HttpProtocols::Http2PriorKnowledge,
+            ..Default::default()
+        })
+        .unwrap();
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.http_version, "HTTP/2.0");
+        assert_eq!(response.text(), "h2c");
+    }
+
+    /// HTTP CONNECT and SOCKS5 proxy tunnelling every target to 127.0.0.1, logging the targets asked for
+    async fn serve_tunnel_proxy() -> (u16, Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let targets = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = targets.clone();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let (host, target_port) = if client.read_u8().await.unwrap() == 5 {
+                        let methods = client.read_u8().await.unwrap();
+                        client.read_exact(&mut vec![0; methods as usize]).await.unwrap();
+                        client.write_all(&[5, 0]).await.unwrap();
+                        let mut request = [0; 4];
+                        client.read_exact(&mut request).await.unwrap();
+                        let host = match request[3] {
+                            3 => {
+                                let mut name = vec![0; client.read_u8().await.unwrap() as usize];
+                                client.read_exact(&mut name).await.unwrap();
+                                String::from_utf8(name).unwrap()
+                            }
+                            _ => {
+                                let mut ip = [0; 4];
+                                client.read_exact(&mut ip).await.unwrap();
+                                std::net::Ipv4Addr::from(ip).to_string()
+                            }
+                        };
+                        let target_port = client.read_u16().await.unwrap();
+                        client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).await.unwrap();
+                        (host, target_port)
+                    } else {
+                        let mut head = vec![b'C'];
+                        while !head.ends_with(b"\r\n\r\n") {
+                            head.push(client.read_u8().await.unwrap());
+                        }
+                        let head = String::from_utf8(head).unwrap();
+                        let authority = head.split_whitespace().nth(1).unwrap().to_string();
+                        let (host, target_port) = authority.rsplit_once(':').unwrap();
+                        client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+                        (host.to_string(), target_port.parse().unwrap())
+                    };
+                    log.lock().unwrap().push(format!("{}:{}", host, target_port));
+                    let mut server = tokio::net::TcpStream::connect(("127.0.0.1", target_port)).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                });
+            }
+        });
+        (port, targets)
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_tls_is_read_from_the_connection() {
+        // TLS 1.2 without session IDs nor tickets
+        let mut config = tls_server_config(&[&rustls::version::TLS12]);
+        config.session_storage = Arc::new(rustls::server::NoServerSessionStorage {});
+        let url = Url::parse(&serve_tls_with(config).await).unwrap();
+        let client = tls_client(HttpProtocols::Http2);
+
+        // Read from the server the page came from
+        client.get(url.as_str()).await.unwrap();
+        client.inspect_tls(&url).await.unwrap();
+        let info = client.certificates().security_info("localhost", url.port().unwrap()).unwrap();
+        assert_eq!(info.protocol.as_deref(), Some("TLS 1.2"));
+        assert!(info.cipher.is_some_and(|c| c.starts_with("TLS_ECDHE_")));
+        assert_eq!(info.chain[0].issuer, "CN=FAGA Test CA");
+
+        // Each port of a host is a server of its own
+        let tls13 = Url::parse(&serve_tls(&[b"http/1.1"]).await).unwrap();
+        assert_eq!(client.certificates().security_info("localhost", tls13.port().unwrap()).unwrap().protocol, None);
+
+        // Through HTTP and SOCKS proxies, like the requests
+        let (proxy_port, targets) = serve_tunnel_proxy().await;
+        for scheme in ["http", "socks5h", "socks5"] {
+            let proxied = HttpClient::with_config(HttpClientConfig {
+                proxy: Some(ProxyConfig::new(&format!("{}://127.0.0.1:{}", scheme, proxy_port)).unwrap()),
+                ..tls_client(HttpProtocols::Http2).config.clone()
+            })
+            .unwrap();
+            assert!(proxied.is_proxied(&tls13));
+            proxied.inspect_tls(&tls13).await.unwrap();
+            let info = proxied.certificates().security_info("localhost", tls13.port().unwrap()).unwrap();
+            assert_eq!(info.protocol.as_deref(), Some("TLS 1.3"), "{}", scheme);
+            assert!(info.cipher.is_some_and(|c| c.starts_with("TLS13_")));
+        }
+        let port = tls13.port().unwrap();
+        assert_eq!(*targets.lock().unwrap(), vec![format!("localhost:{}", port), format!("localhost:{}", port), format!("127.0.0.1:{}", port)]);
+
+        // HTTPS proxies cannot be tunnelled through: the viewer says so
+        let https_proxied = HttpClient::with_config(HttpClientConfig {
+            proxy: Some(ProxyConfig::new(&format!("https://127.0.0.1:{}", proxy_port)).unwrap()),
+            ..tls_client(HttpProtocols::Http2).config.clone()
+        })
+        .unwrap();
+        assert!(matches!(https_proxied.inspect_tls(&tls13).await, Err(HttpClientError::ProxyFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_certificates_and_exceptions() {
+        let url = serve_tls(&[b"h2", b"http/1.1"]).await;
+
+        // Without the test CA, the certificate is untrusted until the user accepts it
+        let client = HttpClient::with_config(HttpClientConfig {
+            cache: None,
+            cookie_file: None,
+            https_only_file: None,
+            dns: DnsConfig::Custom(Arc::new(Loopback)),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            client.get(&url).await.err(),
+            Some(HttpClientError::CertificateRejected("localhost".to_string(), CertificateError::Untrusted))
+        );
+        let port = Url::parse(&url).unwrap().port().unwrap();
+        let rejected = client.certificates().security_info("localhost", port).unwrap();
+        assert_eq!(rejected.chain[0].issuer, "CN=FAGA Test CA");
+        assert!(!rejected.overridden);
+
+        client.certificates().trust("localhost", &rejected.chain[0].fingerprint);
+        assert_eq!(client.get(&url).await.unwrap().text(), "HTTP/2.0");
+        client.inspect_tls(&Url::parse(&url).unwrap()).await.unwrap();
+        let accepted = client.certificates().security_info("localhost", port).unwrap();
+        assert!(accepted.overridden);
+        assert_eq!(accepted.protocol.as_deref(), Some("TLS 1.3"));
+        assert!(accepted.cipher.is_some_and(|c| c.starts_with("TLS13_")));
+
+        // A trusted certificate for another name
+        let other = url.replace("localhost", "other.test");
+        assert_eq!(
+            tls_client(HttpProtocols::Http2).get(&other).await.err(),
+            Some(HttpClientError::CertificateRejected("other.test".to_string(), CertificateError::NameMismatch))
+        );
+    }
+
+    /// Serve HTTP/3 on a local UDP port, answering with the request's HTTP version
+    #[cfg(feature = "http3")]
+    fn serve_h3() -> String {
+        let mut config = tls_server_config(&[&rustls::version::TLS13]);
+        config.alpn_protocols = vec![b"h3".to_vec()];
+
+        let endpoint = quinn::Endpoint::server(quinn::ServerConfig::with_crypto(Arc::new(config)), SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let url = format!("https://localhost:{}/", endpoint.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            while let Some(connecting) = endpoint.accept().await {
+                tokio::spawn(async move {
+                    let Ok(connection) = connecting.await else { return };
+                    let Ok(mut h3) = h3::server::Connection::<_, hyper::body::Bytes>::new(h3_quinn::Connection::new(connection)).await else { return };
+                    while let Ok(Some((request, mut stream))) = h3.accept().await {
+                        let _ = stream.send_response(hyper::Response::new(())).await;
+                        let _ = stream.send_data(format!("{:?}", request.version()).into()).await;
+                        let _ = stream.finish().await;
+                    }
+                });
+            }
+        });
+        url
+    }
+
+    #[cfg(feature = "http3")]
+    #[tokio::test]
+    async fn test_http3_prior_knowledge() {
+        let url = serve_h3();
+        let client = tls_client(HttpProtocols::Http3PriorKnowledge);
+
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.http_version, "HTTP/3.0");
+        assert_eq!(response.text(), "HTTP/3.0");
+    }
+}
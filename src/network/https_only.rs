@@ -0,0 +1,169 @@
+//! HTTPS-only mode for FAGA Browser
+//! Upgrades plain HTTP requests to HTTPS, except for sites the user chose to
+//! visit over HTTP anyway.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Saved HTTPS-only settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PolicyFile {
+    enabled: bool,
+    /// Hosts allowed over plain HTTP
+    exceptions: BTreeSet<String>,
+}
+
+/// Whether, and for which hosts, HTTP requests are upgraded
+#[derive(Debug, Default)]
+pub struct HttpsOnlyPolicy {
+    state: Mutex<PolicyFile>,
+    path: Option<PathBuf>,
+}
+
+impl HttpsOnlyPolicy {
+    /// Create an in-memory policy, initially disabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the policy from disk, disabled if the file is missing or unreadable
+    pub fn open(path: PathBuf) -> Self {
+        let state: PolicyFile = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        Self { state: Mutex::new(state), path: Some(path) }
+    }
+
+    /// In-memory copy of the current settings, for private tabs
+    ///
+    /// Exceptions added to the copy are forgotten with it, never saved.
+    pub fn private_copy(&self) -> Self {
+        let state = self.state.lock().map(|s| s.clone()).unwrap_or_default();
+        Self { state: Mutex::new(state), path: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().map(|s| s.enabled).unwrap_or(false)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.enabled = enabled;
+        }
+        self.save();
+    }
+
+    /// Let `host` be visited over plain HTTP from now on
+    pub fn allow_http(&self, host: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.exceptions.insert(host.to_ascii_lowercase());
+        }
+        log::info!("🔓 HTTP allowed for {}", host);
+        self.save();
+    }
+
+    pub fn remove_exception(&self, host: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.exceptions.remove(&host.to_ascii_lowercase());
+        }
+        self.save();
+    }
+
+    /// Hosts allowed over plain HTTP, sorted
+    pub fn exceptions(&self) -> Vec<String> {
+        self.state.lock().map(|s| s.exceptions.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// HTTPS URL to request instead of `url`, if it must be upgraded
+    ///
+    /// Local addresses and `.onion` sites are left alone: the first rarely
+    /// have certificates and the second are already end-to-end encrypted.
+    pub fn upgrade(&self, url: &Url) -> Option<Url> {
+        if url.scheme() != "http" {
+            return None;
+        }
+        let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+        let state = self.state.lock().ok()?;
+        if !state.enabled || state.exceptions.contains(&host) || is_exempt(&host) {
+            return None;
+        }
+
+        let mut upgraded = url.clone();
+        upgraded.set_scheme("https").ok()?;
+        // Only the default port follows the scheme
+        if url.port() == Some(80) {
+            upgraded.set_port(None).ok()?;
+        }
+        Some(upgraded)
+    }
+
+    /// Write the settings to disk (no-op for in-memory policies)
+    fn save(&self) {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        let state = self.state.lock().map(|s| s.clone()).unwrap_or_default();
+        let result = serde_json::to_vec_pretty(&state)
+            .map_err(std::io::Error::from)
+            .and_then(|data| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, data)
+            });
+        if let Err(e) = result {
+            log::warn!("⚠️ Failed to save HTTPS-only settings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn is_exempt(host: &str) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") || host.ends_with(".onion") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+        Err(_) => false,
+    }
+}
+
+/// Platform location of the HTTPS-only settings (e.g. `~/.local/share/faga/https_only.json`)
+pub fn default_https_only_path() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("https_only.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_rules() {
+        let policy = HttpsOnlyPolicy::new();
+        let upgrade = |url: &str| policy.upgrade(&Url::parse(url).unwrap()).map(|u| u.to_string());
+
+        assert_eq!(upgrade("http://example.com/a?b"), None);
+        policy.set_enabled(true);
+        assert_eq!(upgrade("http://example.com/a?b").as_deref(), Some("https://example.com/a?b"));
+        assert_eq!(upgrade("http://example.com:8080/").as_deref(), Some("https://example.com:8080/"));
+        assert_eq!(upgrade("https://example.com/"), None);
+        assert_eq!(upgrade("http://localhost:3000/"), None);
+        assert_eq!(upgrade("http://192.168.1.1/"), None);
+        assert_eq!(upgrade("http://duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion/"), None);
+
+        policy.allow_http("Example.com");
+        assert_eq!(upgrade("http://example.com/"), None);
+        assert_eq!(upgrade("http://www.example.com/").as_deref(), Some("https://www.example.com/"));
+        policy.remove_exception("example.com");
+        assert!(upgrade("http://example.com/").is_some());
+    }
+}
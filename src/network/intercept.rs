@@ -0,0 +1,33 @@
+//! Request interception for FAGA Browser
+//! Hooks run by `HttpClient` around every request, to rewrite headers, mock
+//! or block requests, or log the traffic without changing the network stack.
+
+use std::fmt::Debug;
+use super::http_client::HttpClientError;
+use super::request::Request;
+use super::response::Response;
+
+/// Code run before a request is sent and after its response is received
+///
+/// Interceptors run in the order of `HttpClientConfig::interceptors` before
+/// sending, and in reverse order after receiving, so the first one registered
+/// sees the request first and the response last.
+pub trait Interceptor: Debug + Send + Sync {
+    /// Inspect or rewrite a request before it is sent
+    ///
+    /// Returning a response answers the request without the network: the
+    /// interceptors after this one are skipped, and only the ones before it
+    /// see the response. Returning an error fails the request (e.g.
+    /// `HttpClientError::Blocked` to block it).
+    fn before_send(&self, _request: &mut Request) -> Result<Option<Response>, HttpClientError> {
+        Ok(None)
+    }
+
+    /// Inspect or rewrite a response once it is received
+    ///
+    /// Not called for requests that failed. For `HttpClient::open`, the body
+    /// is still empty since it has not been read yet.
+    fn after_receive(&self, _request: &Request, _response: &mut Response) -> Result<(), HttpClientError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+//! Subresource loader for FAGA Browser
+//! Fetches stylesheets, scripts, fonts and images after the main document,
+//! sharing duplicate requests, capping connections per host and serving
+//! render-blocking resources first.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use futures::future::{BoxFuture, FutureExt, Shared, WeakShared};
+use tokio::sync::Notify;
+use url::Url;
+use super::http_client::{HttpClient, HttpClientError};
+use super::request::Request;
+use super::response::Response;
+
+/// Connections allowed per host, as in most browsers
+pub const DEFAULT_MAX_PER_HOST: usize = 6;
+
+/// Type of a subresource; variants are declared from most to least urgent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Stylesheet,
+    Script,
+    Font,
+    Image,
+    Other,
+}
+
+impl ResourceKind {
+    /// Accept header sent for this type of resource
+    pub fn accept(&self) -> &'static str {
+        match self {
+            Self::Stylesheet => "text/css,*/*;q=0.1",
+            Self::Font => "font/woff2,font/woff;q=0.9,*/*;q=0.8",
+            Self::Image => "image/avif,image/webp,image/png,image/svg+xml,image/*;q=0.8,*/*;q=0.5",
+            Self::Script | Self::Other => "*/*",
+        }
+    }
+
+    /// Short name shown in the DevTools Network tab
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Stylesheet => "css",
+            Self::Script => "script",
+            Self::Font => "font",
+            Self::Image => "img",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Outcome of a subresource fetch, kept for the DevTools Network tab
+#[derive(Debug, Clone)]
+pub struct LoadedResource {
+    pub url: String,
+    pub kind: ResourceKind,
+    pub result: Result<Arc<Response>, HttpClientError>,
+    /// Time spent waiting for a free connection slot
+    pub queued: Duration,
+    /// Time from sending the request to receiving the whole body
+    pub elapsed: Duration,
+}
+
+type SharedLoad = Shared<BoxFuture<'static, LoadedResource>>;
+
+/// Queue of subresource fetches running through one HttpClient
+pub struct ResourceLoader {
+    client: Arc<HttpClient>,
+    max_per_host: usize,
+    state: Mutex<LoaderState>,
+    slots_changed: Notify,
+}
+
+#[derive(Default)]
+struct LoaderState {
+    /// Fetches in progress, so that duplicate URLs share them
+    in_flight: HashMap<String, WeakShared<BoxFuture<'static, LoadedResource>>>,
+    waiting: Vec<Waiter>,
+    active: HashMap<String, usize>,
+    next_ticket: u64,
+}
+
+struct Waiter {
+    ticket: u64,
+    kind: ResourceKind,
+    host: String,
+}
+
+impl std::fmt::Debug for ResourceLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceLoader")
+            .field("max_per_host", &self.max_per_host)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResourceLoader {
+    pub fn new(client: Arc<HttpClient>) -> Self {
+        Self {
+            client,
+            max_per_host: DEFAULT_MAX_PER_HOST,
+            state: Mutex::new(LoaderState::default()),
+            slots_changed: Notify::new(),
+        }
+    }
+
+    pub fn with_max_per_host(mut self, max_per_host: usize) -> Self {
+        self.max_per_host = max_per_host.max(1);
+        self
+    }
+
+    /// Client used for the fetches
+    pub fn client(&self) -> &Arc<HttpClient> {
+        &self.client
+    }
+
+    /// Fetch a subresource of the page at `first_party`
+    ///
+    /// Requests for a URL that is already being fetched share its result.
+    pub fn fetch(self: &Arc<Self>, url: &str, kind: ResourceKind, first_party: &str) -> impl Future<Output = LoadedResource> + Send + 'static {
+        let mut state = self.lock();
+        state.in_flight.retain(|_, load| load.upgrade().is_some());
+
+        if let Some(load) = state.in_flight.get(url).and_then(|load| load.upgrade()) {
+            log::debug!("🔁 Sharing in-flight fetch of {}", url);
+            return load;
+        }
+
+        let loader = self.clone();
+        let (url, first_party) = (url.to_string(), first_party.to_string());
+        let key = url.clone();
+        let load: SharedLoad = async move {
+            let loaded = loader.load(url, kind, first_party).await;
+            loader.lock().in_flight.remove(&loaded.url);
+            loaded
+        }
+        .boxed()
+        .shared();
+
+        if let Some(weak) = load.downgrade() {
+            state.in_flight.insert(key, weak);
+        }
+        load
+    }
+
+    async fn load(&self, url: String, kind: ResourceKind, first_party: String) -> LoadedResource {
+        let host = Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_default();
+
+        let queued_at = Instant::now();
+        let _slot = self.acquire(&host, kind).await;
+        let started = Instant::now();
+
+        let request = Request::get(&url)
+            .with_header("Accept", kind.accept())
+            .with_first_party(&first_party)
+            .with_referrer(&first_party)
+            .with_kind(kind);
+        let result = self.client.execute(request).await.map(Arc::new);
+
+        match &result {
+            Ok(response) => log::debug!("📦 {} {} ({} bytes)", response.status, url, response.body.len()),
+            Err(e) => log::warn!("⚠️ Failed to load {}: {}", url, e),
+        }
+
+        LoadedResource {
+            url,
+            kind,
+            result,
+            queued: started - queued_at,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Wait for a connection slot to `host`
+    ///
+    /// The most urgent waiter for a host goes first, ties go to the oldest.
+    async fn acquire(&self, host: &str, kind: ResourceKind) -> Slot<'_> {
+        let ticket = {
+            let mut state = self.lock();
+            state.next_ticket += 1;
+            let ticket = state.next_ticket;
+            state.waiting.push(Waiter { ticket, kind, host: host.to_string() });
+            ticket
+        };
+        let _waiting = Waiting { loader: self, ticket };
+
+        loop {
+            let changed = self.slots_changed.notified();
+            if self.try_start(ticket, host) {
+                // Another waiter may fit in the remaining slots
+                self.slots_changed.notify_waiters();
+                return Slot { loader: self, host: host.to_string() };
+            }
+            changed.await;
+        }
+    }
+
+    fn try_start(&self, ticket: u64, host: &str) -> bool {
+        let mut state = self.lock();
+        if state.active.get(host).copied().unwrap_or(0) >= self.max_per_host {
+            return false;
+        }
+
+        let next = state.waiting
+            .iter()
+            .filter(|w| w.host == host)
+            .min_by_key(|w| (w.kind, w.ticket))
+            .map(|w| w.ticket);
+        if next != Some(ticket) {
+            return false;
+        }
+
+        state.waiting.retain(|w| w.ticket != ticket);
+        *state.active.entry(host.to_string()).or_insert(0) += 1;
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LoaderState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Removes a cancelled fetch from the queue
+struct Waiting<'a> {
+    loader: &'a ResourceLoader,
+    ticket: u64,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let removed = {
+            let mut state = self.loader.lock();
+            let before = state.waiting.len();
+            state.waiting.retain(|w| w.ticket != self.ticket);
+            state.waiting.len() != before
+        };
+        if removed {
+            self.loader.slots_changed.notify_waiters();
+        }
+    }
+}
+
+/// A connection slot, released when the fetch ends or is cancelled
+struct Slot<'a> {
+    loader: &'a ResourceLoader,
+    host: String,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.loader.lock();
+            if let Some(active) = state.active.get_mut(&self.host) {
+                *active = active.saturating_sub(1);
+                if *active == 0 {
+                    state.active.remove(&self.host);
+                }
+            }
+        }
+        self.loader.slots_changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Server};
+    use crate::network::http_client::HttpClientConfig;
+
+    /// Requests seen by a test server, which holds every response until released
+    struct Gate {
+        url: String,
+        order: Arc<Mutex<Vec<String>>>,
+        peak: Arc<AtomicUsize>,
+        /// Path of each request, as soon as it reaches the server
+        arrived: tokio::sync::mpsc::UnboundedReceiver<String>,
+        /// One permit lets one response go
+        release: Arc<tokio::sync::Semaphore>,
+    }
+
+    /// Serve held responses, recording the request order and the peak concurrency
+    fn serve_gated() -> Gate {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let current = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(tokio::sync::Semaphore::new(0));
+        let (arrivals, arrived) = tokio::sync::mpsc::unbounded_channel();
+        let (order_log, peak_log, gate) = (order.clone(), peak.clone(), release.clone());
+
+        let make_service = make_service_fn(move |_| {
+            let (order, peak, current, gate, arrivals) = (order_log.clone(), peak_log.clone(), current.clone(), gate.clone(), arrivals.clone());
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                    let (order, peak, current, gate, arrivals) = (order.clone(), peak.clone(), current.clone(), gate.clone(), arrivals.clone());
+                    async move {
+                        let path = request.uri().path().to_string();
+                        order.lock().unwrap().push(path.clone());
+                        let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        let _ = arrivals.send(path.clone());
+                        gate.acquire().await.unwrap().forget();
+                        current.fetch_sub(1, Ordering::SeqCst);
+                        Ok::<_, Infallible>(hyper::Response::new(Body::from(path)))
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        Gate { url, order, peak, arrived, release }
+    }
+
+    fn loader(max_per_host: usize) -> Arc<ResourceLoader> {
+        let client = HttpClient::with_config(HttpClientConfig { cache: None, cookie_file: None, https_only_file: None, ..Default::default() }).unwrap();
+        Arc::new(ResourceLoader::new(Arc::new(client)).with_max_per_host(max_per_host))
+    }
+
+    #[tokio::test]
+    async fn test_dedupes_and_caps_per_host() {
+        let mut gate = serve_gated();
+        let url = gate.url.clone();
+        let loader = loader(2);
+
+        let fetches: Vec<_> = ["/a.png", "/a.png", "/b.png", "/c.png", "/d.png"]
+            .iter()
+            .map(|path| loader.fetch(&format!("{}{}", url, path), ResourceKind::Image, &url))
+            .collect();
+        let (results, _) = futures::future::join(futures::future::join_all(fetches), async {
+            // Both slots are taken before anything is let through
+            gate.arrived.recv().await.unwrap();
+            gate.arrived.recv().await.unwrap();
+            gate.release.add_permits(4);
+        })
+        .await;
+
+        assert!(results.iter().all(|r| r.result.is_ok()));
+        assert_eq!(results[1].result.as_ref().unwrap().text(), "/a.png");
+        assert_eq!(gate.order.lock().unwrap().len(), 4);
+        assert_eq!(gate.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stylesheets_jump_the_queue() {
+        let mut gate = serve_gated();
+        let url = gate.url.clone();
+        let loader = loader(1);
+
+        // Let the first image take the only slot
+        let first = tokio::spawn(loader.fetch(&format!("{}/first.png", url), ResourceKind::Image, &url));
+        assert_eq!(gate.arrived.recv().await.as_deref(), Some("/first.png"));
+
+        // Both are queued by their first poll, before the slot is released
+        futures::future::join3(
+            loader.fetch(&format!("{}/second.png", url), ResourceKind::Image, &url),
+            loader.fetch(&format!("{}/style.css", url), ResourceKind::Stylesheet, &url),
+            async { gate.release.add_permits(3) },
+        )
+        .await;
+        first.await.unwrap();
+
+        assert_eq!(*gate.order.lock().unwrap(), vec!["/first.png", "/style.css", "/second.png"]);
+    }
+}
@@ -0,0 +1,284 @@
+//! Local resources for FAGA Browser
+//! Serves `file:` URLs from disk, with generated directory listings, and
+//! decodes `data:` URLs so pages can be rendered without any network.
+
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use std::path::Path;
+use url::Url;
+use super::download::percent_decode;
+use super::http_client::HttpClientError;
+use super::response::Response;
+
+/// Base64 as found in `data:` URLs, where the padding is often left out
+const DATA_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Whether the URL is served locally instead of over the network
+pub fn is_local(url: &Url) -> bool {
+    matches!(url.scheme(), "file" | "data")
+}
+
+/// Serve a `file:` or `data:` URL
+///
+/// Files cannot be loaded by web pages: only by a navigation or a page that
+/// is itself a local file. Missing and unreadable files answer 404 and 403,
+/// like a server would.
+pub async fn fetch(url: &Url, first_party: Option<&Url>) -> Result<Response, HttpClientError> {
+    match url.scheme() {
+        "data" => decode_data_url(url),
+        "file" if first_party.is_some_and(|fp| fp.scheme() != "file") => {
+            log::warn!("🚫 {} cannot load local file {}", first_party.map(Url::as_str).unwrap_or_default(), url);
+            Ok(status_response(url, 403))
+        }
+        "file" => read_file(url).await,
+        scheme => Err(HttpClientError::InvalidUrl(format!("{}: is not a local scheme", scheme))),
+    }
+}
+
+/// Decode `data:[<mediatype>][;base64],<data>`
+pub fn decode_data_url(url: &Url) -> Result<Response, HttpClientError> {
+    // The URL parser moves a literal '?' into the query and '#' into the fragment
+    let mut content = url.path().to_string();
+    if let Some(query) = url.query() {
+        content.push('?');
+        content.push_str(query);
+    }
+    if let Some(fragment) = url.fragment() {
+        content.push('#');
+        content.push_str(fragment);
+    }
+    let (header, payload) = content
+        .split_once(',')
+        .ok_or_else(|| HttpClientError::InvalidUrl("data: URL without a comma".to_string()))?;
+
+    let header = header.trim();
+    let (media_type, base64) = match header.rsplit_once(';') {
+        Some((rest, flag)) if flag.trim().eq_ignore_ascii_case("base64") => (rest.trim(), true),
+        _ => (header, false),
+    };
+
+    let bytes = percent_decode(payload);
+    let body = if base64 {
+        let compact: Vec<u8> = bytes.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        DATA_BASE64
+            .decode(compact)
+            .map_err(|e| HttpClientError::InvalidUrl(format!("invalid base64 in data: URL: {}", e)))?
+    } else {
+        bytes
+    };
+
+    let media_type = String::from_utf8_lossy(&percent_decode(media_type)).into_owned();
+    let content_type = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_string()
+    } else if media_type.starts_with(';') {
+        format!("text/plain{}", media_type)
+    } else {
+        media_type
+    };
+
+    Ok(local_response(url, 200, content_type, body, "data"))
+}
+
+/// Read a file, or list a directory
+async fn read_file(url: &Url) -> Result<Response, HttpClientError> {
+    let path = url
+        .to_file_path()
+        .map_err(|_| HttpClientError::InvalidUrl(format!("not a local path: {}", url)))?;
+
+    let metadata = match tokio::fs::metadata(&path).await {
+        Ok(metadata) => metadata,
+        Err(e) => return io_failure(url, e),
+    };
+
+    if metadata.is_dir() {
+        return list_directory(url, &path).await;
+    }
+
+    let body = match tokio::fs::read(&path).await {
+        Ok(body) => body,
+        Err(e) => return io_failure(url, e),
+    };
+    log::info!("📄 Read {} ({} bytes)", path.display(), body.len());
+
+    let mut response = local_response(url, 200, guess_content_type(&path).to_string(), body, "file");
+    if let Ok(modified) = metadata.modified() {
+        response.headers.push(("last-modified".to_string(), httpdate::fmt_http_date(modified)));
+    }
+    Ok(response)
+}
+
+/// Turn a failed read into the status a server would answer
+fn io_failure(url: &Url, error: std::io::Error) -> Result<Response, HttpClientError> {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => Ok(status_response(url, 404)),
+        std::io::ErrorKind::PermissionDenied => Ok(status_response(url, 403)),
+        _ => Err(HttpClientError::ResponseReadError(format!("{}: {}", url, error))),
+    }
+}
+
+/// HTML listing of a directory, sub-directories first
+async fn list_directory(url: &Url, path: &Path) -> Result<Response, HttpClientError> {
+    let mut read_dir = match tokio::fs::read_dir(path).await {
+        Ok(read_dir) => read_dir,
+        Err(e) => return io_failure(url, e),
+    };
+
+    let mut entries = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let metadata = entry.metadata().await.ok();
+        let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+        let size = metadata.as_ref().filter(|_| !is_dir).map(|m| m.len());
+        let modified = metadata.and_then(|m| m.modified().ok());
+        entries.push((name, is_dir, size, modified));
+    }
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+
+    // Links are relative to the directory itself, which needs a trailing slash
+    let mut base = url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    let entry_url = |name: &str, is_dir: bool| {
+        let mut entry = base.clone();
+        if let Ok(mut segments) = entry.path_segments_mut() {
+            segments.pop_if_empty().push(name);
+            if is_dir {
+                segments.push("");
+            }
+        }
+        entry.to_string()
+    };
+
+    let title = format!("Index of {}", escape_html(&path.display().to_string()));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n<table>\n",
+        title
+    );
+    if let Some(parent) = path.parent().and_then(|_| base.join("..").ok()) {
+        html.push_str(&format!("<tr><td><a href=\"{}\">../</a></td><td></td><td></td></tr>\n", escape_html(parent.as_str())));
+    }
+    for (name, is_dir, size, modified) in &entries {
+        let label = if *is_dir { format!("{}/", name) } else { name.clone() };
+        html.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&entry_url(name, *is_dir)),
+            escape_html(&label),
+            size.map(|s| s.to_string()).unwrap_or_default(),
+            modified.map(httpdate::fmt_http_date).unwrap_or_default(),
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    log::info!("📂 Listed {} ({} entries)", path.display(), entries.len());
+    Ok(local_response(&base, 200, "text/html; charset=utf-8".to_string(), html.into_bytes(), "file"))
+}
+
+/// Content type of a local file, from its extension
+fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html",
+        "xhtml" => "application/xhtml+xml",
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "json" => "application/json",
+        "txt" | "md" | "log" => "text/plain",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn local_response(url: &Url, status: u16, content_type: String, body: Vec<u8>, protocol: &str) -> Response {
+    Response {
+        status,
+        headers: vec![
+            ("content-type".to_string(), content_type.clone()),
+            ("content-length".to_string(), body.len().to_string()),
+        ],
+        content_type,
+        body,
+        url: url.to_string(),
+        redirects: Vec::new(),
+        retries: Vec::new(),
+        http_version: protocol.to_string(),
+    }
+}
+
+fn status_response(url: &Url, status: u16) -> Response {
+    local_response(url, status, "text/plain".to_string(), Vec::new(), "file")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_urls() {
+        let decode = |url: &str| decode_data_url(&Url::parse(url).unwrap()).unwrap();
+
+        let page = decode("data:text/html;charset=utf-8,%3Ch1%3EBonjour%20%C3%A0%20tous%3C/h1%3E");
+        assert_eq!(page.content_type, "text/html;charset=utf-8");
+        assert_eq!(page.text(), "<h1>Bonjour à tous</h1>");
+
+        let css = decode("data:text/css;base64,Ym9keSB7IGNvbG9yOiByZWQgfQ");
+        assert_eq!(css.content_type, "text/css");
+        assert_eq!(css.body, b"body { color: red }");
+
+        let plain = decode("data:,a%20b#c");
+        assert_eq!(plain.content_type, "text/plain;charset=US-ASCII");
+        assert_eq!(plain.body, b"a b#c");
+
+        let query = decode("data:text/plain,a?b=c#d");
+        assert_eq!(query.content_type, "text/plain");
+        assert_eq!(query.body, b"a?b=c#d");
+
+        assert!(decode_data_url(&Url::parse("data:text/plain;base64,@@@").unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_files_and_directory_listings() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("index.html"), "<p>local</p>").unwrap();
+        std::fs::create_dir(dir.path().join("css")).unwrap();
+        std::fs::write(dir.path().join("css/style.css"), "p { color: blue }").unwrap();
+
+        let dir_url = Url::from_directory_path(dir.path()).unwrap();
+        let page = fetch(&dir_url.join("index.html").unwrap(), None).await.unwrap();
+        assert_eq!(page.status, 200);
+        assert_eq!(page.content_type, "text/html");
+        assert_eq!(page.body, b"<p>local</p>");
+
+        let listing = fetch(&Url::from_file_path(dir.path()).unwrap(), None).await.unwrap();
+        assert!(listing.url.ends_with('/'));
+        let html = listing.text();
+        assert!(html.find("css/").unwrap() < html.find("index.html").unwrap());
+        assert!(html.contains(&format!("href=\"{}css/\"", dir_url)));
+
+        let missing = fetch(&dir_url.join("missing.html").unwrap(), None).await.unwrap();
+        assert_eq!(missing.status, 404);
+
+        // A web page cannot read local files
+        let web = Url::parse("https://example.com/").unwrap();
+        let stylesheet = dir_url.join("css/style.css").unwrap();
+        assert_eq!(fetch(&stylesheet, Some(&web)).await.unwrap().status, 403);
+        assert_eq!(fetch(&stylesheet, Some(&dir_url)).await.unwrap().status, 200);
+    }
+}
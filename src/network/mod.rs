@@ -1,5 +1,38 @@
+pub mod auth;
+pub mod blocker;
+pub mod cache;
+pub mod cookies;
+pub mod dns;
+pub mod download;
+pub mod har;
 pub mod http_client;
+pub mod https_only;
+pub mod intercept;
+pub mod loader;
+pub mod local;
+pub mod privacy;
+pub mod protocol;
+pub mod proxy;
 pub mod request;
 pub mod response;
+pub mod retry;
+pub mod stream;
+pub mod tls;
 
 pub use http_client::HttpClient;
+
+use std::path::PathBuf;
+
+/// Platform folder for the browser's data (e.g. `~/.local/share/faga`)
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|dir| dir.join("faga"))
+}
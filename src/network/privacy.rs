@@ -0,0 +1,256 @@
+//! Privacy profiles for FAGA Browser
+//! Decide what the default request headers reveal about the user: the
+//! user-agent, preferred languages, how much of the referring page is sent
+//! and the order headers go out in.
+
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CACHE_CONTROL, CONTENT_LENGTH,
+    CONTENT_TYPE, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH, ORIGIN, REFERER, USER_AGENT,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use super::cookies::site_of;
+use super::http_client::HttpClientError;
+
+/// User-agent shared by the largest anonymity set (Tor Browser / Firefox ESR on Windows)
+pub const UNIFORM_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; rv:128.0) Gecko/20100101 Firefox/128.0";
+const UNIFORM_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.5";
+const DEFAULT_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+const DEFAULT_ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// Ready-made profiles offered in the settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyMode {
+    /// Look like every other user of the same profile
+    #[default]
+    Uniform,
+    /// Report the real platform and languages
+    Honest,
+}
+
+impl PrivacyMode {
+    pub fn profile(&self) -> PrivacyProfile {
+        match self {
+            Self::Uniform => PrivacyProfile::uniform(),
+            Self::Honest => PrivacyProfile::honest(),
+        }
+    }
+}
+
+/// How much of the referring page is sent in the Referer header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefererPolicy {
+    /// Never send a Referer
+    NoReferrer,
+    /// The full URL within a site, only the origin across sites,
+    /// and nothing when going from HTTPS to HTTP
+    OriginOnlyCrossSite,
+}
+
+impl RefererPolicy {
+    /// Referer to send to `target` for a request made from `referrer`
+    pub fn referer_for(&self, referrer: &Url, target: &Url) -> Option<String> {
+        if !matches!(referrer.scheme(), "http" | "https") {
+            return None;
+        }
+        if referrer.scheme() == "https" && target.scheme() != "https" {
+            return None;
+        }
+
+        let origin = format!("{}/", referrer.origin().ascii_serialization());
+        match self {
+            Self::NoReferrer => None,
+            Self::OriginOnlyCrossSite if !same_site(referrer, target) => Some(origin),
+            Self::OriginOnlyCrossSite => {
+                let mut full = referrer.clone();
+                full.set_fragment(None);
+                let _ = full.set_username("");
+                let _ = full.set_password(None);
+                Some(full.to_string())
+            }
+        }
+    }
+}
+
+/// Same scheme and registrable domain
+fn same_site(a: &Url, b: &Url) -> bool {
+    a.scheme() == b.scheme()
+        && match (a.host_str(), b.host_str()) {
+            (Some(a), Some(b)) => site_of(a) == site_of(b),
+            _ => false,
+        }
+}
+
+/// Headers a client sends by default and how it sends them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivacyProfile {
+    pub user_agent: String,
+    pub accept_language: String,
+    pub referer: RefererPolicy,
+    /// Order headers are sent in; the others follow in the order they were added
+    pub header_order: Vec<HeaderName>,
+}
+
+impl Default for PrivacyProfile {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+impl PrivacyProfile {
+    /// "Uniform" mode: everyone sends the same headers, in the same order,
+    /// as the browser being imitated
+    pub fn uniform() -> Self {
+        Self {
+            user_agent: UNIFORM_USER_AGENT.to_string(),
+            accept_language: UNIFORM_ACCEPT_LANGUAGE.to_string(),
+            referer: RefererPolicy::OriginOnlyCrossSite,
+            header_order: vec![
+                USER_AGENT, ACCEPT, ACCEPT_LANGUAGE, ACCEPT_ENCODING, CONTENT_TYPE, CONTENT_LENGTH, ORIGIN,
+                REFERER, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH, CACHE_CONTROL,
+            ],
+        }
+    }
+
+    /// "Honest" mode: the real platform and the system's languages
+    pub fn honest() -> Self {
+        Self {
+            user_agent: format!("Mozilla/5.0 ({}) FAGA/{}", platform(), env!("CARGO_PKG_VERSION")),
+            accept_language: system_accept_language().unwrap_or_else(|| UNIFORM_ACCEPT_LANGUAGE.to_string()),
+            referer: RefererPolicy::OriginOnlyCrossSite,
+            header_order: Vec::new(),
+        }
+    }
+
+    /// Headers added to every request that does not set them itself
+    pub(crate) fn default_headers(&self) -> Result<HeaderMap, HttpClientError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_str(&self.user_agent).map_err(|_| HttpClientError::InvalidHeader)?);
+        headers.insert(ACCEPT, HeaderValue::from_static(DEFAULT_ACCEPT));
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_str(&self.accept_language).map_err(|_| HttpClientError::InvalidHeader)?);
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(DEFAULT_ACCEPT_ENCODING));
+        Ok(headers)
+    }
+
+    /// Final headers for a request to `url`
+    ///
+    /// Fills in the defaults, trims the Referer (which holds the full URL of
+    /// the referring page until now) and puts everything in profile order.
+    pub(crate) fn arrange(&self, headers: &HeaderMap, defaults: &HeaderMap, url: &Url) -> HeaderMap {
+        let mut merged = headers.clone();
+        for (name, value) in defaults {
+            if !merged.contains_key(name) {
+                merged.insert(name.clone(), value.clone());
+            }
+        }
+
+        let referer = merged
+            .remove(REFERER)
+            .and_then(|value| Url::parse(value.to_str().ok()?).ok())
+            .and_then(|referrer| self.referer.referer_for(&referrer, url))
+            .and_then(|value| HeaderValue::from_str(&value).ok());
+        if let Some(referer) = referer {
+            merged.insert(REFERER, referer);
+        }
+
+        let mut ordered = HeaderMap::with_capacity(merged.len());
+        for name in &self.header_order {
+            for value in merged.get_all(name) {
+                ordered.append(name.clone(), value.clone());
+            }
+        }
+        for (name, value) in &merged {
+            if !self.header_order.contains(name) {
+                ordered.append(name.clone(), value.clone());
+            }
+        }
+        ordered
+    }
+}
+
+/// Platform location of the saved privacy mode (e.g. `~/.local/share/faga/privacy.json`)
+pub fn default_privacy_path() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("privacy.json"))
+}
+
+/// Read the saved privacy mode, uniform when there is none
+pub fn load_privacy_mode(path: &Path) -> PrivacyMode {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_privacy_mode(path: &Path, mode: PrivacyMode) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_vec_pretty(&mode).map_err(std::io::Error::from)?;
+    fs::write(path, data)
+}
+
+/// Platform token of the user-agent, as other browsers write it
+fn platform() -> String {
+    let arch = std::env::consts::ARCH;
+    match std::env::consts::OS {
+        "windows" => "Windows NT 10.0; Win64; x64".to_string(),
+        "macos" => "Macintosh; Intel Mac OS X 10_15_7".to_string(),
+        "linux" => format!("X11; Linux {}", arch),
+        os => format!("{}; {}", os, arch),
+    }
+}
+
+/// Accept-Language built from the locale environment variables (e.g. `fr_FR.UTF-8`)
+fn system_accept_language() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    accept_language_for(&locale)
+}
+
+fn accept_language_for(locale: &str) -> Option<String> {
+    let tag = locale.split(['.', '@']).next()?.replace('_', "-");
+    let language = tag.split('-').next()?.to_ascii_lowercase();
+    if language.len() < 2 || !language.chars().all(|c| c.is_ascii_alphabetic()) || tag == "C" || tag == "POSIX" {
+        return None;
+    }
+
+    let mut ranges = vec![tag.clone()];
+    if tag != language {
+        ranges.push(format!("{};q=0.9", language));
+    }
+    if language != "en" {
+        ranges.push("en;q=0.5".to_string());
+    }
+    Some(ranges.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referer_trimming() {
+        let policy = RefererPolicy::OriginOnlyCrossSite;
+        let page = Url::parse("https://user:pw@www.example.com/articles/42?id=1#comments").unwrap();
+        let referer = |target: &str| policy.referer_for(&page, &Url::parse(target).unwrap());
+
+        assert_eq!(referer("https://cdn.example.com/app.css").as_deref(), Some("https://www.example.com/articles/42?id=1"));
+        assert_eq!(referer("https://tracker.net/pixel.gif").as_deref(), Some("https://www.example.com/"));
+        assert_eq!(referer("http://www.example.com/insecure"), None);
+        assert_eq!(RefererPolicy::NoReferrer.referer_for(&page, &page), None);
+    }
+
+    #[test]
+    fn test_accept_language_from_locale() {
+        assert_eq!(accept_language_for("fr_FR.UTF-8").as_deref(), Some("fr-FR,fr;q=0.9,en;q=0.5"));
+        assert_eq!(accept_language_for("en_US.UTF-8").as_deref(), Some("en-US,en;q=0.9"));
+        assert_eq!(accept_language_for("de").as_deref(), Some("de,en;q=0.5"));
+        assert_eq!(accept_language_for("C.UTF-8"), None);
+        assert_eq!(accept_language_for("POSIX"), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! HTTP protocol selection for FAGA Browser
+//! Which HTTP versions the client offers to servers through ALPN, and the
+//! HTTP/3 endpoints servers advertise in `Alt-Svc` (used with the `http3`
+//! cargo feature).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Alt-Svc entries live 24 hours unless `ma` says otherwise
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// HTTP versions the client offers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpProtocols {
+    /// HTTP/1.1 only (ALPN `http/1.1`)
+    Http1Only,
+    /// HTTP/2 when the server picks it through ALPN (`h2`, then `http/1.1`)
+    #[default]
+    Http2,
+    /// HTTP/2 without negotiation, also over cleartext (h2c)
+    Http2PriorKnowledge,
+    /// HTTP/3 over QUIC for origins that advertise it in Alt-Svc, HTTP/2 otherwise
+    #[cfg(feature = "http3")]
+    Http3,
+    /// HTTP/3 only, for servers known to speak it
+    #[cfg(feature = "http3")]
+    Http3PriorKnowledge,
+}
+
+impl HttpProtocols {
+    /// Choices offered in the settings
+    pub fn choices() -> Vec<Self> {
+        vec![
+            Self::Http1Only,
+            Self::Http2,
+            #[cfg(feature = "http3")]
+            Self::Http3,
+        ]
+    }
+
+    /// Protocols offered through TLS ALPN, most preferred first
+    pub fn alpn_protocols(&self) -> Vec<Vec<u8>> {
+        let ids: &[&[u8]] = match self {
+            Self::Http1Only => &[b"http/1.1"],
+            Self::Http2PriorKnowledge => &[b"h2"],
+            #[cfg(feature = "http3")]
+            Self::Http3PriorKnowledge => &[b"h3"],
+            _ => &[b"h2", b"http/1.1"],
+        };
+        ids.iter().map(|id| id.to_vec()).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Http1Only => "HTTP/1.1",
+            Self::Http2 => "HTTP/2",
+            Self::Http2PriorKnowledge => "HTTP/2 (prior knowledge)",
+            #[cfg(feature = "http3")]
+            Self::Http3 => "HTTP/3",
+            #[cfg(feature = "http3")]
+            Self::Http3PriorKnowledge => "HTTP/3 (prior knowledge)",
+        }
+    }
+}
+
+/// Origins that announced an HTTP/3 endpoint on the same port
+#[derive(Debug, Default)]
+pub struct AltSvcCache {
+    entries: Mutex<HashMap<String, AltSvcEntry>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AltSvcEntry {
+    expires: Instant,
+    /// Set when an HTTP/3 attempt failed, so the origin falls back to TCP
+    broken: bool,
+}
+
+impl AltSvcCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the Alt-Svc headers of a response from `url`
+    pub fn store(&self, url: &Url, headers: Vec<&str>) {
+        if headers.is_empty() {
+            return;
+        }
+        let origin = url.origin().ascii_serialization();
+        let port = url.port_or_known_default();
+        let mut entries = self.entries.lock().unwrap();
+
+        for header in headers {
+            if header.trim().eq_ignore_ascii_case("clear") {
+                entries.remove(&origin);
+                continue;
+            }
+            // Only alternatives on the same host and port can be reached without rewriting the URL
+            let max_age = parse_alt_svc(header)
+                .into_iter()
+                .find(|alt| alt.protocol == "h3" && alt.host.is_none() && Some(alt.port) == port)
+                .map(|alt| alt.max_age);
+            if let Some(max_age) = max_age {
+                let broken = entries.get(&origin).is_some_and(|e| e.broken);
+                entries.insert(origin.clone(), AltSvcEntry { expires: Instant::now() + max_age, broken });
+            }
+        }
+    }
+
+    /// Whether requests to `url` should go over HTTP/3
+    pub fn has_h3(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(&origin) {
+            Some(entry) if entry.expires <= Instant::now() => {
+                entries.remove(&origin);
+                false
+            }
+            Some(entry) => !entry.broken,
+            None => false,
+        }
+    }
+
+    /// Stop using HTTP/3 for `url`'s origin until the entry expires
+    pub fn mark_broken(&self, url: &Url) {
+        let origin = url.origin().ascii_serialization();
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(&origin) {
+            entry.broken = true;
+        }
+    }
+}
+
+/// One alternative of an Alt-Svc header
+#[derive(Debug, Clone, PartialEq)]
+struct AltService {
+    protocol: String,
+    /// None when the alternative is on the same host
+    host: Option<String>,
+    port: u16,
+    max_age: Duration,
+}
+
+/// Parse `h3=":443"; ma=86400, h2="alt.example.com:443"`
+fn parse_alt_svc(header: &str) -> Vec<AltService> {
+    header
+        .split(',')
+        .filter_map(|alternative| {
+            let mut params = alternative.split(';');
+            let (protocol, authority) = params.next()?.split_once('=')?;
+            let authority = authority.trim().trim_matches('"');
+            let (host, port) = authority.rsplit_once(':')?;
+            let max_age = params
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("ma"))
+                .and_then(|(_, value)| value.trim().trim_matches('"').parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_MAX_AGE);
+            Some(AltService {
+                protocol: protocol.trim().to_ascii_lowercase(),
+                host: (!host.is_empty()).then(|| host.to_ascii_lowercase()),
+                port: port.parse().ok()?,
+                max_age,
+            })
+        })
+        .collect()
+}
+
+/// Where the protocol choice is saved (e.g. `~/.local/share/faga/protocols.json`)
+pub fn default_protocols_path() -> Option<PathBuf> {
+    super::data_dir().map(|dir| dir.join("protocols.json"))
+}
+
+/// Read the saved protocol choice, HTTP/2 when there is none (or it needs a missing feature)
+pub fn load_protocols(path: &Path) -> HttpProtocols {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_protocols(path: &Path, protocols: HttpProtocols) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_vec_pretty(&protocols).map_err(std::io::Error::from)?;
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alt_svc_records_same_port_h3() {
+        assert_eq!(
+            parse_alt_svc("h3=\":443\"; ma=3600, h2=\"alt.example.com:8443\""),
+            vec![
+                AltService { protocol: "h3".to_string(), host: None, port: 443, max_age: Duration::from_secs(3600) },
+                AltService { protocol: "h2".to_string(), host: Some("alt.example.com".to_string()), port: 8443, max_age: DEFAULT_MAX_AGE },
+            ]
+        );
+
+        let cache = AltSvcCache::new();
+        let site = Url::parse("https://example.com/page").unwrap();
+        let other_port = Url::parse("https://other.example/").unwrap();
+        cache.store(&site, vec!["h3-29=\":443\", h3=\":443\"; ma=60"]);
+        cache.store(&other_port, vec!["h3=\":8443\""]);
+        assert!(cache.has_h3(&Url::parse("https://example.com/other").unwrap()));
+        assert!(!cache.has_h3(&other_port));
+
+        cache.mark_broken(&site);
+        assert!(!cache.has_h3(&site));
+
+        cache.store(&site, vec!["clear"]);
+        cache.store(&site, vec!["h3=\":443\"; ma=0"]);
+        assert!(!cache.has_h3(&site));
+    }
+}
@@ -1,6 +1,7 @@
 //! HTTP Request structure for FAGA Browser
 
 use std::collections::HashMap;
+use super::loader::ResourceKind;
 
 /// Represents an HTTP request
 #[derive(Debug, Clone)]
@@ -8,7 +9,11 @@ pub struct Request {
     pub url: String,
     pub method: String,
     pub headers: HashMap<String, String>,
-    pub body: Option<String>,
+    pub body: Option<Vec<u8>>,
+    /// URL of the top-level document that triggered this request (None for a navigation)
+    pub first_party: Option<String>,
+    /// What a subresource request fetches (None for a document)
+    pub kind: Option<ResourceKind>,
 }
 
 impl Request {
@@ -19,6 +24,8 @@ impl Request {
             method: "GET".to_string(),
             headers: HashMap::new(),
             body: None,
+            first_party: None,
+            kind: None,
         }
     }
 
@@ -28,7 +35,9 @@ impl Request {
             url: url.to_string(),
             method: "POST".to_string(),
             headers: HashMap::new(),
-            body: Some(body.to_string()),
+            body: Some(body.as_bytes().to_vec()),
+            first_party: None,
+            kind: None,
         }
     }
 
@@ -40,7 +49,32 @@ impl Request {
 
     /// Set the request body
     pub fn with_body(mut self, body: &str) -> Self {
-        self.body = Some(body.to_string());
+        self.body = Some(body.as_bytes().to_vec());
+        self
+    }
+
+    /// Mark the request as a subresource of the given top-level document
+    pub fn with_first_party(mut self, url: &str) -> Self {
+        self.first_party = Some(url.to_string());
+        self
+    }
+
+    /// Set what a subresource request fetches, for content blocking
+    pub fn with_kind(mut self, kind: ResourceKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set the page the request was made from (trimmed by the client's Referer policy)
+    pub fn with_referrer(mut self, url: &str) -> Self {
+        self.headers.insert("Referer".to_string(), url.to_string());
+        self
+    }
+
+    /// Set a binary request body along with its Content-Type
+    pub fn with_bytes(mut self, body: Vec<u8>, content_type: &str) -> Self {
+        self.headers.insert("Content-Type".to_string(), content_type.to_string());
+        self.body = Some(body);
         self
     }
 }
@@ -68,7 +102,17 @@ impl RequestBuilder {
     }
 
     pub fn body(mut self, body: &str) -> Self {
-        self.request.body = Some(body.to_string());
+        self.request = self.request.with_body(body);
+        self
+    }
+
+    pub fn bytes(mut self, body: Vec<u8>, content_type: &str) -> Self {
+        self.request = self.request.with_bytes(body, content_type);
+        self
+    }
+
+    pub fn first_party(mut self, url: &str) -> Self {
+        self.request.first_party = Some(url.to_string());
         self
     }
 
@@ -1,15 +1,56 @@
 //! HTTP Response structure for FAGA Browser
 
+use crate::parser::charset;
+use super::retry::RetryAttempt;
+
 /// Represents an HTTP response
 #[derive(Debug, Clone)]
 pub struct Response {
     pub status: u16,
     pub content_type: String,
-    pub body: String,
+    pub body: Vec<u8>,
+    /// Final URL, after all redirects have been followed
+    pub url: String,
+    /// Response headers in the order the server sent them (names are lowercase)
+    pub headers: Vec<(String, String)>,
+    /// Redirect hops that led to this response, oldest first
+    pub redirects: Vec<RedirectHop>,
+    /// Failed attempts that were retried before this response
+    pub retries: Vec<RetryAttempt>,
+    /// Negotiated protocol, e.g. "HTTP/1.1" or "HTTP/2.0"
+    pub http_version: String,
+}
+
+/// A single redirect followed while fetching a response
+#[derive(Debug, Clone)]
+pub struct RedirectHop {
     pub url: String,
+    pub status: u16,
 }
 
 impl Response {
+    /// Get the first value of a header (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get every value of a header, e.g. multiple Set-Cookie lines
+    pub fn header_all(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Get the URL originally requested, before any redirect
+    pub fn requested_url(&self) -> &str {
+        self.redirects.first().map(|hop| hop.url.as_str()).unwrap_or(&self.url)
+    }
+
     /// Check if the response was successful (2xx status)
     pub fn is_success(&self) -> bool {
         self.status >= 200 && self.status < 300
@@ -55,9 +96,27 @@ impl Response {
         self.content_type.starts_with("image/")
     }
 
-    /// Get the body as bytes
+    /// Get the raw body bytes
     pub fn body_bytes(&self) -> &[u8] {
-        self.body.as_bytes()
+        &self.body
+    }
+
+    /// Get the character encoding of the body, sniffed for HTML and CSS
+    pub fn encoding(&self) -> &'static encoding_rs::Encoding {
+        if self.is_html() {
+            charset::detect_html_encoding(&self.body, Some(&self.content_type))
+        } else if self.is_css() {
+            charset::detect_css_encoding(&self.body, Some(&self.content_type))
+        } else {
+            charset::sniff_bom(&self.body)
+                .or_else(|| charset::charset_from_content_type(&self.content_type))
+                .unwrap_or(encoding_rs::UTF_8)
+        }
+    }
+
+    /// Decode the body as text using its detected encoding
+    pub fn text(&self) -> String {
+        charset::decode(&self.body, self.encoding())
     }
 
     /// Get the content length
@@ -0,0 +1,147 @@
+//! Retry policy for FAGA Browser
+//! Decides which failed requests are sent again, and how long to wait first:
+//! exponential backoff with jitter, or the server's `Retry-After`.
+
+use reqwest::Method;
+use std::time::{Duration, SystemTime};
+use super::http_client::HttpClientError;
+
+/// Failures worth sending the request again for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryableError {
+    /// No response within the timeout
+    Timeout,
+    /// Nothing was listening (e.g. a server restarting)
+    ConnectionRefused,
+    /// The connection failed or was reset
+    Network,
+    /// The host name could not be resolved
+    Dns,
+}
+
+/// When and how often requests are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, the first one included (1 disables retries)
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub base_delay: Duration,
+    /// Longest wait between two attempts; a longer `Retry-After` is not waited for
+    pub max_delay: Duration,
+    /// Statuses answered by overloaded or restarting servers
+    pub statuses: Vec<u16>,
+    pub errors: Vec<RetryableError>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+            statuses: vec![408, 429, 502, 503, 504],
+            errors: vec![RetryableError::Timeout, RetryableError::ConnectionRefused, RetryableError::Network],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    pub fn retries_error(&self, error: &HttpClientError) -> bool {
+        let kind = match error {
+            HttpClientError::Timeout => RetryableError::Timeout,
+            HttpClientError::ConnectionRefused(_) => RetryableError::ConnectionRefused,
+            HttpClientError::NetworkError(_) | HttpClientError::RequestFailed(_) => RetryableError::Network,
+            HttpClientError::DnsFailure(_) => RetryableError::Dns,
+            _ => return false,
+        };
+        self.errors.contains(&kind)
+    }
+
+    /// How long to wait after failed attempt number `attempt` (1-based)
+    ///
+    /// None when there is no attempt left, or the server asks to wait
+    /// longer than `max_delay`.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if let Some(wait) = retry_after {
+            return (wait <= self.max_delay).then_some(wait);
+        }
+        // Half of the backoff is fixed, the other half random, so clients do not retry in step
+        let backoff = self.base_delay.saturating_mul(1 << (attempt - 1).min(16)).min(self.max_delay);
+        Some(backoff / 2 + backoff.mul_f64(random_fraction() / 2.0))
+    }
+}
+
+/// One failed attempt that was retried, shown in DevTools
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryAttempt {
+    /// 1 for the first request
+    pub attempt: u32,
+    /// Status or error that made it fail, e.g. "503" or "Request timeout"
+    pub outcome: String,
+    /// Wait before the next attempt
+    pub delay: Duration,
+}
+
+/// Whether sending the request twice has the same effect as sending it once
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE)
+}
+
+/// Parse `Retry-After`, either a number of seconds or an HTTP date
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// A number in [0, 1)
+fn random_fraction() -> f64 {
+    let mut bytes = [0u8; 4];
+    if ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes).is_err() {
+        return 0.5;
+    }
+    u32::from_le_bytes(bytes) as f64 / (u32::MAX as f64 + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_retry_after() {
+        let policy = RetryPolicy { max_attempts: 4, base_delay: Duration::from_millis(100), max_delay: Duration::from_millis(300), ..Default::default() };
+
+        let first = policy.delay(1, None).unwrap();
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+        let second = policy.delay(2, None).unwrap();
+        assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+        // Capped at max_delay
+        let third = policy.delay(3, None).unwrap();
+        assert!(third >= Duration::from_millis(150) && third <= Duration::from_millis(300));
+        assert_eq!(policy.delay(4, None), None);
+
+        assert_eq!(policy.delay(1, Some(Duration::from_millis(200))), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        assert_eq!(parse_retry_after(" 120 ", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Sun, 09 Sep 2001 01:48:20 GMT", now), Some(Duration::from_secs(100)));
+        assert_eq!(parse_retry_after("Sat, 01 Jan 2000 00:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        assert!(is_idempotent(&Method::PUT));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(policy.retries_error(&HttpClientError::Timeout));
+        assert!(!policy.retries_error(&HttpClientError::DnsFailure("example.com".to_string())));
+    }
+}
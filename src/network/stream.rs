@@ -0,0 +1,127 @@
+//! Streaming response bodies for FAGA Browser
+//! Lets callers consume a body chunk by chunk and report progress
+
+use std::sync::Arc;
+use reqwest::header::HeaderMap;
+use super::cache::HttpCache;
+use super::har::{HarExchange, HarRecorder};
+use super::http_client::HttpClientError;
+use super::response::Response;
+
+/// Bytes received so far, and the total announced by Content-Length
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub received: u64,
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// Completion ratio between 0 and 1, when the total is known
+    pub fn fraction(&self) -> Option<f32> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.received as f32 / total as f32).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// A response whose headers have arrived but whose body is still being received
+#[derive(Debug)]
+pub struct ResponseStream {
+    /// Status, headers and redirects (the body is left empty)
+    pub head: Response,
+    body: Option<reqwest::Response>,
+    buffered: Option<Vec<u8>>,
+    progress: Progress,
+    /// Cache to store the response in once `collect` has read it all
+    cache: Option<(Arc<HttpCache>, HeaderMap)>,
+    /// HAR recorder to add the exchange to once the body has ended, with the body so far
+    har: Option<(Arc<HarRecorder>, HarExchange, Vec<u8>)>,
+}
+
+impl ResponseStream {
+    pub(crate) fn new(head: Response, body: reqwest::Response) -> Self {
+        let total = body.content_length();
+        Self {
+            head,
+            body: Some(body),
+            buffered: None,
+            progress: Progress { received: 0, total },
+            cache: None,
+            har: None,
+        }
+    }
+
+    /// Wrap a response that was already read completely (e.g. a cache hit)
+    pub(crate) fn buffered(mut response: Response) -> Self {
+        let body = std::mem::take(&mut response.body);
+        let total = Some(body.len() as u64);
+        Self {
+            head: response,
+            body: None,
+            buffered: Some(body),
+            progress: Progress { received: 0, total },
+            cache: None,
+            har: None,
+        }
+    }
+
+    pub(crate) fn caching(self, cache: Arc<HttpCache>, request_headers: HeaderMap) -> Self {
+        Self { cache: Some((cache, request_headers)), ..self }
+    }
+
+    pub(crate) fn recording(self, recorder: Arc<HarRecorder>, exchange: HarExchange) -> Self {
+        Self { har: Some((recorder, exchange, Vec::new())), ..self }
+    }
+
+    /// Progress of the body received so far
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    /// Receive the next chunk of the body, or `None` once it is complete
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, HttpClientError> {
+        let chunk = if let Some(buffered) = self.buffered.take() {
+            Some(buffered).filter(|b| !b.is_empty())
+        } else if let Some(body) = self.body.as_mut() {
+            body.chunk().await.map_err(HttpClientError::from_body)?.map(|bytes| bytes.to_vec())
+        } else {
+            None
+        };
+
+        match chunk {
+            Some(chunk) => {
+                self.progress.received += chunk.len() as u64;
+                if let Some((_, _, ref mut body)) = self.har {
+                    body.extend_from_slice(&chunk);
+                }
+                Ok(Some(chunk))
+            }
+            None => {
+                self.body = None;
+                if let Some((recorder, exchange, body)) = self.har.take() {
+                    recorder.record(exchange, &self.head, &body);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Read the rest of the body and return it as a response
+    ///
+    /// Chunks already taken with `chunk` are not included, and such partial
+    /// bodies are never cached.
+    pub async fn collect(mut self) -> Result<Response, HttpClientError> {
+        let whole = self.progress.received == 0;
+        let mut body = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            body.extend_from_slice(&chunk);
+        }
+        let response = Response { body, ..self.head };
+        if let (Some((cache, request_headers)), true) = (self.cache, whole) {
+            cache.store(&response.url, &request_headers, &response);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,364 @@
+//! TLS certificates for FAGA Browser
+//! Verifies server certificates with rustls and remembers, per host, the
+//! certificate chain the server presented and, per origin, the TLS version
+//! and cipher suite negotiated, for the certificate viewer. Rejected
+//! certificates can be trusted for one host.
+
+use rustls::client::{Resumption, ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, ProtocolVersion, RootCertStore, ServerName};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// TLS sessions kept for resumption
+const SESSION_CACHE_SIZE: usize = 256;
+
+/// Why a server certificate was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificateError {
+    /// Not issued by a trusted authority (self-signed, unknown CA, bad signature)
+    Untrusted,
+    Expired,
+    NotYetValid,
+    /// Issued for another host name
+    NameMismatch,
+    Revoked,
+    /// Malformed, or not meant for a TLS server
+    Invalid(String),
+}
+
+impl From<&rustls::CertificateError> for CertificateError {
+    fn from(error: &rustls::CertificateError) -> Self {
+        use rustls::CertificateError as E;
+        match error {
+            E::UnknownIssuer | E::BadSignature => Self::Untrusted,
+            E::Expired => Self::Expired,
+            E::NotValidYet => Self::NotYetValid,
+            E::NotValidForName => Self::NameMismatch,
+            E::Revoked => Self::Revoked,
+            other => Self::Invalid(format!("{:?}", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Untrusted => write!(f, "certificate is not issued by a trusted authority"),
+            Self::Expired => write!(f, "certificate has expired"),
+            Self::NotYetValid => write!(f, "certificate is not valid yet"),
+            Self::NameMismatch => write!(f, "certificate is for another host name"),
+            Self::Revoked => write!(f, "certificate has been revoked"),
+            Self::Invalid(e) => write!(f, "invalid certificate: {}", e),
+        }
+    }
+}
+
+/// The fields of an X.509 certificate shown in the certificate viewer
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateInfo {
+    /// Distinguished name, e.g. "CN=example.com, O=Example"
+    pub subject: String,
+    pub issuer: String,
+    /// Hexadecimal, bytes separated by ':'
+    pub serial: String,
+    pub not_before: SystemTime,
+    pub not_after: SystemTime,
+    /// DNS names and IP addresses the certificate is valid for
+    pub alt_names: Vec<String>,
+    /// SHA-256 of the DER encoding, bytes separated by ':'
+    pub fingerprint: String,
+}
+
+impl CertificateInfo {
+    /// Parse a DER-encoded certificate
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+        let time = |t: &x509_parser::time::ASN1Time| UNIX_EPOCH + Duration::from_secs(t.timestamp().max(0) as u64);
+
+        let alt_names = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|san| {
+                san.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        x509_parser::extensions::GeneralName::DNSName(dns) => Some(dns.to_string()),
+                        x509_parser::extensions::GeneralName::IPAddress(ip) => ip_to_string(ip),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            serial: cert.raw_serial_as_string(),
+            not_before: time(&cert.validity().not_before),
+            not_after: time(&cert.validity().not_after),
+            alt_names,
+            fingerprint: fingerprint(der),
+        })
+    }
+
+    /// The subject's common name, or the whole subject when it has none
+    pub fn name(&self) -> &str {
+        self.subject
+            .split(", ")
+            .find_map(|part| part.strip_prefix("CN="))
+            .unwrap_or(&self.subject)
+    }
+}
+
+/// What a host presented during its last TLS handshake
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SecurityInfo {
+    /// Server certificate first, then the intermediates it sent
+    pub chain: Vec<CertificateInfo>,
+    /// e.g. "TLS 1.3"
+    pub protocol: Option<String>,
+    /// e.g. "TLS13_AES_128_GCM_SHA256" or "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"
+    pub cipher: Option<String>,
+    /// Why the certificate was rejected, if it was
+    pub error: Option<CertificateError>,
+    /// Rejected, but accepted anyway because the user trusted it
+    pub overridden: bool,
+}
+
+/// The last connection made to an origin
+#[derive(Debug, Clone, Default)]
+struct Connection {
+    /// Server the pages of the origin came from, when reached directly
+    address: Option<SocketAddr>,
+    protocol: Option<String>,
+    cipher: Option<String>,
+}
+
+/// Certificates seen for each host, and the ones the user chose to trust
+#[derive(Debug, Default)]
+pub struct CertificateStore {
+    hosts: Mutex<HashMap<String, SecurityInfo>>,
+    /// "host:port" → connection parameters
+    origins: Mutex<HashMap<String, Connection>>,
+    /// Host → fingerprint of a rejected certificate trusted anyway
+    exceptions: Mutex<HashMap<String, String>>,
+}
+
+impl CertificateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What `host` presented during the last handshake, with the TLS
+    /// version and cipher suite last read for the origin on `port`
+    pub fn security_info(&self, host: &str, port: u16) -> Option<SecurityInfo> {
+        let mut info = self.hosts.lock().unwrap().get(&host.to_ascii_lowercase()).cloned()?;
+        if let Some(connection) = self.origins.lock().unwrap().get(&origin(host, port)) {
+            info.protocol = connection.protocol.clone();
+            info.cipher = connection.cipher.clone();
+        }
+        Some(info)
+    }
+
+    /// Remember the server a page of `host:port` was fetched from
+    pub(crate) fn record_address(&self, host: &str, port: u16, address: SocketAddr) {
+        self.origins.lock().unwrap().entry(origin(host, port)).or_default().address = Some(address);
+    }
+
+    /// Server a page of `host:port` was last fetched from directly
+    pub(crate) fn address(&self, host: &str, port: u16) -> Option<SocketAddr> {
+        self.origins.lock().unwrap().get(&origin(host, port)).and_then(|c| c.address)
+    }
+
+    /// Accept the certificate with this fingerprint for `host`, even though it was rejected
+    ///
+    /// Exceptions last for the session and only cover that exact certificate.
+    pub fn trust(&self, host: &str, fingerprint: &str) {
+        log::warn!("⚠️ Trusting certificate {} for {}", fingerprint, host);
+        self.exceptions.lock().unwrap().insert(host.to_ascii_lowercase(), fingerprint.to_string());
+    }
+
+    pub fn remove_exception(&self, host: &str) {
+        self.exceptions.lock().unwrap().remove(&host.to_ascii_lowercase());
+    }
+
+    fn is_trusted(&self, host: &str, fingerprint: &str) -> bool {
+        self.exceptions.lock().unwrap().get(host).is_some_and(|f| f == fingerprint)
+    }
+
+    fn record_chain(&self, host: String, chain: Vec<CertificateInfo>, error: Option<CertificateError>, overridden: bool) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let info = hosts.entry(host).or_default();
+        info.chain = chain;
+        info.error = error;
+        info.overridden = overridden;
+    }
+
+    fn record_connection(&self, host: &str, port: u16, protocol: Option<String>, cipher: Option<String>) {
+        let mut origins = self.origins.lock().unwrap();
+        let connection = origins.entry(origin(host, port)).or_default();
+        connection.protocol = protocol;
+        connection.cipher = cipher;
+    }
+}
+
+/// Build the rustls configuration used by the HTTP client
+///
+/// Servers are checked against the bundled Mozilla roots plus `extra_roots`
+/// (PEM). `alpn` lists the protocols offered, most preferred first.
+pub fn client_config(extra_roots: &[Vec<u8>], alpn: Vec<Vec<u8>>, store: Arc<CertificateStore>) -> Result<ClientConfig, String> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    for pem in extra_roots {
+        let certs = rustls_pemfile::certs(&mut &pem[..]).map_err(|e| format!("invalid root certificate: {}", e))?;
+        if certs.is_empty() {
+            return Err("invalid root certificate: no certificate in PEM".to_string());
+        }
+        for der in certs {
+            roots.add(&Certificate(der)).map_err(|e| format!("invalid root certificate: {}", e))?;
+        }
+    }
+
+    let verifier = RecordingVerifier { webpki: WebPkiVerifier::new(roots, None), store: store.clone() };
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    config.alpn_protocols = alpn;
+    config.resumption = Resumption::in_memory_sessions(SESSION_CACHE_SIZE);
+    Ok(config)
+}
+
+/// Make a TLS handshake with `host` over `stream` and record the protocol
+/// version and cipher suite the connection negotiated, for the origin on `port`
+///
+/// reqwest keeps its connections to itself, so these are read from a
+/// connection of our own, made with the client's configuration. The
+/// certificate chain is recorded by the verifier as for any other handshake.
+pub async fn inspect<S>(config: Arc<ClientConfig>, store: &CertificateStore, host: &str, port: u16, stream: S) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let server_name = ServerName::try_from(host).map_err(|e| format!("{}: {}", host, e))?;
+    let tls = tokio_rustls::TlsConnector::from(config)
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (_, connection) = tls.get_ref();
+    let protocol = connection.protocol_version().map(|version| match version {
+        ProtocolVersion::TLSv1_2 => "TLS 1.2".to_string(),
+        ProtocolVersion::TLSv1_3 => "TLS 1.3".to_string(),
+        other => format!("{:?}", other),
+    });
+    let cipher = connection.negotiated_cipher_suite().map(|suite| {
+        let suite = suite.suite();
+        suite.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", suite))
+    });
+    store.record_connection(host, port, protocol, cipher);
+    Ok(())
+}
+
+/// The usual web PKI checks, recording the chain and honouring user exceptions
+struct RecordingVerifier {
+    webpki: WebPkiVerifier,
+    store: Arc<CertificateStore>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = host_of(server_name);
+        let result = self.webpki.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now);
+        let chain = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|cert| CertificateInfo::parse(&cert.0))
+            .collect();
+
+        let error = match &result {
+            Ok(_) => None,
+            Err(rustls::Error::InvalidCertificate(e)) => Some(CertificateError::from(e)),
+            Err(e) => Some(CertificateError::Invalid(e.to_string())),
+        };
+        let overridden = error.is_some() && self.store.is_trusted(&host, &fingerprint(&end_entity.0));
+        if let Some(ref error) = error {
+            log::warn!("🔓 Certificate for {} rejected: {}{}", host, error, if overridden { " (trusted by the user)" } else { "" });
+        }
+        self.store.record_chain(host, chain, error, overridden);
+
+        if overridden {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            result
+        }
+    }
+}
+
+fn origin(host: &str, port: u16) -> String {
+    format!("{}:{}", host.to_ascii_lowercase(), port)
+}
+
+fn host_of(server_name: &ServerName) -> String {
+    match server_name {
+        ServerName::DnsName(name) => name.as_ref().to_ascii_lowercase(),
+        ServerName::IpAddress(ip) => ip.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// SHA-256 of a DER certificate, as shown by browsers
+fn fingerprint(der: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, der)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn ip_to_string(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string()),
+        16 => Some(std::net::Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_certificate() {
+        let pem = include_bytes!("../../tests/fixtures/tls/localhost.pem");
+        let der = rustls_pemfile::certs(&mut &pem[..]).unwrap().remove(0);
+        let cert = CertificateInfo::parse(&der).unwrap();
+
+        assert_eq!(cert.subject, "CN=localhost");
+        assert_eq!(cert.name(), "localhost");
+        assert_eq!(cert.issuer, "CN=FAGA Test CA");
+        assert_eq!(cert.alt_names, vec!["localhost".to_string(), "127.0.0.1".to_string()]);
+        assert!(cert.not_before < cert.not_after);
+        assert_eq!(cert.fingerprint.len(), 32 * 3 - 1);
+
+        let store = CertificateStore::new();
+        store.trust("LocalHost", &cert.fingerprint);
+        assert!(store.is_trusted("localhost", &cert.fingerprint));
+        store.remove_exception("localhost");
+        assert!(!store.is_trusted("localhost", &cert.fingerprint));
+    }
+}
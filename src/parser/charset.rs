@@ -0,0 +1,179 @@
+//! Character encoding detection for FAGA Browser
+//! Sniffs the charset of HTML and CSS bytes and decodes them to UTF-8
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// Number of bytes examined by the `<meta>` prescan (HTML spec uses 1024)
+const PRESCAN_LIMIT: usize = 1024;
+
+/// Extract the `charset` parameter of a Content-Type header value
+pub fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches(|c| c == '"' || c == '\'').as_bytes()))
+}
+
+/// Detect a byte order mark, returning the encoding it announces
+pub fn sniff_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    Encoding::for_bom(bytes).map(|(encoding, _)| encoding)
+}
+
+/// Look for `<meta charset>` or `<meta http-equiv="content-type">` in the first bytes of a document
+pub fn prescan_meta(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(PRESCAN_LIMIT)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    let mut rest = head.as_str();
+    while let Some(start) = rest.find("<meta") {
+        let tag = &rest[start + 5..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        let attrs = parse_attributes(&tag[..end]);
+        rest = &tag[end..];
+
+        let get = |name: &str| attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+
+        let label = if let Some(charset) = get("charset") {
+            Some(charset.to_string())
+        } else if get("http-equiv") == Some("content-type") {
+            get("content").and_then(|content| {
+                content
+                    .split(';')
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(name, _)| name.trim() == "charset")
+                    .map(|(_, value)| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            })
+        } else {
+            None
+        };
+
+        if let Some(encoding) = label.and_then(|l| Encoding::for_label(l.as_bytes())) {
+            // A document that could be read as ASCII cannot really be UTF-16
+            if encoding == UTF_16LE || encoding == UTF_16BE {
+                return Some(UTF_8);
+            }
+            return Some(encoding);
+        }
+    }
+
+    None
+}
+
+/// Read the `@charset "...";` rule at the very start of a stylesheet
+pub fn css_charset_rule(bytes: &[u8]) -> Option<&'static Encoding> {
+    let rest = bytes.strip_prefix(b"@charset \"")?;
+    let end = rest.iter().position(|&b| b == b'"')?;
+    if rest.get(end + 1) != Some(&b';') {
+        return None;
+    }
+
+    let encoding = Encoding::for_label(&rest[..end])?;
+    // The rule is ASCII, so UTF-16 labels are only valid alongside a BOM
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        Some(UTF_8)
+    } else {
+        Some(encoding)
+    }
+}
+
+/// Determine the encoding of an HTML document
+///
+/// Order: BOM, transport Content-Type, `<meta>` prescan, then UTF-8 if the
+/// bytes are valid UTF-8 and windows-1252 otherwise.
+pub fn detect_html_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    sniff_bom(bytes)
+        .or_else(|| content_type.and_then(charset_from_content_type))
+        .or_else(|| prescan_meta(bytes))
+        .unwrap_or_else(|| fallback_encoding(bytes))
+}
+
+/// Determine the encoding of a CSS stylesheet
+///
+/// Order: BOM, transport Content-Type, `@charset` rule, then UTF-8.
+pub fn detect_css_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    sniff_bom(bytes)
+        .or_else(|| content_type.and_then(charset_from_content_type))
+        .or_else(|| css_charset_rule(bytes))
+        .unwrap_or(UTF_8)
+}
+
+/// Decode bytes with the given encoding (a BOM, if present, takes precedence)
+pub fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        log::warn!("⚠️ Malformed {} sequences replaced while decoding", encoding.name());
+    }
+    text.into_owned()
+}
+
+fn fallback_encoding(bytes: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
+
+/// Minimal attribute tokenizer for the inside of a `<meta ...>` tag
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut chars = tag.trim_end_matches('/').chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace() || *c == '/') {
+            chars.next();
+        }
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '=')).collect();
+        if name.is_empty() {
+            break;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.peek().copied() {
+                Some(quote @ ('"' | '\'')) => {
+                    chars.next();
+                    value = std::iter::from_fn(|| chars.next_if(|c| *c != quote)).collect();
+                    chars.next();
+                }
+                _ => {
+                    value = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+                }
+            }
+        }
+
+        attrs.push((name, value));
+    }
+
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{ISO_8859_2, SHIFT_JIS};
+
+    #[test]
+    fn test_detect_html_encoding_order() {
+        let html = b"<html><head><meta charset=\"shift_jis\"></head></html>";
+        assert_eq!(detect_html_encoding(html, None), SHIFT_JIS);
+        assert_eq!(detect_html_encoding(html, Some("text/html; charset=ISO-8859-2")), ISO_8859_2);
+
+        let http_equiv = b"<meta http-equiv='Content-Type' content='text/html; charset=windows-1252'>";
+        assert_eq!(detect_html_encoding(http_equiv, None), WINDOWS_1252);
+
+        assert_eq!(detect_html_encoding(b"\xEF\xBB\xBF<p>caf\xC3\xA9</p>", Some("text/html; charset=latin1")), UTF_8);
+        assert_eq!(detect_html_encoding(b"<p>caf\xE9</p>", None), WINDOWS_1252);
+    }
+
+    #[test]
+    fn test_detect_css_encoding() {
+        assert_eq!(detect_css_encoding(b"@charset \"Shift_JIS\"; body {}", None), SHIFT_JIS);
+        assert_eq!(detect_css_encoding(b" @charset \"Shift_JIS\";", None), UTF_8);
+        assert_eq!(decode(b"p::before { content: \"\xE9\" }", WINDOWS_1252), "p::before { content: \"é\" }");
+    }
+}
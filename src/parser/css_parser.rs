@@ -485,6 +485,38 @@ impl CssParser {
         Self::parse_declarations(style)
     }
 
+    /// URLs of the font files declared by `@font-face` rules
+    ///
+    /// Only the first `url()` of each rule is returned, the preferred format
+    /// by convention. URLs are left relative to the stylesheet.
+    pub fn font_face_urls(css: &str) -> Vec<String> {
+        let lower = css.to_ascii_lowercase();
+        let mut urls = Vec::new();
+        let mut rest = 0;
+
+        while let Some(pos) = lower[rest..].find("@font-face") {
+            let start = rest + pos;
+            let block_start = match lower[start..].find('{') {
+                Some(offset) => start + offset,
+                None => break,
+            };
+            let block_end = lower[block_start..].find('}').map(|offset| block_start + offset).unwrap_or(css.len());
+            rest = block_end;
+
+            if let Some(url_pos) = lower[block_start..block_end].find("url(") {
+                let value_start = block_start + url_pos + 4;
+                if let Some(len) = css[value_start..block_end].find(')') {
+                    let url = css[value_start..value_start + len].trim().trim_matches(|c| c == '"' || c == '\'');
+                    if !url.is_empty() && !url.starts_with("data:") {
+                        urls.push(url.to_string());
+                    }
+                }
+            }
+        }
+
+        urls
+    }
+
     /// Get computed style for an element based on matching rules
     pub fn get_computed_style(
         stylesheet: &Stylesheet,
@@ -602,4 +634,15 @@ mod tests {
         assert_eq!(color.g, 0);
         assert_eq!(color.b, 0);
     }
+
+    #[test]
+    fn test_font_face_urls() {
+        let css = r#"
+            @font-face { font-family: "Inter"; src: url("/fonts/inter.woff2") format("woff2"), url(/fonts/inter.woff); }
+            body { background: url(bg.png); }
+            @FONT-FACE { font-family: Icons; src: url('icons.woff2'); }
+        "#;
+
+        assert_eq!(CssParser::font_face_urls(css), vec!["/fonts/inter.woff2", "icons.woff2"]);
+    }
 }
@@ -2,15 +2,37 @@
 //! Represents the parsed HTML document tree
 
 use std::collections::HashMap;
+use super::selector::Selector;
+
+/// A stylesheet referenced by a document
+#[derive(Debug, Clone, PartialEq)]
+pub enum StylesheetRef {
+    /// `<link rel="stylesheet">`, with its href resolved against the base URL
+    External { url: String, media: Option<String> },
+    /// Contents of a `<style>` element
+    Inline { css: String, media: Option<String> },
+}
+
+impl StylesheetRef {
+    /// Value of the `media` attribute, if any
+    pub fn media(&self) -> Option<&str> {
+        match self {
+            Self::External { media, .. } | Self::Inline { media, .. } => media.as_deref(),
+        }
+    }
+}
 
 /// Represents an HTML document
 #[derive(Debug, Clone)]
 pub struct Document {
     pub root: Option<Node>,
     pub title: String,
-    pub stylesheets: Vec<String>,
+    /// `<link rel="stylesheet">` and `<style>` elements, in document order
+    pub stylesheets: Vec<StylesheetRef>,
     pub scripts: Vec<String>,
+    pub images: Vec<String>,
     pub base_url: String,
+    pub encoding: String,
 }
 
 impl Document {
@@ -20,7 +42,9 @@ impl Document {
             title: String::new(),
             stylesheets: Vec::new(),
             scripts: Vec::new(),
+            images: Vec::new(),
             base_url: String::new(),
+            encoding: "UTF-8".to_string(),
         }
     }
 
@@ -31,7 +55,7 @@ impl Document {
 
     /// Add a stylesheet URL
     pub fn add_stylesheet(&mut self, url: &str) {
-        self.stylesheets.push(url.to_string());
+        self.stylesheets.push(StylesheetRef::External { url: url.to_string(), media: None });
     }
 
     /// Add a script URL
@@ -103,6 +127,61 @@ impl Document {
             }
         }
     }
+
+    /// Remove the elements matching any of `selectors`, with their subtree
+    ///
+    /// Returns the number of elements removed. The root element is kept.
+    pub fn remove_matching(&mut self, selectors: &[Selector]) -> usize {
+        let root = match self.root {
+            Some(Node::Element(ref mut root)) => root,
+            _ => return 0,
+        };
+        if selectors.is_empty() {
+            return 0;
+        }
+
+        let mut doomed = Vec::new();
+        Self::collect_matching(root, &mut Vec::new(), &mut Vec::new(), selectors, &mut doomed);
+
+        // Removing the last paths first keeps the indices of the others valid
+        doomed.sort();
+        for path in doomed.iter().rev() {
+            Self::remove_at(root, path);
+        }
+        doomed.len()
+    }
+
+    fn remove_at(element: &mut Element, path: &[usize]) {
+        match path {
+            [] => {}
+            [index] => {
+                if *index < element.children.len() {
+                    element.children.remove(*index);
+                }
+            }
+            [index, rest @ ..] => {
+                if let Some(Node::Element(child)) = element.children.get_mut(*index) {
+                    Self::remove_at(child, rest);
+                }
+            }
+        }
+    }
+
+    fn collect_matching<'a>(element: &'a Element, ancestors: &mut Vec<&'a Element>, path: &mut Vec<usize>, selectors: &[Selector], doomed: &mut Vec<Vec<usize>>) {
+        ancestors.push(element);
+        for (index, child) in element.children.iter().enumerate() {
+            if let Node::Element(child) = child {
+                path.push(index);
+                if selectors.iter().any(|s| s.matches(child, ancestors)) {
+                    doomed.push(path.clone());
+                } else {
+                    Self::collect_matching(child, ancestors, path, selectors, doomed);
+                }
+                path.pop();
+            }
+        }
+        ancestors.pop();
+    }
 }
 
 impl Default for Document {
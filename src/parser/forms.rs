@@ -0,0 +1,570 @@
+//! HTML forms for FAGA Browser
+//! Collects the controls of `<form>` elements from the DOM, keeps their
+//! current values while the user edits them and encodes a submission as an
+//! `application/x-www-form-urlencoded`, `multipart/form-data` or `text/plain`
+//! request.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+use super::dom::{Document, Element, Node};
+use crate::network::request::Request;
+
+/// How a form's data is encoded in the request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormEncoding {
+    UrlEncoded,
+    Multipart,
+    TextPlain,
+}
+
+impl FormEncoding {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "multipart/form-data" => Self::Multipart,
+            "text/plain" => Self::TextPlain,
+            _ => Self::UrlEncoded,
+        }
+    }
+}
+
+/// A `<form>` element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    /// Absolute action URL (the document URL when the attribute is missing)
+    pub action: String,
+    /// `GET` or `POST`
+    pub method: String,
+    pub encoding: FormEncoding,
+    id: Option<String>,
+}
+
+/// What kind of control an element is
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlKind {
+    /// Single-line text field; `input_type` keeps the original type (email, search...)
+    Text { input_type: String },
+    Password,
+    Hidden,
+    Checkbox,
+    Radio,
+    File,
+    TextArea,
+    Select { multiple: bool },
+    /// `<input type=submit>` or `<button type=submit>`
+    Submit,
+    /// `<input type=image>`, which also submits the click coordinates
+    Image,
+    Reset,
+    /// A button with no default action
+    Button,
+}
+
+/// An `<option>` of a `<select>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
+    pub selected: bool,
+}
+
+impl std::fmt::Display for SelectOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// An `<input>`, `<textarea>`, `<select>` or `<button>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormControl {
+    pub kind: ControlKind,
+    pub name: String,
+    /// Current value (for buttons, their label is `label`)
+    pub value: String,
+    pub checked: bool,
+    pub options: Vec<SelectOption>,
+    pub placeholder: String,
+    /// Text shown on buttons
+    pub label: String,
+    pub disabled: bool,
+    /// Index of the owner form in `FormSet::forms`
+    pub form: Option<usize>,
+    /// `formaction`, `formmethod` and `formenctype` of a submit button
+    overrides: [Option<String>; 3],
+    form_id: Option<String>,
+}
+
+impl FormControl {
+    /// Whether activating this control submits its form
+    pub fn is_submit_button(&self) -> bool {
+        matches!(self.kind, ControlKind::Submit | ControlKind::Image)
+    }
+}
+
+/// A value in the form data set
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Text(String),
+    /// A file input; picking files is not supported, so it is always empty
+    EmptyFile,
+}
+
+/// Every form and control of a document, with the user's edits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormSet {
+    pub forms: Vec<Form>,
+    /// Controls in document order (the renderer numbers them the same way)
+    pub controls: Vec<FormControl>,
+    /// Values from the markup, restored by reset buttons
+    defaults: Vec<FormControl>,
+    /// URL of the document the forms belong to
+    document_url: String,
+}
+
+/// Whether an element is a form control
+pub fn is_control(element: &Element) -> bool {
+    matches!(element.tag_name.as_str(), "input" | "textarea" | "select" | "button")
+}
+
+/// Number of controls inside an element, counted like `FormSet::from_document`
+pub fn count_controls(element: &Element) -> usize {
+    if is_control(element) {
+        return 1;
+    }
+    element.children
+        .iter()
+        .map(|child| match child {
+            Node::Element(child) => count_controls(child),
+            _ => 0,
+        })
+        .sum()
+}
+
+impl FormSet {
+    /// Collect the forms and controls of a parsed document
+    pub fn from_document(document: &Document) -> Self {
+        let base = Url::parse(&document.base_url).ok();
+        let mut set = Self { document_url: document.base_url.clone(), ..Self::default() };
+        if let Some(Node::Element(root)) = &document.root {
+            set.collect(root, None, base.as_ref());
+        }
+
+        // `form="id"` may point to a form further down the document
+        let ids: HashMap<&str, usize> = set.forms
+            .iter()
+            .enumerate()
+            .filter_map(|(i, form)| form.id.as_deref().map(|id| (id, i)))
+            .collect();
+        for control in &mut set.controls {
+            if let Some(id) = &control.form_id {
+                control.form = ids.get(id.as_str()).copied();
+            }
+        }
+
+        set.defaults = set.controls.clone();
+        set
+    }
+
+    fn collect(&mut self, element: &Element, form: Option<usize>, base: Option<&Url>) {
+        if is_control(element) {
+            self.controls.push(control_from_element(element, form));
+            return;
+        }
+
+        let mut form = form;
+        // Nested forms are ignored by HTML parsers, the outer one wins
+        if element.tag_name == "form" && form.is_none() {
+            let attribute = |name: &str| element.get_attribute(name).map(|v| v.trim().to_string());
+            let action = attribute("action")
+                .filter(|a| !a.is_empty())
+                .and_then(|a| base.and_then(|b| b.join(&a).ok()))
+                .or_else(|| base.cloned())
+                .map(|url| url.to_string())
+                .unwrap_or_default();
+            self.forms.push(Form {
+                action,
+                method: method_name(attribute("method").as_deref()),
+                encoding: FormEncoding::parse(&attribute("enctype").unwrap_or_default()),
+                id: attribute("id"),
+            });
+            form = Some(self.forms.len() - 1);
+        }
+
+        for child in &element.children {
+            if let Node::Element(child) = child {
+                self.collect(child, form, base);
+            }
+        }
+    }
+
+    /// Replace the text of a field
+    pub fn set_value(&mut self, control: usize, value: String) {
+        if let Some(control) = self.controls.get_mut(control) {
+            control.value = value;
+        }
+    }
+
+    /// Tick or untick a checkbox or radio button; ticking a radio button
+    /// unticks the others of its group
+    pub fn set_checked(&mut self, index: usize, checked: bool) {
+        let (kind, name, form) = match self.controls.get(index) {
+            Some(control) => (control.kind.clone(), control.name.clone(), control.form),
+            None => return,
+        };
+        if kind == ControlKind::Radio && checked && !name.is_empty() {
+            for other in &mut self.controls {
+                if other.kind == ControlKind::Radio && other.name == name && other.form == form {
+                    other.checked = false;
+                }
+            }
+        }
+        self.controls[index].checked = checked;
+    }
+
+    /// Select an option by value; in a multiple select this toggles it
+    pub fn select(&mut self, control: usize, value: &str) {
+        let control = match self.controls.get_mut(control) {
+            Some(control) => control,
+            None => return,
+        };
+        let multiple = matches!(control.kind, ControlKind::Select { multiple: true });
+        for option in &mut control.options {
+            if option.value == value {
+                option.selected = !(multiple && option.selected);
+            } else if !multiple {
+                option.selected = false;
+            }
+        }
+    }
+
+    /// Put the controls of a form back to the values from the markup
+    pub fn reset(&mut self, form: usize) {
+        for (control, default) in self.controls.iter_mut().zip(&self.defaults) {
+            if control.form == Some(form) {
+                *control = default.clone();
+            }
+        }
+    }
+
+    /// The request that submitting the form of `control` sends
+    ///
+    /// `control` is the button that was pressed, or the field in which Enter
+    /// was hit (implicit submission, no button value is sent). `None` if the
+    /// control is not in a form or is disabled.
+    pub fn submission(&self, control: usize) -> Option<Request> {
+        let trigger = self.controls.get(control)?;
+        let form_index = trigger.form?;
+        let form = self.forms.get(form_index)?;
+        if trigger.disabled {
+            return None;
+        }
+        let submitter = trigger.is_submit_button().then_some(control);
+
+        let [action, method, encoding] = submitter.map(|_| trigger.overrides.clone()).unwrap_or_default();
+        let base = Url::parse(&self.document_url).ok();
+        let action = action
+            .filter(|a| !a.is_empty())
+            .and_then(|a| base.as_ref().and_then(|b| b.join(&a).ok()).map(|u| u.to_string()))
+            .unwrap_or_else(|| form.action.clone());
+        let method = method.map(|m| method_name(Some(&m))).unwrap_or_else(|| form.method.clone());
+        let encoding = encoding.map(|e| FormEncoding::parse(&e)).unwrap_or(form.encoding);
+
+        let entries = self.entries(form_index, submitter);
+        log::info!("📝 Submitting form {} ({} fields) to {} {}", form_index, entries.len(), method, action);
+
+        let request = if method == "GET" {
+            let mut url = Url::parse(&action).ok()?;
+            let query = url_encode(&entries);
+            url.set_query(Some(&query));
+            Request::get(url.as_str())
+        } else {
+            let (body, content_type) = match encoding {
+                FormEncoding::UrlEncoded => (url_encode(&entries).into_bytes(), "application/x-www-form-urlencoded".to_string()),
+                FormEncoding::Multipart => {
+                    let boundary = new_boundary();
+                    let body = multipart_encode(&entries, &boundary);
+                    (body, format!("multipart/form-data; boundary={}", boundary))
+                }
+                FormEncoding::TextPlain => (plain_encode(&entries).into_bytes(), "text/plain; charset=utf-8".to_string()),
+            };
+            Request::post(&action, "").with_bytes(body, &content_type)
+        };
+
+        Some(request.with_referrer(&self.document_url))
+    }
+
+    /// The form data set: names and values of the successful controls, in order
+    fn entries(&self, form: usize, submitter: Option<usize>) -> Vec<(String, Entry)> {
+        let mut entries = Vec::new();
+        for (index, control) in self.controls.iter().enumerate() {
+            if control.form != Some(form) || control.disabled || control.name.is_empty() {
+                continue;
+            }
+            let name = control.name.clone();
+            match &control.kind {
+                ControlKind::Submit if submitter == Some(index) => entries.push((name, Entry::Text(control.value.clone()))),
+                // No coordinates are known, the image is considered clicked at its corner
+                ControlKind::Image if submitter == Some(index) => {
+                    entries.push((format!("{}.x", name), Entry::Text("0".to_string())));
+                    entries.push((format!("{}.y", name), Entry::Text("0".to_string())));
+                }
+                ControlKind::Submit | ControlKind::Image | ControlKind::Reset | ControlKind::Button => {}
+                ControlKind::Checkbox | ControlKind::Radio => {
+                    if control.checked {
+                        let value = if control.value.is_empty() { "on".to_string() } else { control.value.clone() };
+                        entries.push((name, Entry::Text(value)));
+                    }
+                }
+                ControlKind::Select { .. } => {
+                    for option in control.options.iter().filter(|o| o.selected) {
+                        entries.push((name.clone(), Entry::Text(option.value.clone())));
+                    }
+                }
+                ControlKind::File => entries.push((name, Entry::EmptyFile)),
+                ControlKind::TextArea => entries.push((name, Entry::Text(normalize_newlines(&control.value)))),
+                ControlKind::Text { .. } | ControlKind::Password | ControlKind::Hidden => {
+                    entries.push((name, Entry::Text(control.value.clone())));
+                }
+            }
+        }
+        entries
+    }
+}
+
+fn method_name(value: Option<&str>) -> String {
+    match value.map(|v| v.trim().to_ascii_uppercase()) {
+        Some(method) if method == "POST" => method,
+        _ => "GET".to_string(),
+    }
+}
+
+fn control_from_element(element: &Element, form: Option<usize>) -> FormControl {
+    let attribute = |name: &str| element.get_attribute(name).cloned();
+    let text = || Node::Element(element.clone()).text_content();
+
+    let kind = match element.tag_name.as_str() {
+        "textarea" => ControlKind::TextArea,
+        "select" => ControlKind::Select { multiple: element.has_attribute("multiple") },
+        "button" => match attribute("type").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "reset" => ControlKind::Reset,
+            "button" => ControlKind::Button,
+            _ => ControlKind::Submit,
+        },
+        _ => match attribute("type").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "password" => ControlKind::Password,
+            "hidden" => ControlKind::Hidden,
+            "checkbox" => ControlKind::Checkbox,
+            "radio" => ControlKind::Radio,
+            "file" => ControlKind::File,
+            "submit" => ControlKind::Submit,
+            "image" => ControlKind::Image,
+            "reset" => ControlKind::Reset,
+            "button" => ControlKind::Button,
+            "" => ControlKind::Text { input_type: "text".to_string() },
+            other => ControlKind::Text { input_type: other.to_string() },
+        },
+    };
+
+    let value = match kind {
+        // The first newline after <textarea> is not part of the value
+        ControlKind::TextArea => {
+            let content = text();
+            content.strip_prefix("\r\n").or_else(|| content.strip_prefix('\n')).unwrap_or(&content).to_string()
+        }
+        _ => attribute("value").unwrap_or_default(),
+    };
+
+    let label = match (&kind, element.tag_name.as_str()) {
+        (_, "button") => text().split_whitespace().collect::<Vec<_>>().join(" "),
+        (ControlKind::Submit | ControlKind::Image, _) => attribute("value").unwrap_or_else(|| "Envoyer".to_string()),
+        (ControlKind::Reset, _) => attribute("value").unwrap_or_else(|| "Réinitialiser".to_string()),
+        _ => attribute("value").unwrap_or_default(),
+    };
+
+    let mut options = Vec::new();
+    if let ControlKind::Select { .. } = kind {
+        collect_options(element, &mut options);
+        if !matches!(kind, ControlKind::Select { multiple: true }) {
+            // A single select shows its last selected option, or the first one
+            let selected = options.iter().rposition(|o| o.selected).unwrap_or(0);
+            for (i, option) in options.iter_mut().enumerate() {
+                option.selected = i == selected;
+            }
+        }
+    }
+
+    FormControl {
+        kind,
+        name: attribute("name").unwrap_or_default(),
+        value,
+        checked: element.has_attribute("checked"),
+        options,
+        placeholder: attribute("placeholder").unwrap_or_default(),
+        label,
+        disabled: element.has_attribute("disabled"),
+        form,
+        overrides: [attribute("formaction"), attribute("formmethod"), attribute("formenctype")],
+        form_id: attribute("form"),
+    }
+}
+
+/// `<option>` elements of a select, optgroups included
+fn collect_options(element: &Element, options: &mut Vec<SelectOption>) {
+    for child in &element.children {
+        if let Node::Element(child) = child {
+            if child.tag_name == "option" {
+                let label = Node::Element(child.clone()).text_content().split_whitespace().collect::<Vec<_>>().join(" ");
+                options.push(SelectOption {
+                    value: child.get_attribute("value").cloned().unwrap_or_else(|| label.clone()),
+                    label,
+                    selected: child.has_attribute("selected"),
+                });
+            } else {
+                collect_options(child, options);
+            }
+        }
+    }
+}
+
+fn normalize_newlines(value: &str) -> String {
+    value.replace("\r\n", "\n").replace('\r', "\n").replace('\n', "\r\n")
+}
+
+fn url_encode(entries: &[(String, Entry)]) -> String {
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (name, entry) in entries {
+        match entry {
+            Entry::Text(value) => serializer.append_pair(name, value),
+            // Only the file name is sent, and none was picked
+            Entry::EmptyFile => serializer.append_pair(name, ""),
+        };
+    }
+    serializer.finish()
+}
+
+fn plain_encode(entries: &[(String, Entry)]) -> String {
+    entries
+        .iter()
+        .map(|(name, entry)| match entry {
+            Entry::Text(value) => format!("{}={}\r\n", name, value),
+            Entry::EmptyFile => format!("{}=\r\n", name),
+        })
+        .collect()
+}
+
+fn multipart_encode(entries: &[(String, Entry)], boundary: &str) -> Vec<u8> {
+    // Names are escaped as browsers do, so a quote cannot end the header value
+    let escape = |name: &str| name.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A");
+
+    let mut body = Vec::new();
+    for (name, entry) in entries {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        match entry {
+            Entry::Text(value) => {
+                body.extend_from_slice(format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", escape(name)).as_bytes());
+                body.extend_from_slice(normalize_newlines(value).as_bytes());
+            }
+            Entry::EmptyFile => {
+                body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"; filename=\"\"\r\nContent-Type: application/octet-stream\r\n\r\n", escape(name)).as_bytes(),
+                );
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Random multipart boundary, in the same shape as other browsers'
+fn new_boundary() -> String {
+    let mut random = [0u8; 12];
+    if ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut random).is_err() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+        random.copy_from_slice(&nanos.to_le_bytes()[..12]);
+    }
+    let hex: String = random.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("----FagaFormBoundary{}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::HtmlParser;
+
+    const PAGE: &str = r#"<html><body>
+        <form action="/search" id="search">
+            <input name="q" value="rust lang">
+            <input type="hidden" name="src" value="faga">
+            <input type="checkbox" name="safe" checked>
+            <input type="radio" name="lang" value="fr" checked>
+            <input type="radio" name="lang" value="en">
+            <select name="sort"><option value="date">Date</option><option selected>Pertinence</option></select>
+            <button name="go" value="1">Chercher</button>
+            <input type="submit" name="lucky" value="J'ai de la chance">
+        </form>
+        <input name="page" value="2" form="search">
+        <form method="post" enctype="multipart/form-data" action="https://upload.example.com/send">
+            <textarea name="msg">
+a "quoted"
+line</textarea>
+            <input type="file" name="doc">
+            <input name="off" value="x" disabled>
+        </form>
+    </body></html>"#;
+
+    fn forms() -> FormSet {
+        let document = HtmlParser::parse(PAGE, "https://example.com/index.html").unwrap();
+        FormSet::from_document(&document)
+    }
+
+    #[test]
+    fn test_get_submission_is_url_encoded() {
+        let mut forms = forms();
+        assert_eq!(forms.forms.len(), 2);
+        assert_eq!(forms.controls.len(), 12);
+        assert_eq!(forms.controls[6].label, "Chercher");
+        assert_eq!(forms.controls[8].form, Some(0));
+
+        // Enter in the text field: no button value is sent
+        let request = forms.submission(0).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://example.com/search?q=rust+lang&src=faga&safe=on&lang=fr&sort=Pertinence&page=2");
+        assert!(request.body.is_none());
+
+        forms.set_value(0, "é & co".to_string());
+        forms.set_checked(2, false);
+        forms.set_checked(4, true);
+        forms.select(5, "date");
+        let request = forms.submission(7).unwrap();
+        assert_eq!(
+            request.url,
+            "https://example.com/search?q=%C3%A9+%26+co&src=faga&lang=en&sort=date&lucky=J%27ai+de+la+chance&page=2"
+        );
+
+        forms.reset(0);
+        assert_eq!(forms.controls[0].value, "rust lang");
+        assert!(forms.controls[3].checked && !forms.controls[4].checked);
+    }
+
+    #[test]
+    fn test_post_submission_is_multipart() {
+        let forms = forms();
+        let request = forms.submission(9).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.url, "https://upload.example.com/send");
+
+        let content_type = &request.headers["Content-Type"];
+        let boundary = content_type.strip_prefix("multipart/form-data; boundary=").unwrap();
+        let body = String::from_utf8(request.body.unwrap()).unwrap();
+        assert_eq!(
+            body,
+            format!(
+                "--{b}\r\nContent-Disposition: form-data; name=\"msg\"\r\n\r\na \"quoted\"\r\nline\r\n\
+                 --{b}\r\nContent-Disposition: form-data; name=\"doc\"; filename=\"\"\r\nContent-Type: application/octet-stream\r\n\r\n\r\n\
+                 --{b}--\r\n",
+                b = boundary
+            )
+        );
+    }
+}
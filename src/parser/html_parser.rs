@@ -2,7 +2,8 @@
 //! Parses HTML content into a DOM tree structure
 
 use scraper::{Html, Selector, ElementRef};
-use super::dom::{Document, Element, Node};
+use super::dom::{Document, Element, Node, StylesheetRef};
+use super::charset;
 
 /// HTML Parser using scraper crate
 pub struct HtmlParser;
@@ -30,6 +31,10 @@ impl HtmlParser {
         document.scripts = Self::extract_scripts(&parsed, base_url);
         log::debug!("📜 Found {} scripts", document.scripts.len());
 
+        // Extract images
+        document.images = Self::extract_images(&parsed, base_url);
+        log::debug!("🖼️ Found {} images", document.images.len());
+
         // Build DOM tree from body
         if let Ok(body_selector) = Selector::parse("body") {
             if let Some(body) = parsed.select(&body_selector).next() {
@@ -50,33 +55,49 @@ impl HtmlParser {
         Ok(document)
     }
 
+    /// Parse raw HTML bytes, sniffing the character encoding first
+    pub fn parse_bytes(bytes: &[u8], content_type: Option<&str>, base_url: &str) -> Result<Document, HtmlParseError> {
+        let encoding = charset::detect_html_encoding(bytes, content_type);
+        log::debug!("🔤 Document encoding: {}", encoding.name());
+
+        let html = charset::decode(bytes, encoding);
+        let mut document = Self::parse(&html, base_url)?;
+        document.encoding = encoding.name().to_string();
+        Ok(document)
+    }
+
     /// Extract document title
     fn extract_title(html: &Html) -> Option<String> {
         let selector = Selector::parse("title").ok()?;
         html.select(&selector).next().map(|el| el.text().collect::<String>())
     }
 
-    /// Extract stylesheet URLs
-    fn extract_stylesheets(html: &Html, base_url: &str) -> Vec<String> {
+    /// Extract `<link rel="stylesheet">` and `<style>` elements in document order
+    fn extract_stylesheets(html: &Html, base_url: &str) -> Vec<StylesheetRef> {
         let mut stylesheets = Vec::new();
 
-        // External stylesheets via <link>
-        if let Ok(selector) = Selector::parse("link[rel='stylesheet'], link[rel='Stylesheet']") {
-            for link in html.select(&selector) {
-                if let Some(href) = link.value().attr("href") {
-                    let url = Self::resolve_url(href, base_url);
-                    stylesheets.push(url);
+        if let Ok(selector) = Selector::parse("link[rel], style") {
+            for element in html.select(&selector) {
+                let media = element.value().attr("media").map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+
+                if element.value().name() == "style" {
+                    let css = element.text().collect::<String>();
+                    if !css.trim().is_empty() {
+                        stylesheets.push(StylesheetRef::Inline { css, media });
+                    }
+                    continue;
                 }
-            }
-        }
 
-        // Inline styles via <style>
-        if let Ok(selector) = Selector::parse("style") {
-            for style in html.select(&selector) {
-                let css = style.text().collect::<String>();
-                if !css.trim().is_empty() {
-                    // Mark as inline with special prefix
-                    stylesheets.push(format!("inline:{}", css));
+                // rel is a space-separated, case-insensitive token list; alternate sheets are not applied
+                let rel = element.value().attr("rel").unwrap_or("").to_ascii_lowercase();
+                let tokens: Vec<&str> = rel.split_ascii_whitespace().collect();
+                if !tokens.contains(&"stylesheet") || tokens.contains(&"alternate") {
+                    continue;
+                }
+
+                if let Some(href) = element.value().attr("href").filter(|h| !h.trim().is_empty()) {
+                    let url = Self::resolve_url(href.trim(), base_url);
+                    stylesheets.push(StylesheetRef::External { url, media });
                 }
             }
         }
@@ -100,6 +121,21 @@ impl HtmlParser {
         scripts
     }
 
+    /// Extract image URLs from `<img src>`
+    fn extract_images(html: &Html, base_url: &str) -> Vec<String> {
+        let mut images = Vec::new();
+
+        if let Ok(selector) = Selector::parse("img[src]") {
+            for img in html.select(&selector) {
+                if let Some(src) = img.value().attr("src").map(str::trim).filter(|s| !s.is_empty()) {
+                    images.push(Self::resolve_url(src, base_url));
+                }
+            }
+        }
+
+        images
+    }
+
     /// Convert a scraper ElementRef to our Node structure
     fn element_to_node(element: ElementRef) -> Node {
         let tag_name = element.value().name().to_string();
@@ -135,27 +171,12 @@ impl HtmlParser {
     }
 
     /// Resolve a relative URL against a base URL
-    fn resolve_url(href: &str, base_url: &str) -> String {
-        if href.starts_with("http://") || href.starts_with("https://") || href.starts_with("//") {
-            if href.starts_with("//") {
-                format!("https:{}", href)
-            } else {
-                href.to_string()
-            }
-        } else if href.starts_with('/') {
-            // Absolute path
-            if let Ok(base) = url::Url::parse(base_url) {
-                format!("{}://{}{}", base.scheme(), base.host_str().unwrap_or(""), href)
-            } else {
-                href.to_string()
-            }
-        } else {
-            // Relative path
-            if let Ok(base) = url::Url::parse(base_url) {
-                base.join(href).map(|u| u.to_string()).unwrap_or_else(|_| href.to_string())
-            } else {
-                href.to_string()
-            }
+    pub fn resolve_url(href: &str, base_url: &str) -> String {
+        match url::Url::parse(base_url).and_then(|base| base.join(href)) {
+            Ok(url) => url.to_string(),
+            // Without a usable base, scheme-relative URLs go over HTTPS
+            Err(_) if href.starts_with("//") => format!("https:{}", href),
+            Err(_) => href.to_string(),
         }
     }
 
@@ -264,4 +285,43 @@ mod tests {
         assert!(text.contains("Title"));
         assert!(text.contains("Paragraph"));
     }
+
+    #[test]
+    fn test_parse_bytes_latin1() {
+        let html = b"<html><head><meta charset=\"iso-8859-1\"><title>Caf\xE9</title></head><body></body></html>";
+
+        let doc = HtmlParser::parse_bytes(html, Some("text/html"), "https://example.com").unwrap();
+        assert_eq!(doc.title, "Café");
+        assert_eq!(doc.encoding, "windows-1252");
+    }
+
+    #[test]
+    fn test_resolve_url_keeps_the_origin() {
+        let base = "http://user@127.0.0.1:8080/dir/page";
+        assert_eq!(HtmlParser::resolve_url("/site.css", base), "http://user@127.0.0.1:8080/site.css");
+        assert_eq!(HtmlParser::resolve_url("x.png", base), "http://user@127.0.0.1:8080/dir/x.png");
+        assert_eq!(HtmlParser::resolve_url("//cdn.test/a.js", base), "http://cdn.test/a.js");
+        assert_eq!(HtmlParser::resolve_url("https://other.test/", base), "https://other.test/");
+        assert_eq!(HtmlParser::resolve_url("//cdn.test/a.js", "about:blank"), "https://cdn.test/a.js");
+    }
+
+    #[test]
+    fn test_stylesheets_in_document_order() {
+        let html = r#"
+            <html><head>
+                <style>body { color: red; }</style>
+                <link rel="Stylesheet" href="/main.css">
+                <link rel="alternate stylesheet" href="/dark.css">
+                <link rel="icon" href="/favicon.ico">
+                <link rel="stylesheet" href="print.css" media="print">
+            </head><body></body></html>
+        "#;
+
+        let doc = HtmlParser::parse(html, "https://example.com/blog/post").unwrap();
+        assert_eq!(doc.stylesheets, vec![
+            StylesheetRef::Inline { css: "body { color: red; }".to_string(), media: None },
+            StylesheetRef::External { url: "https://example.com/main.css".to_string(), media: None },
+            StylesheetRef::External { url: "https://example.com/blog/print.css".to_string(), media: Some("print".to_string()) },
+        ]);
+    }
 }
@@ -0,0 +1,108 @@
+//! Media query evaluation for FAGA Browser
+//! Decides whether a `media` attribute applies to the current screen
+
+/// Font size used to resolve `em`/`rem` lengths in media queries
+const MEDIA_FONT_SIZE: f32 = 16.0;
+
+/// Check a media query list (e.g. `screen and (min-width: 600px), print`)
+///
+/// An empty list matches everything; a list matches when any query does.
+pub fn media_matches(media: &str, viewport_width: f32, viewport_height: f32) -> bool {
+    let media = media.trim();
+    if media.is_empty() {
+        return true;
+    }
+
+    media
+        .to_ascii_lowercase()
+        .split(',')
+        .any(|query| query_matches(query.trim(), viewport_width, viewport_height))
+}
+
+/// Evaluate a single media query
+fn query_matches(query: &str, viewport_width: f32, viewport_height: f32) -> bool {
+    let (negated, query) = match query.strip_prefix("not ") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, query.strip_prefix("only ").map(str::trim).unwrap_or(query)),
+    };
+
+    let mut matched = true;
+    for part in query.split(" and ") {
+        let part = part.trim();
+        let ok = if let Some(feature) = part.strip_prefix('(').and_then(|p| p.strip_suffix(')')) {
+            feature_matches(feature, viewport_width, viewport_height)
+        } else {
+            match part {
+                "all" | "screen" => Some(true),
+                "print" | "speech" | "tty" | "tv" | "projection" | "handheld" | "braille" | "embossed" | "aural" => Some(false),
+                _ => None,
+            }
+        };
+
+        match ok {
+            Some(ok) => matched &= ok,
+            // An unknown type or feature makes the whole query "not all", even when negated
+            None => return false,
+        }
+    }
+
+    matched != negated
+}
+
+/// Evaluate a `(feature: value)` expression, `None` if it is not understood
+fn feature_matches(feature: &str, viewport_width: f32, viewport_height: f32) -> Option<bool> {
+    let (name, value) = match feature.split_once(':') {
+        Some((name, value)) => (name.trim(), Some(value.trim())),
+        None => (feature.trim(), None),
+    };
+
+    match (name, value) {
+        ("width", Some(v)) => Some((viewport_width - length(v)?).abs() < 0.5),
+        ("min-width", Some(v)) => Some(viewport_width >= length(v)?),
+        ("max-width", Some(v)) => Some(viewport_width <= length(v)?),
+        ("height", Some(v)) => Some((viewport_height - length(v)?).abs() < 0.5),
+        ("min-height", Some(v)) => Some(viewport_height >= length(v)?),
+        ("max-height", Some(v)) => Some(viewport_height <= length(v)?),
+        ("orientation", Some("portrait")) => Some(viewport_height >= viewport_width),
+        ("orientation", Some("landscape")) => Some(viewport_width > viewport_height),
+        ("prefers-color-scheme", Some(scheme)) => Some(scheme == "light"),
+        ("prefers-reduced-motion", Some(v)) => Some(v == "no-preference"),
+        ("hover", Some(v)) | ("any-hover", Some(v)) => Some(v == "hover"),
+        ("pointer", Some(v)) | ("any-pointer", Some(v)) => Some(v == "fine"),
+        ("color", None) => Some(true),
+        ("width" | "height" | "hover" | "pointer", None) => Some(true),
+        _ => None,
+    }
+}
+
+/// Parse a media query length in CSS pixels
+fn length(value: &str) -> Option<f32> {
+    let split = value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len());
+    let number: f32 = value[..split].trim().parse().ok()?;
+
+    match &value[split..] {
+        "px" => Some(number),
+        "em" | "rem" => Some(number * MEDIA_FONT_SIZE),
+        "pt" => Some(number * 96.0 / 72.0),
+        "" if number == 0.0 => Some(0.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_matches() {
+        assert!(media_matches("", 1200.0, 800.0));
+        assert!(media_matches("all", 1200.0, 800.0));
+        assert!(media_matches("screen and (min-width: 768px)", 1200.0, 800.0));
+        assert!(!media_matches("screen and (max-width: 40em)", 1200.0, 800.0));
+        assert!(!media_matches("print", 1200.0, 800.0));
+        assert!(media_matches("print, (orientation: landscape)", 1200.0, 800.0));
+        assert!(media_matches("not print", 1200.0, 800.0));
+        assert!(!media_matches("not screen and (foo: bar)", 1200.0, 800.0));
+        assert!(media_matches("only screen and (min-width:600px) and (max-width:1300px)", 1200.0, 800.0));
+    }
+}
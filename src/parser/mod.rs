@@ -1,7 +1,11 @@
+pub mod charset;
 pub mod html_parser;
 pub mod css_parser;
 pub mod dom;
+pub mod forms;
+pub mod media;
 pub mod renderer;
+pub mod selector;
 
 pub use html_parser::HtmlParser;
 pub use renderer::{HtmlRenderer, StyledText, flatten_render_tree_with_body};
@@ -4,6 +4,7 @@
 use std::collections::HashMap;
 use std::fs;
 use super::dom::{Document, Node, Element};
+use super::forms;
 use super::css_parser::{CssParser, CssValue, Stylesheet};
 
 /// Load the default CSS from the assets folder
@@ -47,6 +48,10 @@ pub struct RenderNode {
     pub text: String,
     pub tag: String, // Tag name for identification (e.g., "body", "div")
     pub href: Option<String>,
+    /// Index of the form control (input, select...) in the page's `FormSet`
+    pub control: Option<usize>,
+    /// `src` of an `<img>`, drawn by the interface once loaded (`text` holds its alt text)
+    pub image: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -190,10 +195,12 @@ impl HtmlRenderer {
     }
 
     pub fn render(&self, document: &Document) -> Option<RenderNode> {
-        document.root.as_ref().map(|root| self.render_node(root, &ComputedStyles::default()))
+        // Les contrôles de formulaire sont numérotés dans l'ordre du document
+        let mut controls = 0;
+        document.root.as_ref().map(|root| self.render_node(root, &ComputedStyles::default(), &mut controls))
     }
 
-    fn render_node(&self, node: &Node, parent_styles: &ComputedStyles) -> RenderNode {
+    fn render_node(&self, node: &Node, parent_styles: &ComputedStyles, controls: &mut usize) -> RenderNode {
         match node {
             Node::Text(text) => RenderNode {
                 node_type: RenderNodeType::Text,
@@ -202,6 +209,8 @@ impl HtmlRenderer {
                 text: text.clone(),
                 tag: String::new(),
                 href: None,
+                control: None,
+                image: None,
             },
             Node::Comment(_) => RenderNode {
                 node_type: RenderNodeType::Hidden,
@@ -210,12 +219,14 @@ impl HtmlRenderer {
                 text: String::new(),
                 tag: String::new(),
                 href: None,
+                control: None,
+                image: None,
             },
-            Node::Element(elem) => self.render_element(elem, parent_styles),
+            Node::Element(elem) => self.render_element(elem, parent_styles, controls),
         }
     }
 
-    fn render_element(&self, elem: &Element, parent_styles: &ComputedStyles) -> RenderNode {
+    fn render_element(&self, elem: &Element, parent_styles: &ComputedStyles, controls: &mut usize) -> RenderNode {
         let styles = self.compute_styles(elem, parent_styles);
         let node_type = self.determine_node_type(&elem.tag_name, &styles);
         let tag = elem.tag_name.to_lowercase();
@@ -227,7 +238,11 @@ impl HtmlRenderer {
             None
         };
 
-        if matches!(node_type, RenderNodeType::Hidden) {
+        let hidden_input = tag == "input"
+            && elem.get_attribute("type").is_some_and(|t| t.eq_ignore_ascii_case("hidden"));
+        if matches!(node_type, RenderNodeType::Hidden) || hidden_input {
+            // Les contrôles masqués gardent leur numéro (ils sont quand même envoyés)
+            *controls += forms::count_controls(elem);
             return RenderNode {
                 node_type: RenderNodeType::Hidden,
                 styles,
@@ -235,16 +250,32 @@ impl HtmlRenderer {
                 text: String::new(),
                 tag,
                 href: None,
+                control: None,
+                image: None,
             };
         }
 
+        // Un contrôle de formulaire est dessiné par l'interface, sans ses enfants
+        if forms::is_control(elem) {
+            let control = Some(*controls);
+            *controls += 1;
+            return RenderNode { node_type: RenderNodeType::Inline, styles, children: Vec::new(), text: String::new(), tag, href: None, control, image: None };
+        }
+
+        // Une image est dessinée par l'interface, son texte alternatif en attendant
+        if let Some(src) = elem.get_attribute("src").filter(|src| tag == "img" && !src.trim().is_empty()) {
+            let alt = elem.get_attribute("alt").cloned().unwrap_or_default();
+            let image = Some(src.trim().to_string());
+            return RenderNode { node_type: RenderNodeType::Inline, styles, children: Vec::new(), text: alt, tag, href: None, control: None, image };
+        }
+
         let children: Vec<RenderNode> = elem.children
             .iter()
-            .map(|child| self.render_node(child, &styles))
+            .map(|child| self.render_node(child, &styles, controls))
             .filter(|n| !matches!(n.node_type, RenderNodeType::Hidden))
             .collect();
 
-        RenderNode { node_type, styles, children, text: String::new(), tag, href }
+        RenderNode { node_type, styles, children, text: String::new(), tag, href, control: None, image: None }
     }
 
     fn compute_styles(&self, elem: &Element, parent_styles: &ComputedStyles) -> ComputedStyles {
@@ -773,6 +804,32 @@ fn flatten_node(node: &RenderNode, result: &mut Vec<StyledText>, depth: usize, p
     // Si ce nœud est un lien <a>, utiliser son href, sinon utiliser celui du parent
     let current_href = node.href.as_deref().or(parent_href);
 
+    if node.control.is_some() {
+        result.push(StyledText {
+            text: String::new(),
+            styles: node.styles.clone(),
+            is_block: false,
+            depth,
+            href: None,
+            control: node.control,
+            image: None,
+        });
+        return;
+    }
+
+    if node.image.is_some() {
+        result.push(StyledText {
+            text: node.text.clone(),
+            styles: node.styles.clone(),
+            is_block: false,
+            depth,
+            href: current_href.map(|s| s.to_string()),
+            control: None,
+            image: node.image.clone(),
+        });
+        return;
+    }
+
     match node.node_type {
         RenderNodeType::Hidden => return,
         RenderNodeType::Text => {
@@ -783,6 +840,8 @@ fn flatten_node(node: &RenderNode, result: &mut Vec<StyledText>, depth: usize, p
                     is_block: false,
                     depth,
                     href: current_href.map(|s| s.to_string()),
+                    control: None,
+                    image: None,
                 });
             }
         }
@@ -794,6 +853,8 @@ fn flatten_node(node: &RenderNode, result: &mut Vec<StyledText>, depth: usize, p
                     is_block: true,
                     depth,
                     href: None,
+                    control: None,
+                    image: None,
                 });
             }
             if matches!(node.node_type, RenderNodeType::ListItem) {
@@ -803,6 +864,8 @@ fn flatten_node(node: &RenderNode, result: &mut Vec<StyledText>, depth: usize, p
                     is_block: false,
                     depth,
                     href: None,
+                    control: None,
+                    image: None,
                 });
             }
             for child in &node.children {
@@ -814,6 +877,8 @@ fn flatten_node(node: &RenderNode, result: &mut Vec<StyledText>, depth: usize, p
                 is_block: true,
                 depth,
                 href: None,
+                control: None,
+                image: None,
             });
         }
         _ => {
@@ -831,4 +896,8 @@ pub struct StyledText {
     pub is_block: bool,
     pub depth: usize,
     pub href: Option<String>,
+    /// Form control drawn at this position, see `RenderNode::control`
+    pub control: Option<usize>,
+    /// Image drawn at this position, see `RenderNode::image`
+    pub image: Option<String>,
 }
@@ -0,0 +1,313 @@
+//! CSS selector matching for FAGA Browser
+//! Supports type, `#id`, `.class` and `[attribute]` selectors joined by
+//! descendant and child combinators, which covers most element hiding rules.
+
+use super::dom::Element;
+
+/// A parsed CSS selector
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    /// Compound selectors from left to right, each with the combinator before it
+    parts: Vec<(Combinator, Compound)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<AttributeTest>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AttributeTest {
+    name: String,
+    op: AttributeOp,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeOp {
+    Exists,
+    Equals,
+    Prefix,
+    Suffix,
+    Contains,
+    /// `~=`: one of the whitespace-separated words
+    Word,
+}
+
+impl Selector {
+    /// Parse a single selector; `None` if it uses unsupported syntax
+    /// (pseudo-classes, sibling combinators, ...)
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut chars = input.trim().chars().peekable();
+        let mut parts = Vec::new();
+        let mut combinator = Combinator::Descendant;
+
+        loop {
+            let mut saw_space = false;
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+                saw_space = true;
+            }
+            match chars.peek() {
+                None => break,
+                Some('>') => {
+                    chars.next();
+                    combinator = Combinator::Child;
+                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                }
+                Some(_) if saw_space || parts.is_empty() => {}
+                Some(_) => return None,
+            }
+
+            let compound = parse_compound(&mut chars)?;
+            parts.push((combinator, compound));
+            combinator = Combinator::Descendant;
+        }
+
+        (!parts.is_empty()).then_some(Self { parts })
+    }
+
+    /// Parse a comma-separated selector list, skipping unsupported selectors
+    pub fn parse_list(input: &str) -> Vec<Self> {
+        split_list(input).into_iter().filter_map(Self::parse).collect()
+    }
+
+    /// Whether `element`, whose parents are `ancestors` (root first), matches
+    pub fn matches(&self, element: &Element, ancestors: &[&Element]) -> bool {
+        match self.parts.split_last() {
+            Some(((combinator, last), rest)) => last.matches(element) && Self::matches_ancestors(rest, *combinator, ancestors),
+            None => false,
+        }
+    }
+
+    /// Match the remaining compounds (right to left) against the ancestors
+    fn matches_ancestors(parts: &[(Combinator, Compound)], combinator: Combinator, ancestors: &[&Element]) -> bool {
+        let ((next_combinator, compound), rest) = match parts.split_last() {
+            Some(split) => split,
+            None => return true,
+        };
+        match combinator {
+            Combinator::Child => match ancestors.split_last() {
+                Some((parent, above)) => compound.matches(parent) && Self::matches_ancestors(rest, *next_combinator, above),
+                None => false,
+            },
+            Combinator::Descendant => (0..ancestors.len()).rev().any(|i| {
+                compound.matches(ancestors[i]) && Self::matches_ancestors(rest, *next_combinator, &ancestors[..i])
+            }),
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        if self.tag.as_ref().is_some_and(|tag| !element.tag_name.eq_ignore_ascii_case(tag)) {
+            return false;
+        }
+        if self.id.as_ref().is_some_and(|id| element.id() != Some(id)) {
+            return false;
+        }
+        if !self.classes.iter().all(|class| element.has_class(class)) {
+            return false;
+        }
+        self.attributes.iter().all(|test| {
+            let value = match element.get_attribute(&test.name) {
+                Some(value) => value,
+                None => return false,
+            };
+            match test.op {
+                AttributeOp::Exists => true,
+                AttributeOp::Equals => *value == test.value,
+                AttributeOp::Prefix => !test.value.is_empty() && value.starts_with(&test.value),
+                AttributeOp::Suffix => !test.value.is_empty() && value.ends_with(&test.value),
+                AttributeOp::Contains => !test.value.is_empty() && value.contains(&test.value),
+                AttributeOp::Word => value.split_whitespace().any(|word| word == test.value),
+            }
+        })
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '\\' || !c.is_ascii()
+}
+
+fn parse_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if !is_name_char(c) {
+            break;
+        }
+        chars.next();
+        if c == '\\' {
+            name.push(chars.next()?);
+        } else {
+            name.push(c);
+        }
+    }
+    (!name.is_empty()).then_some(name)
+}
+
+fn parse_compound(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Compound> {
+    let mut compound = Compound::default();
+    let mut empty = true;
+
+    match chars.peek() {
+        Some('*') => {
+            chars.next();
+            empty = false;
+        }
+        Some(&c) if is_name_char(c) => {
+            compound.tag = Some(parse_name(chars)?.to_ascii_lowercase());
+            empty = false;
+        }
+        _ => {}
+    }
+
+    loop {
+        match chars.peek() {
+            Some('#') => {
+                chars.next();
+                compound.id = Some(parse_name(chars)?);
+            }
+            Some('.') => {
+                chars.next();
+                compound.classes.push(parse_name(chars)?);
+            }
+            Some('[') => {
+                chars.next();
+                compound.attributes.push(parse_attribute(chars)?);
+            }
+            Some(c) if c.is_whitespace() || *c == '>' => break,
+            None => break,
+            Some(_) => return None,
+        }
+        empty = false;
+    }
+
+    (!empty).then_some(compound)
+}
+
+/// Parse the inside of `[...]`, the opening bracket already consumed
+fn parse_attribute(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<AttributeTest> {
+    let skip_spaces = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    };
+
+    skip_spaces(chars);
+    let name = parse_name(chars)?.to_ascii_lowercase();
+    skip_spaces(chars);
+
+    let op = match chars.next()? {
+        ']' => return Some(AttributeTest { name, op: AttributeOp::Exists, value: String::new() }),
+        '=' => AttributeOp::Equals,
+        c => {
+            let op = match c {
+                '^' => AttributeOp::Prefix,
+                '$' => AttributeOp::Suffix,
+                '*' => AttributeOp::Contains,
+                '~' => AttributeOp::Word,
+                _ => return None,
+            };
+            if chars.next()? != '=' {
+                return None;
+            }
+            op
+        }
+    };
+    skip_spaces(chars);
+
+    let value = match chars.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next()? {
+                    '\\' => value.push(chars.next()?),
+                    c if c == quote => break,
+                    c => value.push(c),
+                }
+            }
+            value
+        }
+        _ => parse_name(chars)?,
+    };
+    skip_spaces(chars);
+
+    // Case-sensitivity flags are not supported
+    (chars.next()? == ']').then_some(AttributeTest { name, op, value })
+}
+
+/// Split a selector list on the commas outside brackets and quotes
+fn split_list(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut depth, mut quote) = (0, 0, None);
+    for (i, c) in input.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('[' | '(', None) => depth += 1,
+            (']' | ')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                parts.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tag: &str, attributes: &[(&str, &str)]) -> Element {
+        let mut element = Element::new(tag);
+        for (name, value) in attributes {
+            element.set_attribute(name, value);
+        }
+        element
+    }
+
+    #[test]
+    fn test_selector_matching() {
+        let body = element("body", &[]);
+        let aside = element("aside", &[("id", "sidebar"), ("class", "col right")]);
+        let div = element("div", &[("id", "div-gpt-ad-123"), ("class", "ad-slot banner")]);
+        let link = element("a", &[("href", "https://ads.example.net/click?id=1")]);
+
+        let matches = |selector: &str, element: &Element, ancestors: &[&Element]| {
+            Selector::parse(selector).unwrap_or_else(|| panic!("{} should parse", selector)).matches(element, ancestors)
+        };
+
+        assert!(matches(".banner", &div, &[]));
+        assert!(matches("div.ad-slot.banner", &div, &[]));
+        assert!(!matches("span.banner", &div, &[]));
+        assert!(matches("#div-gpt-ad-123", &div, &[]));
+        assert!(matches("div[id^=\"div-gpt-ad\"]", &div, &[]));
+        assert!(matches("a[href*='://ads.']", &link, &[]));
+        assert!(!matches("a[href$=\".gif\"]", &link, &[]));
+        assert!(matches("#sidebar > .ad-slot", &div, &[&body, &aside]));
+        assert!(matches("body .banner", &div, &[&body, &aside]));
+        assert!(!matches("body > .banner", &div, &[&body, &aside]));
+        assert!(!matches(".right > .col .banner", &div, &[&body, &aside]));
+
+        assert_eq!(Selector::parse("div:has(> .ad)"), None);
+        assert_eq!(Selector::parse("h1 + .ad"), None);
+        assert_eq!(Selector::parse_list(".a, div[title=\"x,y\"], p::before").len(), 2);
+    }
+}